@@ -0,0 +1,203 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Internal helpers for recognising ANSI escape sequences embedded in text.
+//!
+//! Both `tutil::crayon` and `tutil::text` need to step over escape sequences
+//! without treating them as visible characters, so the scanning logic lives
+//! here rather than being duplicated in each module.
+
+/// The escape character that introduces every ANSI escape sequence.
+pub const ESC: char = '\x1b';
+
+/// A piece of a string, either visible text or a single escape sequence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment<'a> {
+    /// A run of text containing no escape sequences.
+    Text(&'a str),
+    /// A single, complete (or truncated at the end of the input) escape
+    /// sequence, including the leading `ESC`.
+    Escape(&'a str),
+}
+
+/// An iterator over the [`Segment`]s of a string.
+///
+/// [`Segment`]: enum.Segment.html
+#[derive(Debug, Clone)]
+pub struct Segments<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Segment<'a>> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let len = if self.rest.starts_with(ESC) {
+            escape_len(self.rest)
+        } else {
+            self.rest.find(ESC).unwrap_or(self.rest.len())
+        };
+
+        let (head, tail) = self.rest.split_at(len);
+        self.rest = tail;
+
+        if head.starts_with(ESC) {
+            Some(Segment::Escape(head))
+        } else {
+            Some(Segment::Text(head))
+        }
+    }
+}
+
+/// Splits a string into runs of visible text and escape sequences.
+pub fn segments<'a>(s: &'a str) -> Segments<'a> {
+    Segments { rest: s }
+}
+
+/// Returns the length in bytes of the escape sequence at the start of `s`.
+///
+/// `s` must start with `ESC`. Three forms are recognised:
+///
+/// * CSI sequences (`ESC [`), which run until a final byte in the range
+///   `0x40` to `0x7E`, such as the `m` that ends an SGR sequence.
+/// * OSC sequences (`ESC ]`), which run until either `BEL` or `ESC \`.
+/// * Any other two character sequence, such as `ESC 7`.
+///
+/// A sequence that is cut off by the end of the string consumes the rest of
+/// the string, so a lone `ESC` at the very end has a length of 1.
+pub fn escape_len(s: &str) -> usize {
+    debug_assert!(s.starts_with(ESC));
+
+    let bytes = s.as_bytes();
+
+    match bytes.get(1) {
+        Some(&b'[') => {
+            bytes[2..].iter()
+                .position(|b| (0x40..=0x7e).contains(b))
+                .map_or(s.len(), |i| i + 3)
+        }
+        Some(&b']') => {
+            let mut i = 2;
+            while i < bytes.len() {
+                match bytes[i] {
+                    0x07 => return i + 1,
+                    0x1b if bytes.get(i + 1) == Some(&b'\\') => return i + 2,
+                    _ => i += 1,
+                }
+            }
+            s.len()
+        }
+        Some(_) => 1 + s[1..].chars().next().map_or(0, char::len_utf8),
+        None => 1,
+    }
+}
+
+/// Removes escape sequences from a stream of bytes that may be split at any
+/// point, such as the buffers passed to `Write::write()`.
+///
+/// Sequences are recognised in the same way as by `escape_len()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EscapeFilter {
+    state: FilterState,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum FilterState {
+    /// Outside of any escape sequence.
+    #[default]
+    Text,
+    /// After an `ESC`.
+    Escape,
+    /// Within the remaining bytes of a UTF-8 character following an `ESC`.
+    Continuation,
+    /// Within a CSI sequence.
+    Csi,
+    /// Within an OSC sequence.
+    Osc,
+    /// After an `ESC` within an OSC sequence.
+    OscEscape,
+}
+
+impl EscapeFilter {
+    /// Appends the bytes of `input` that are not part of an escape sequence
+    /// to `output`.
+    pub fn filter(&mut self, input: &[u8], output: &mut Vec<u8>) {
+        for &byte in input {
+            self.state = match (self.state, byte) {
+                (FilterState::Continuation, 0x80..=0xbf) => FilterState::Continuation,
+                (FilterState::Text, 0x1b) | (FilterState::Continuation, 0x1b) => {
+                    FilterState::Escape
+                }
+                (FilterState::Text, _) | (FilterState::Continuation, _) => {
+                    output.push(byte);
+                    FilterState::Text
+                }
+                (FilterState::Escape, b'[') => FilterState::Csi,
+                (FilterState::Escape, b']') => FilterState::Osc,
+                (FilterState::Escape, 0x80..=0xff) => FilterState::Continuation,
+                (FilterState::Escape, _) => FilterState::Text,
+                (FilterState::Csi, 0x40..=0x7e) => FilterState::Text,
+                (FilterState::Csi, _) => FilterState::Csi,
+                (FilterState::Osc, 0x07) | (FilterState::OscEscape, b'\\') => FilterState::Text,
+                (FilterState::Osc, 0x1b) | (FilterState::OscEscape, 0x1b) => FilterState::OscEscape,
+                (FilterState::Osc, _) | (FilterState::OscEscape, _) => FilterState::Osc,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::Segment::*;
+
+    #[test]
+    fn csi_sequence() {
+        assert_eq!(escape_len("\x1b[1;31mTEST"), 7);
+    }
+
+    #[test]
+    fn osc_sequences() {
+        assert_eq!(escape_len("\x1b]0;title\x07TEST"), 10);
+        assert_eq!(escape_len("\x1b]0;title\x1b\\TEST"), 11);
+    }
+
+    #[test]
+    fn truncated_sequences() {
+        assert_eq!(escape_len("\x1b"), 1);
+        assert_eq!(escape_len("\x1b[31"), 4);
+        assert_eq!(escape_len("\x1b]8;;http://"), 12);
+    }
+
+    #[test]
+    fn split_into_segments() {
+        let parts: Vec<_> = segments("a\x1b[31mb\x1b[0m").collect();
+        assert_eq!(parts, vec![Text("a"), Escape("\x1b[31m"), Text("b"), Escape("\x1b[0m")]);
+    }
+
+    #[test]
+    fn filter_escapes() {
+        let mut filter = EscapeFilter::default();
+        let mut output = Vec::new();
+        filter.filter(b"a\x1b[1;31mb\x1b]8;;url\x1b\\c\x1b]0;t\x07d\x1b7e", &mut output);
+
+        assert_eq!(output, b"abcde");
+    }
+
+    #[test]
+    fn filter_split_escapes() {
+        let mut filter = EscapeFilter::default();
+        let mut output = Vec::new();
+
+        for part in &["a\x1b", "[3", "1mb\x1b]8;;u\x1b", "\\c"] {
+            filter.filter(part.as_bytes(), &mut output);
+        }
+
+        assert_eq!(output, b"abc");
+    }
+}
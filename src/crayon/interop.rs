@@ -0,0 +1,149 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Conversions to and from the types of the [`ansi_term`][at] crate, enabled
+//! by the `ansi_term` feature.
+//!
+//! [at]: https://crates.io/crates/ansi_term
+
+use ansi_term;
+
+use super::{Color, Style};
+
+impl Style {
+    /// Converts the `Style` into the equivalent `ansi_term::Style`.
+    ///
+    /// Every property and colour except for 'superscript' and 'subscript' has
+    /// a counterpart in `ansi_term`, so apart from those two the conversion
+    /// loses nothing and converting back with `From` results in the same
+    /// style.
+    ///
+    /// ```
+    /// # extern crate ansi_term;
+    /// # extern crate tutil;
+    /// # fn main() {
+    /// use tutil::crayon::Color;
+    /// use ansi_term::Colour;
+    ///
+    /// let style = Color::Red.bold().to_ansi_term();
+    /// assert_eq!(style, Colour::Red.bold());
+    /// # }
+    /// ```
+    pub fn to_ansi_term(&self) -> ansi_term::Style {
+        ansi_term::Style {
+            foreground: self.foreground.map(ansi_term::Colour::from),
+            background: self.background.map(ansi_term::Colour::from),
+            is_bold: self.bold,
+            is_dimmed: self.dimmed,
+            is_italic: self.italic,
+            is_underline: self.underline,
+            is_blink: self.blink,
+            is_reverse: self.reverse,
+            is_hidden: self.hidden,
+            is_strikethrough: self.strikethrough,
+        }
+    }
+}
+
+impl From<ansi_term::Style> for Style {
+    fn from(style: ansi_term::Style) -> Style {
+        Style {
+            foreground: style.foreground.map(Color::from),
+            background: style.background.map(Color::from),
+            bold: style.is_bold,
+            dimmed: style.is_dimmed,
+            italic: style.is_italic,
+            underline: style.is_underline,
+            blink: style.is_blink,
+            reverse: style.is_reverse,
+            hidden: style.is_hidden,
+            strikethrough: style.is_strikethrough,
+            ..Style::default()
+        }
+    }
+}
+
+impl From<Color> for ansi_term::Colour {
+    fn from(color: Color) -> ansi_term::Colour {
+        match color {
+            Color::Black => ansi_term::Colour::Black,
+            Color::Red => ansi_term::Colour::Red,
+            Color::Green => ansi_term::Colour::Green,
+            Color::Yellow => ansi_term::Colour::Yellow,
+            Color::Blue => ansi_term::Colour::Blue,
+            Color::Purple => ansi_term::Colour::Purple,
+            Color::Cyan => ansi_term::Colour::Cyan,
+            Color::White => ansi_term::Colour::White,
+            Color::BrightBlack => ansi_term::Colour::Fixed(8),
+            Color::BrightRed => ansi_term::Colour::Fixed(9),
+            Color::BrightGreen => ansi_term::Colour::Fixed(10),
+            Color::BrightYellow => ansi_term::Colour::Fixed(11),
+            Color::BrightBlue => ansi_term::Colour::Fixed(12),
+            Color::BrightPurple => ansi_term::Colour::Fixed(13),
+            Color::BrightCyan => ansi_term::Colour::Fixed(14),
+            Color::BrightWhite => ansi_term::Colour::Fixed(15),
+            Color::Fixed(n) => ansi_term::Colour::Fixed(n),
+            Color::Rgb(r, g, b) => ansi_term::Colour::RGB(r, g, b),
+            Color::Adaptive { .. } => color.resolved().into(),
+        }
+    }
+}
+
+impl From<ansi_term::Colour> for Color {
+    fn from(colour: ansi_term::Colour) -> Color {
+        match colour {
+            ansi_term::Colour::Black => Color::Black,
+            ansi_term::Colour::Red => Color::Red,
+            ansi_term::Colour::Green => Color::Green,
+            ansi_term::Colour::Yellow => Color::Yellow,
+            ansi_term::Colour::Blue => Color::Blue,
+            ansi_term::Colour::Purple => Color::Purple,
+            ansi_term::Colour::Cyan => Color::Cyan,
+            ansi_term::Colour::White => Color::White,
+            ansi_term::Colour::Fixed(n) => Color::Fixed(n),
+            ansi_term::Colour::RGB(r, g, b) => Color::Rgb(r, g, b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::Color::*;
+
+    #[test]
+    fn bold_red_on_blue() {
+        let style = Red.on(Blue).bold();
+        let converted = style.to_ansi_term();
+
+        assert_eq!(converted, ansi_term::Colour::Red.on(ansi_term::Colour::Blue).bold());
+        assert_eq!(Style::from(converted), style);
+    }
+
+    #[test]
+    fn every_property() {
+        let style = Style::new().bold().dimmed().italic().underline().blink().reverse().hidden()
+                                .strikethrough();
+        assert_eq!(Style::from(style.to_ansi_term()), style);
+    }
+
+    #[test]
+    fn every_kind_of_color() {
+        for &color in &[Cyan, Fixed(208), Rgb(1, 2, 3)] {
+            assert_eq!(Color::from(ansi_term::Colour::from(color)), color);
+        }
+    }
+
+    #[test]
+    fn strikethrough_kept() {
+        let style = ansi_term::Style::new().strikethrough().italic();
+        assert_eq!(Style::from(style), Style::new().italic().strikethrough());
+    }
+
+    #[test]
+    fn superscript_dropped() {
+        let style = Style::new().superscript().bold();
+        assert_eq!(Style::from(style.to_ansi_term()), Style::new().bold());
+    }
+}
@@ -0,0 +1,4015 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A module for styling terminal output.
+//!
+//! It would be fair to say that this module ~~is a rip off of~~ is *heavily*
+//! influenced by [ogham][ogham]'s [ansi_term][ansiterm] crate and
+//! [peter-murach][peter]'s [Pastel][pastel] library. However if there is
+//! something original of mine in here it would be true-colour support at the
+//! very least.
+//!
+//! # OS Support
+//!
+//! * Linux
+//! * OS X
+//! * FreeBSD
+//! * Windows
+//!
+//! Most other POSIX/*nix systems will probably work as well. On Windows,
+//! consoles that cannot interpret escape codes, such as `cmd.exe` before
+//! Windows 10, can be written to through [`windows::WindowsRenderer`].
+//!
+//! [`windows::WindowsRenderer`]: windows/struct.WindowsRenderer.html
+//!
+//! # Basic Usage
+//!
+//! The two main data structures in this module are `Style`, which holds
+//! stylistic information such as the foreground colour, the background colour
+//! and other properties such as if the text should be bold, blinking, etc. and
+//! `ANSIString`, which is string paired with a `Style`.
+//!
+//! In order to format a string, call the `paint()` method on a `Style` or
+//! `Color`. For example, here is how you get red text and blue text:
+//!
+//! ```
+//! use tutil::crayon::Color::{Red, Blue};
+//!
+//! println!("{}", Red.paint("Hello world in red!"));
+//! println!("{}", Blue.paint("Hello world in blue!"));
+//! ```
+//!
+//! It is worth noting that `paint()` does not actually return a string with the
+//! escape codes surrounding it, but instead returns a `StyledString` that has
+//! an implementation of `Display` that will return the escape codes as well as
+//! the string when formatted.
+//!
+//! In the case that you *do* want the escape codes, you can convert the
+//! `StyledString` to a string:
+//!
+//! ```
+//! use std::string::ToString;
+//! use tutil::crayon::Color::Blue;
+//!
+//! let string = Blue.paint("Hello!").to_string(); // => "\x1b[34mTEST\x1b[0m"
+//! ```
+//!
+//! # Advanced Usage
+//!
+//! For complex styling you can construct a `Style` object rather than operating
+//! directly on a `Color`:
+//!
+//! ```
+//! use tutil::crayon::Style;
+//! use tutil::crayon::Color::{Red, Blue};
+//!
+//! // Red blinking text on a black background:
+//! println!("This will be {} and this will be {}.",
+//!          Style::new().foreground(Red).bold().paint("red and bold"),
+//!          Style::new().foreground(Blue).italic().paint("blue and italic"));
+//! ```
+//!
+//! The same styling methods that you can use on a `Style` have been implemented
+//! on the `Color` struct, allowing you to skip creating an empty `Style` with
+//! `Style::new()`:
+//!
+//! ```
+//! use tutil::crayon::Color::{Red, Blue};
+//!
+//! // Red blinking text on a black background:
+//! println!("This will be {} and this will be {}.",
+//!          Red.bold().paint("red and bold"),
+//!          Blue.italic().paint("blue and italic"));
+//! ```
+//!
+//! [ogham]: https://github.com/ogham
+//! [ansiterm]: https://github.com/ogham/rust-ansi-term
+//! [peter]: https://github.com/peter-murach
+//! [pastel]: https://github.com/peter-murach/pastel
+
+use std::fmt;
+use std::error::Error;
+use std::io::{self, Write};
+use std::iter::FromIterator;
+use std::str::FromStr;
+use std::ops::{Add, Deref};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::default::Default;
+
+use unicode_width::UnicodeWidthStr;
+
+use ansi::{self, EscapeFilter, Segment};
+use screen::{self, Size, Width};
+use system::{self, Stream};
+
+use self::Color::*;
+
+#[cfg(windows)]
+pub mod windows;
+
+#[cfg(feature = "ansi_term")]
+mod interop;
+mod parse;
+
+pub use self::parse::parse_ansi;
+
+thread_local! {
+    /// Whether colour has been disabled for the current thread by
+    /// `without_color()`.
+    static COLOR_DISABLED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether colour is enabled for the whole process: 0 until it is set or
+/// detected, 1 or 2 when `set_enabled()` has turned it on or off, and 3 or 4
+/// when the environment has been found to leave it on or turn it off.
+static ENABLED: AtomicUsize = AtomicUsize::new(0);
+
+/// The current `BlinkPolicy`, stored as its index so that it can be atomic.
+static BLINK_POLICY: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether the terminal's background is dark, for resolving
+/// `Color::Adaptive`: 0 until it is detected or set, 1 for dark and 2 for
+/// light.
+static DARK_BACKGROUND: AtomicUsize = AtomicUsize::new(0);
+
+/// What to do with the blink property when a style is rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlinkPolicy {
+    /// Write the blink code as it is. This is the default.
+    Emit,
+
+    /// Leave the blink code out, keeping the rest of the style.
+    Strip,
+
+    /// Write the bold code in place of the blink code, so that the text still
+    /// stands out without blinking.
+    BoldSubstitute,
+}
+
+/// Sets what to do with the blink property when any style is rendered, for
+/// the whole process.
+///
+/// Blinking text is distracting, and harmful to some people, so this lets a
+/// program turn it off everywhere at once rather than editing each style. The
+/// policy applies to the `Display` implementation of [`StyledString`], and so
+/// to everything built on it, as well as to [`join()`] and
+/// [`Style::byte_len()`].
+///
+/// ```
+/// # tutil::crayon::set_enabled(true);
+/// use tutil::crayon::{self, BlinkPolicy};
+/// use tutil::crayon::Color::Red;
+///
+/// crayon::set_blink_policy(BlinkPolicy::BoldSubstitute);
+/// assert_eq!(Red.blink().paint("!").to_string(), "\x1b[1;31m!\x1b[0m");
+/// # crayon::set_blink_policy(BlinkPolicy::Emit);
+/// ```
+///
+/// [`StyledString`]: struct.StyledString.html
+/// [`join()`]: fn.join.html
+/// [`Style::byte_len()`]: struct.Style.html#method.byte_len
+pub fn set_blink_policy(policy: BlinkPolicy) {
+    BLINK_POLICY.store(policy as usize, Ordering::SeqCst);
+}
+
+/// Returns the policy set by [`set_blink_policy()`], which is
+/// `BlinkPolicy::Emit` unless it has been changed.
+///
+/// [`set_blink_policy()`]: fn.set_blink_policy.html
+pub fn blink_policy() -> BlinkPolicy {
+    match BLINK_POLICY.load(Ordering::SeqCst) {
+        1 => BlinkPolicy::Strip,
+        2 => BlinkPolicy::BoldSubstitute,
+        _ => BlinkPolicy::Emit,
+    }
+}
+
+/// Sets whether the terminal's background is dark for the whole process,
+/// which decides the colour that every [`Color::Adaptive`] is rendered as.
+///
+/// This overrides detection, such as when the user has chosen a theme in the
+/// program's settings or the output is not going to the terminal.
+///
+/// [`Color::Adaptive`]: enum.Color.html#variant.Adaptive
+pub fn set_dark_background(dark: bool) {
+    DARK_BACKGROUND.store(if dark { 1 } else { 2 }, Ordering::SeqCst);
+}
+
+/// Returns whether the terminal's background is dark, as set by
+/// [`set_dark_background()`] or otherwise detected with
+/// [`system::background_is_dark()`].
+///
+/// Detection can mean querying the terminal, so it only happens the first
+/// time this is called and the answer is kept for the rest of the process. A
+/// dark background is assumed if it cannot be detected.
+///
+/// [`set_dark_background()`]: fn.set_dark_background.html
+/// [`system::background_is_dark()`]: ../system/fn.background_is_dark.html
+pub fn dark_background() -> bool {
+    match DARK_BACKGROUND.load(Ordering::SeqCst) {
+        1 => true,
+        2 => false,
+        _ => {
+            let dark = system::background_is_dark().unwrap_or(true);
+            // Another thread may have set it in the meantime, which wins.
+            let _ = DARK_BACKGROUND.compare_exchange(0, if dark { 1 } else { 2 },
+                                                     Ordering::SeqCst, Ordering::SeqCst);
+            dark_background()
+        }
+    }
+}
+
+/// Returns `on_dark` if the terminal's background is dark, as given by
+/// [`dark_background()`], or `on_light` otherwise.
+///
+/// This is the counterpart of [`Color::Adaptive`] for colours that are only
+/// known at run time, such as those read from a configuration file, which
+/// cannot be borrowed for `'static`. The choice is made when this is called
+/// rather than each time the colour is rendered, so call it after any call to
+/// [`set_dark_background()`].
+///
+/// ```
+/// use tutil::crayon::{self, Color};
+///
+/// let on_dark: Color = "#c0c0c0".parse().unwrap();
+/// let on_light: Color = "fixed:238".parse().unwrap();
+///
+/// crayon::set_dark_background(false);
+/// assert_eq!(crayon::adaptive(on_dark, on_light), Color::Fixed(238));
+/// ```
+///
+/// [`dark_background()`]: fn.dark_background.html
+/// [`Color::Adaptive`]: enum.Color.html#variant.Adaptive
+/// [`set_dark_background()`]: fn.set_dark_background.html
+pub fn adaptive(on_dark: Color, on_light: Color) -> Color {
+    if dark_background() { on_dark.resolved() } else { on_light.resolved() }
+}
+
+/// Returns how many colours standard output can show, or `ColorSupport::None`
+/// if it is not a terminal, such as when it is piped to a file.
+///
+/// The level is a best guess from the environment: `COLORTERM` set to
+/// `truecolor` or `24bit` and terminals known by `TERM_PROGRAM` to support
+/// any colour give `ColorSupport::TrueColor`, a `TERM` ending in `256color`
+/// gives `ColorSupport::Ansi256`, and `TERM=dumb` gives no colour at all. This
+/// is the same level as in [`system::Capabilities`].
+///
+/// ```
+/// use tutil::crayon::{self, Color, ColorSupport};
+///
+/// let level = crayon::supports_color();
+/// crayon::set_enabled(level != ColorSupport::None);
+/// println!("{}", Color::Rgb(255, 136, 0).paint_adaptive("orange", level));
+/// ```
+///
+/// [`system::Capabilities`]: ../system/struct.Capabilities.html
+pub fn supports_color() -> ColorSupport {
+    if system::stream_is_redirected(Stream::Stdout) {
+        ColorSupport::None
+    } else {
+        system::color_support_with(system::var)
+    }
+}
+
+/// Enables or disables colour for the whole process.
+///
+/// While colour is disabled, every [`StyledString`] is displayed as plain text
+/// without any escape codes, as within [`without_color()`] but on every
+/// thread. Either way, this takes precedence over everything else that decides
+/// whether to use colour apart from [`without_color()`], including the
+/// `FORCE_COLOR` and `NO_COLOR` environment variables and whether the output
+/// is a terminal. It suits a program's `--color=always` and `--color=never`
+/// options.
+///
+/// ```
+/// use tutil::crayon::{self, Color};
+///
+/// crayon::set_enabled(false);
+/// assert_eq!(Color::Red.paint("TEST").to_string(), "TEST");
+/// # crayon::set_enabled(true);
+/// ```
+///
+/// [`StyledString`]: struct.StyledString.html
+/// [`without_color()`]: fn.without_color.html
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(if enabled { 1 } else { 2 }, Ordering::SeqCst);
+}
+
+/// Returns whether colour is enabled for the whole process, as set by
+/// [`set_enabled()`].
+///
+/// Unless it has been set, colour is enabled unless the environment turns it
+/// off: a `FORCE_COLOR` of `0` or `false` does, as does a non-empty
+/// `NO_COLOR`, following the [NO_COLOR] convention, unless `FORCE_COLOR` has
+/// some other value. The environment is only read the first time this is
+/// called.
+///
+/// [`set_enabled()`]: fn.set_enabled.html
+/// [NO_COLOR]: https://no-color.org
+pub fn enabled() -> bool {
+    match ENABLED.load(Ordering::SeqCst) {
+        1 | 3 => true,
+        2 | 4 => false,
+        _ => {
+            let detected = if enabled_with(system::var) { 3 } else { 4 };
+            // Another thread may have set it in the meantime, which wins.
+            let _ = ENABLED.compare_exchange(0, detected, Ordering::SeqCst, Ordering::SeqCst);
+            enabled()
+        }
+    }
+}
+
+/// The detection done by `enabled()` when colour has not been set, reading
+/// environment variables through `var`.
+fn enabled_with<F>(var: F) -> bool where F: Fn(&str) -> Option<String> {
+    env_colors_enabled_with(var) != Some(false)
+}
+
+/// Returns the value given to `set_enabled()`, if it has been called.
+fn explicitly_enabled() -> Option<bool> {
+    match ENABLED.load(Ordering::SeqCst) {
+        1 => Some(true),
+        2 => Some(false),
+        _ => None,
+    }
+}
+
+/// Returns whether the environment turns colour on or off, or `None` if it
+/// leaves the choice to the program.
+///
+/// `FORCE_COLOR` turns colour off if it is `0` or `false`, and on if it has any
+/// other non-empty value. Otherwise a non-empty `NO_COLOR` turns colour off.
+fn env_colors_enabled_with<F>(var: F) -> Option<bool> where F: Fn(&str) -> Option<String> {
+    match var("FORCE_COLOR").as_deref() {
+        Some("0") | Some("false") => Some(false),
+        Some(_) => Some(true),
+        None if var("NO_COLOR").is_some() => Some(false),
+        None => None,
+    }
+}
+
+/// Returns true if a [`StyledString`] will be displayed with escape codes.
+///
+/// In order of precedence, colour is turned off for the current thread by
+/// [`without_color()`], decided by [`set_enabled()`], and then decided by the
+/// environment as described for [`enabled()`].
+///
+/// [`StyledString`]: struct.StyledString.html
+/// [`set_enabled()`]: fn.set_enabled.html
+/// [`without_color()`]: fn.without_color.html
+/// [`enabled()`]: fn.enabled.html
+pub fn colors_enabled() -> bool {
+    !COLOR_DISABLED.with(|disabled| disabled.get()) && enabled()
+}
+
+/// Calls `f` with colour disabled, so that any [`StyledString`] displayed
+/// within it is written as plain text, then restores the previous state.
+///
+/// The state is restored even if `f` panics. Colour is only disabled for the
+/// current thread, so output from other threads is unaffected.
+///
+/// ```
+/// use tutil::crayon::{self, Color};
+///
+/// let plain = crayon::without_color(|| Color::Red.paint("TEST").to_string());
+/// assert_eq!(plain, "TEST");
+/// ```
+///
+/// [`StyledString`]: struct.StyledString.html
+/// [`set_enabled()`]: fn.set_enabled.html
+pub fn without_color<T, F>(f: F) -> T where F: FnOnce() -> T {
+    /// Restores the previous state when dropped, including while unwinding.
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0;
+            COLOR_DISABLED.with(|disabled| disabled.set(previous));
+        }
+    }
+
+    let _restore = Restore(COLOR_DISABLED.with(|disabled| disabled.replace(true)));
+    f()
+}
+
+/// Returns true if styled output written to `stream` should include escape
+/// codes.
+///
+/// Each stream is decided separately, so colour can be written to standard
+/// error while standard output is piped to another program. In order of
+/// precedence:
+///
+/// * Colour is off inside [`without_color()`].
+/// * [`set_enabled()`] turns colour on or off.
+/// * `FORCE_COLOR` turns colour off if it is `0` or `false`, and on if it has
+///   any other non-empty value.
+/// * `NO_COLOR` with any non-empty value turns colour off.
+/// * Otherwise colour is on if `stream` is a terminal.
+///
+/// ```
+/// use tutil::crayon;
+/// use tutil::system::Stream;
+///
+/// if crayon::colors_enabled_for(Stream::Stderr) {
+///     eprintln!("Colour will be used for errors");
+/// }
+/// ```
+///
+/// [`set_enabled()`]: fn.set_enabled.html
+/// [`without_color()`]: fn.without_color.html
+pub fn colors_enabled_for(stream: Stream) -> bool {
+    colors_enabled_for_with(stream, explicitly_enabled(), system::var,
+                            |stream| !system::stream_is_redirected(stream))
+}
+
+/// The implementation of `colors_enabled_for()`, given the value passed to
+/// `set_enabled()`, reading environment variables through `var` and asking
+/// `is_terminal` whether a stream is a terminal.
+fn colors_enabled_for_with<F, T>(stream: Stream, set: Option<bool>, var: F, is_terminal: T)
+    -> bool
+    where F: Fn(&str) -> Option<String>,
+          T: Fn(Stream) -> bool
+{
+    if COLOR_DISABLED.with(|disabled| disabled.get()) {
+        return false;
+    }
+
+    if let Some(enabled) = set {
+        return enabled;
+    }
+
+    env_colors_enabled_with(var).unwrap_or_else(|| is_terminal(stream))
+}
+
+/// Writes `value` to standard output, keeping escape codes only if
+/// [`colors_enabled_for(Stream::Stdout)`] is true.
+///
+/// No newline is added, just like `print!()`.
+///
+/// ```
+/// use tutil::crayon::{self, Color::Green};
+///
+/// crayon::print_styled(format!("{}\n", Green.paint("Done"))).unwrap();
+/// ```
+///
+/// [`colors_enabled_for(Stream::Stdout)`]: fn.colors_enabled_for.html
+pub fn print_styled<D: fmt::Display>(value: D) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut writer = ColorWriter::new(stdout.lock(), colors_enabled_for(Stream::Stdout));
+    try!(write!(writer, "{}", value));
+    writer.flush()
+}
+
+/// Writes `value` to standard error, keeping escape codes only if
+/// [`colors_enabled_for(Stream::Stderr)`] is true.
+///
+/// No newline is added, just like `eprint!()`.
+///
+/// [`colors_enabled_for(Stream::Stderr)`]: fn.colors_enabled_for.html
+pub fn eprint_styled<D: fmt::Display>(value: D) -> io::Result<()> {
+    let stderr = io::stderr();
+    let mut writer = ColorWriter::new(stderr.lock(), colors_enabled_for(Stream::Stderr));
+    try!(write!(writer, "{}", value));
+    writer.flush()
+}
+
+/// How much a program has been asked to say, such as by `-v` flags, for
+/// printing messages only at certain levels.
+///
+/// ```
+/// use tutil::crayon::Verbosity;
+/// use tutil::crayon::Color::Fixed;
+///
+/// let verbosity = Verbosity::new(1);
+///
+/// // Only printed with `-vv`.
+/// verbosity.vprintln(2, &Fixed(244).paint("reading config")).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Verbosity {
+    level: u8,
+}
+
+impl Verbosity {
+    /// Creates a `Verbosity` at `level`, where 0 is the default and each `-v`
+    /// flag usually adds one.
+    pub fn new(level: u8) -> Verbosity {
+        Verbosity { level }
+    }
+
+    /// Returns the level that the `Verbosity` was created with.
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Returns true if messages that need `required` are printed.
+    pub fn enabled(&self, required: u8) -> bool {
+        self.level >= required
+    }
+
+    /// Prints `s` and a newline to standard output with [`print_styled()`],
+    /// but only if the level is at least `required`.
+    ///
+    /// [`print_styled()`]: fn.print_styled.html
+    pub fn vprintln(&self, required: u8, s: &StyledString) -> io::Result<()> {
+        if self.enabled(required) {
+            print_styled(format_args!("{}\n", s))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Writes `s` and a newline to `writer`, but only if the level is at
+    /// least `required`.
+    ///
+    /// Escape codes are always written, so wrap `writer` in a
+    /// [`ColorWriter`] to decide whether it gets colour.
+    ///
+    /// [`ColorWriter`]: struct.ColorWriter.html
+    pub fn vwriteln<W: Write>(&self, writer: &mut W, required: u8, s: &StyledString)
+                              -> io::Result<()> {
+        if self.enabled(required) {
+            writeln!(writer, "{}", s)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Returns the number of columns that `s` occupies when displayed in a
+/// terminal.
+///
+/// Escape sequences, such as colour codes and OSC hyperlinks, take up no
+/// columns, and wide characters, such as most CJK characters, take up two. No
+/// allocations are made, unlike stripping the escape sequences first.
+///
+/// ```
+/// use tutil::crayon::{self, Color};
+///
+/// let styled = Color::Red.paint("hello").to_string();
+/// assert_eq!(crayon::visible_width(&styled), 5);
+/// ```
+pub fn visible_width(s: &str) -> usize {
+    ansi::segments(s)
+        .map(|segment| match segment {
+            Segment::Text(text) => text.width(),
+            Segment::Escape(_) => 0,
+        })
+        .sum()
+}
+
+/// Removes every escape sequence from `input`, including OSC sequences such as
+/// hyperlinks and window titles, leaving only the visible text.
+///
+/// The input is borrowed rather than copied if it contains no escape
+/// sequences.
+///
+/// ```
+/// use tutil::crayon::{self, Color};
+///
+/// let styled = Color::Red.paint("hello").to_string();
+/// assert_eq!(crayon::strip_ansi(&styled), "hello");
+/// ```
+pub fn strip_ansi(input: &str) -> Cow<'_, str> {
+    strip_escapes(input, |_| true)
+}
+
+/// Removes OSC sequences from `input`, such as those that set the window title
+/// or wrap text in a hyperlink, while keeping other escape sequences such as
+/// colour codes.
+///
+/// OSC sequences start with `ESC ]` and may end with either `BEL` (`\x07`) or
+/// the string terminator `ESC \`, both of which are recognised. The text of a
+/// hyperlink is not part of the sequence, so it is kept.
+///
+/// ```
+/// use tutil::crayon;
+///
+/// let link = "\x1b]8;;https://example.com\x1b\\example\x1b]8;;\x1b\\";
+/// assert_eq!(crayon::strip_osc(link), "example");
+/// ```
+pub fn strip_osc(input: &str) -> Cow<'_, str> {
+    strip_escapes(input, |escape| escape.starts_with("\x1b]"))
+}
+
+/// Removes SGR sequences from `input`, such as those that set colours and
+/// styles, while keeping other escape sequences such as hyperlinks.
+///
+/// SGR sequences are the CSI sequences that end with `m`, such as `\x1b[31m`.
+/// Use [`strip_ansi()`](fn.strip_ansi.html) to remove every escape sequence.
+///
+/// ```
+/// use tutil::crayon::{self, Color};
+///
+/// let styled = Color::Red.on(Color::Black).bold().paint("hi").to_string();
+/// assert_eq!(crayon::strip_sgr(&styled), "hi");
+/// ```
+pub fn strip_sgr(input: &str) -> Cow<'_, str> {
+    strip_escapes(input, |escape| escape.starts_with("\x1b[") && escape.ends_with('m'))
+}
+
+/// Removes the escape sequences in `input` for which `strip` returns true.
+fn strip_escapes<F>(input: &str, strip: F) -> Cow<'_, str> where F: Fn(&str) -> bool {
+    let needs_stripping = ansi::segments(input).any(|segment| match segment {
+        Segment::Escape(escape) => strip(escape),
+        Segment::Text(_) => false,
+    });
+
+    if !needs_stripping {
+        return Cow::Borrowed(input);
+    }
+
+    let mut output = String::with_capacity(input.len());
+
+    for segment in ansi::segments(input) {
+        match segment {
+            Segment::Escape(escape) if strip(escape) => {}
+            Segment::Escape(text) | Segment::Text(text) => output.push_str(text),
+        }
+    }
+
+    Cow::Owned(output)
+}
+
+/// Renders a chart of the 256-colour palette, with each colour's number shown
+/// on a background of that colour.
+///
+/// The chart is laid out in the same way as xterm's: the eight standard
+/// colours, the eight bright colours, the 6×6×6 colour cube and then the
+/// greyscale ramp. This is useful for choosing `Fixed` colours that look
+/// right in the user's own terminal.
+///
+/// ```
+/// use tutil::crayon;
+///
+/// println!("{}", crayon::swatch_256());
+/// ```
+pub fn swatch_256() -> String {
+    let cell = |n: u8| {
+        let color = Fixed(n);
+        color.readable_foreground().on(color).paint(format!("{:>4}", n)).to_string()
+    };
+
+    let row = |colors: &mut dyn Iterator<Item = u8>| colors.map(cell).collect::<String>();
+
+    let mut sections = vec![format!("{}\n{}", row(&mut (0..8)), row(&mut (8..16)))];
+
+    // The cube is drawn as two rows of three 6×6 blocks, with red increasing
+    // from block to block, green down each block and blue across each row.
+    let mut cube = Vec::new();
+    for reds in [0..3, 3..6].iter() {
+        for green in 0..6 {
+            let mut colors = reds.clone()
+                .flat_map(move |red| (0..6).map(move |blue| 16 + 36 * red + 6 * green + blue));
+            cube.push(row(&mut colors));
+        }
+    }
+    sections.push(cube.join("\n"));
+
+    sections.push(format!("{}\n{}", row(&mut (232..244)), row(&mut (244..=255))));
+
+    sections.join("\n\n")
+}
+
+/// Joins styled segments into a single string, placing `separator` between
+/// each of them.
+///
+/// The separator keeps its own style, independent of the segments around it.
+/// Only the escape codes needed to change from one style to the next are
+/// written, rather than resetting after every segment, which keeps the output
+/// short when building breadcrumbs or status lines.
+///
+/// ```
+/// use tutil::crayon::{self, Color};
+///
+/// let path = [Color::Blue.paint("home"), Color::Blue.paint("docs")];
+/// let separator = Color::Fixed(244).paint(" › ");
+///
+/// println!("{}", crayon::join(&path, &separator));
+/// ```
+pub fn join(segments: &[StyledString], separator: &StyledString) -> String {
+    use std::fmt::Write;
+
+    let mut output = String::new();
+    let mut current = Style::default();
+
+    for (i, segment) in segments.iter().enumerate() {
+        if i > 0 {
+            let _ = write!(output, "{}{}", Transition(current, separator.style), separator.string);
+            current = separator.style;
+        }
+
+        let _ = write!(output, "{}{}", Transition(current, segment.style), segment.string);
+        current = segment.style;
+    }
+
+    let _ = write!(output, "{}", Transition(current, Style::default()));
+    output
+}
+
+/// Displays the escape codes needed to change from the first style to the
+/// second, or nothing if colour is disabled.
+pub(crate) struct Transition(pub Style, pub Style);
+
+impl fmt::Display for Transition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if colors_enabled() {
+            let policy = blink_policy();
+            self.1.with_blink_policy(policy).write_transition(&self.0.with_blink_policy(policy), f)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A string coupled with a [`Style`] in order to display it in a terminal.
+///
+/// It can be turned into a string with the `.to_string()` method.
+///
+/// [`Style`]: struct.Style.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyledString<'a> {
+    string: Cow<'a, str>,
+    style: Style,
+}
+
+impl<'a> StyledString<'a> {
+    /// Returns the `Style` that the string is painted with.
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    /// Renders the string centred in the width of the terminal, padded with
+    /// unstyled spaces on both sides.
+    ///
+    /// The string is measured by its visible width, so escape codes do not
+    /// affect the centring. Any odd space left over goes on the right. If the
+    /// width of the terminal cannot be determined, 80 columns is assumed, and
+    /// a string that is already too wide is returned without padding.
+    ///
+    /// ```
+    /// use tutil::crayon::Color::Blue;
+    ///
+    /// println!("{}", Blue.bold().paint("tutil").center_in_terminal());
+    /// ```
+    pub fn center_in_terminal(self) -> String {
+        let Size(Width(width), _) = screen::size_or_default();
+        self.center_in(width as usize)
+    }
+
+    /// The implementation of `center_in_terminal()`, centring the string in
+    /// `width` columns.
+    fn center_in(&self, width: usize) -> String {
+        let extra = width.saturating_sub(visible_width(&self.string));
+        let left = extra / 2;
+
+        format!("{}{}{}", " ".repeat(left), self, " ".repeat(extra - left))
+    }
+
+    /// Replaces the control characters in the string with printable
+    /// representations, so that text from an untrusted source cannot move the
+    /// cursor, change the style or send other escape sequences to the
+    /// terminal.
+    ///
+    /// ASCII control characters are shown in caret notation, such as `^[` for
+    /// `ESC` and `^G` for `BEL`, and the C1 control characters as `\xNN`. Tabs
+    /// and newlines are kept. The style of the string is unaffected, and the
+    /// string is not copied if it contains no control characters.
+    ///
+    /// ```
+    /// # tutil::crayon::set_enabled(true);
+    /// use tutil::crayon::Color::Red;
+    ///
+    /// let name = "\x1b[2Jeve";
+    /// assert_eq!(Red.paint(name).sanitized().to_string(), "\x1b[31m^[[2Jeve\x1b[0m");
+    /// ```
+    pub fn sanitized(self) -> StyledString<'a> {
+        let is_unsafe = |c: char| c.is_control() && c != '\t' && c != '\n';
+
+        if !self.string.chars().any(is_unsafe) {
+            return self;
+        }
+
+        let mut sanitized = String::with_capacity(self.string.len() + 8);
+
+        for c in self.string.chars() {
+            match c {
+                _ if !is_unsafe(c) => sanitized.push(c),
+                '\x7f' => sanitized.push_str("^?"),
+                '\x00'..='\x1f' => {
+                    sanitized.push('^');
+                    sanitized.push((c as u8 + b'@') as char);
+                }
+                _ => sanitized.push_str(&format!("\\x{:02x}", c as u32)),
+            }
+        }
+
+        StyledString { string: Cow::Owned(sanitized), style: self.style }
+    }
+
+    /// Renders the string as HTML, wrapped in a `<span>` styled with
+    /// [`Style::to_css()`].
+    ///
+    /// The text is escaped so that it is safe to include in an HTML document,
+    /// and the `<span>` is left out entirely if the style is plain.
+    ///
+    /// [`Style::to_css()`]: struct.Style.html#method.to_css
+    pub fn to_html(&self) -> String {
+        let mut text = String::with_capacity(self.string.len());
+
+        for c in self.string.chars() {
+            match c {
+                '<' => text.push_str("&lt;"),
+                '>' => text.push_str("&gt;"),
+                '&' => text.push_str("&amp;"),
+                '"' => text.push_str("&quot;"),
+                '\'' => text.push_str("&#39;"),
+                _ => text.push(c),
+            }
+        }
+
+        if self.style.is_plain() {
+            text
+        } else {
+            format!("<span style=\"{}\">{}</span>", self.style.to_css(), text)
+        }
+    }
+
+    /// Renders the string for a terminal with the given level of colour
+    /// support, downgrading its colours with [`Style::adapt()`].
+    ///
+    /// For `ColorSupport::None` the plain string is returned without any
+    /// escape codes at all.
+    ///
+    /// [`Style::adapt()`]: struct.Style.html#method.adapt
+    pub fn render_for(&self, level: ColorSupport) -> String {
+        if level == ColorSupport::None {
+            return self.string.to_string();
+        }
+
+        StyledString { string: Cow::Borrowed(&self.string), style: self.style.adapt(level) }
+            .to_string()
+    }
+
+    /// Returns the string with its colours downgraded with [`Style::adapt()`]
+    /// to those that standard output can show, as detected by
+    /// [`supports_color()`].
+    ///
+    /// ```
+    /// use tutil::crayon::Color::Rgb;
+    ///
+    /// println!("{}", Rgb(255, 128, 0).paint("orange").downgraded());
+    /// ```
+    ///
+    /// [`Style::adapt()`]: struct.Style.html#method.adapt
+    /// [`supports_color()`]: fn.supports_color.html
+    pub fn downgraded(self) -> StyledString<'a> {
+        self.downgraded_to(supports_color())
+    }
+
+    /// The implementation of `downgraded()`, for the given level of colour
+    /// support.
+    fn downgraded_to(self, level: ColorSupport) -> StyledString<'a> {
+        StyledString { style: self.style.adapt(level), ..self }
+    }
+
+    /// Returns this string followed by `other` as [`StyledText`], with each
+    /// keeping its own style.
+    ///
+    /// Unlike the free function [`join()`], there is no separator, and each
+    /// segment is written with its own escape codes and a reset.
+    ///
+    /// ```
+    /// use tutil::crayon::Color::{Blue, Red};
+    ///
+    /// # tutil::crayon::set_enabled(true);
+    /// let line = Red.paint("a").join(Blue.paint("b"));
+    /// assert_eq!(line.to_string(), "\x1b[31ma\x1b[0m\x1b[34mb\x1b[0m");
+    /// ```
+    ///
+    /// [`StyledText`]: struct.StyledText.html
+    /// [`join()`]: fn.join.html
+    pub fn join(self, other: StyledString<'a>) -> StyledText<'a> {
+        StyledText::from(vec![self, other])
+    }
+}
+
+impl<'a> fmt::Display for StyledString<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !colors_enabled() {
+            return write!(f, "{}", self.string);
+        }
+
+        let style = self.style.with_blink_policy(blink_policy());
+
+        // TODO: Convert the `try!()` calls to the `?` operator once it is
+        //       stable.
+        try!(style.write_prefix(f));
+        try!(write!(f, "{}", self.string));
+        style.write_suffix(f)
+    }
+}
+
+impl<'a, S> From<S> for StyledString<'a> where S: Into<Cow<'a, str>> {
+    fn from(string: S) -> StyledString<'a> {
+        StyledString { string: string.into(), style: Style::default() }
+    }
+}
+
+impl<'a> Deref for StyledString<'a> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.string.deref()
+    }
+}
+
+impl<'a> Add for StyledString<'a> {
+    type Output = StyledStrings<'a>;
+
+    fn add(self, other: StyledString<'a>) -> StyledStrings<'a> {
+        StyledStrings { segments: vec![self, other] }
+    }
+}
+
+impl<'a> Add<&'a str> for StyledString<'a> {
+    type Output = StyledStrings<'a>;
+
+    /// Appends plain, unstyled text.
+    fn add(self, other: &'a str) -> StyledStrings<'a> {
+        self + StyledString::from(other)
+    }
+}
+
+/// A sequence of [`StyledString`]s that are displayed one after another, such
+/// as a line made up of text in several colours.
+///
+/// Only the escape codes needed to change from one segment's style to the
+/// next are written, as with [`join()`]. To write each segment with its own
+/// codes instead, convert it into a [`StyledText`]. The easiest way to build
+/// one is by adding styled strings and plain text together:
+///
+/// ```
+/// use tutil::crayon::Color::{Green, Red};
+///
+/// let line = Green.paint("passed: 3") + ", " + Red.paint("failed: 1");
+/// println!("{}", line);
+/// ```
+///
+/// [`StyledString`]: struct.StyledString.html
+/// [`join()`]: fn.join.html
+/// [`StyledText`]: struct.StyledText.html
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StyledStrings<'a> {
+    segments: Vec<StyledString<'a>>,
+}
+
+impl<'a> StyledStrings<'a> {
+    /// Creates an empty `StyledStrings`.
+    pub fn new() -> StyledStrings<'a> {
+        StyledStrings::default()
+    }
+
+    /// Appends a segment to the end.
+    pub fn push(&mut self, segment: StyledString<'a>) {
+        self.segments.push(segment);
+    }
+
+    /// Returns the segments in order.
+    pub fn segments(&self) -> &[StyledString<'a>] {
+        &self.segments
+    }
+
+    /// Returns the text of every segment joined together, without any escape
+    /// codes.
+    pub fn unstyled(&self) -> String {
+        self.segments.iter().map(|segment| &*segment.string).collect()
+    }
+}
+
+impl<'a> fmt::Display for StyledStrings<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut current = Style::default();
+
+        for segment in &self.segments {
+            try!(write!(f, "{}{}", Transition(current, segment.style), segment.string));
+            current = segment.style;
+        }
+
+        write!(f, "{}", Transition(current, Style::default()))
+    }
+}
+
+impl<'a> Add<StyledString<'a>> for StyledStrings<'a> {
+    type Output = StyledStrings<'a>;
+
+    fn add(mut self, other: StyledString<'a>) -> StyledStrings<'a> {
+        self.segments.push(other);
+        self
+    }
+}
+
+impl<'a> Add<&'a str> for StyledStrings<'a> {
+    type Output = StyledStrings<'a>;
+
+    /// Appends plain, unstyled text.
+    fn add(self, other: &'a str) -> StyledStrings<'a> {
+        self + StyledString::from(other)
+    }
+}
+
+impl<'a> From<Vec<StyledString<'a>>> for StyledStrings<'a> {
+    fn from(segments: Vec<StyledString<'a>>) -> StyledStrings<'a> {
+        StyledStrings { segments }
+    }
+}
+
+impl<'a> FromIterator<StyledString<'a>> for StyledStrings<'a> {
+    fn from_iter<I>(iter: I) -> StyledStrings<'a> where I: IntoIterator<Item = StyledString<'a>> {
+        StyledStrings { segments: iter.into_iter().collect() }
+    }
+}
+
+/// The segments of a [`StyledStrings`], displayed so that each is written
+/// exactly as it would be on its own.
+///
+/// [`StyledStrings`] only writes the escape codes needed to change from one
+/// segment's style to the next, whereas here every segment is written with
+/// its own codes and followed by a reset. The output is longer, but no style
+/// can carry over from one segment to the next, and the text can be split
+/// between any two segments. The two convert into each other freely.
+///
+/// ```
+/// use tutil::crayon::Color::{Blue, Red};
+///
+/// let mut line = Red.paint("error").join(Blue.paint(": "));
+/// line.push("not found".into());
+/// println!("{}", line + " (exit 1)");
+/// ```
+///
+/// [`StyledStrings`]: struct.StyledStrings.html
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StyledText<'a> {
+    strings: StyledStrings<'a>,
+}
+
+impl<'a> StyledText<'a> {
+    /// Creates an empty `StyledText`.
+    pub fn new() -> StyledText<'a> {
+        StyledText::default()
+    }
+
+    /// Appends a segment to the end.
+    pub fn push(&mut self, segment: StyledString<'a>) {
+        self.strings.push(segment);
+    }
+
+    /// Returns the segments in order.
+    pub fn segments(&self) -> &[StyledString<'a>] {
+        self.strings.segments()
+    }
+
+    /// Returns the text of every segment joined together, without any escape
+    /// codes.
+    pub fn unstyled(&self) -> String {
+        self.strings.unstyled()
+    }
+}
+
+impl<'a> fmt::Display for StyledText<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for segment in self.segments() {
+            try!(write!(f, "{}", segment));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Add<StyledString<'a>> for StyledText<'a> {
+    type Output = StyledText<'a>;
+
+    fn add(mut self, other: StyledString<'a>) -> StyledText<'a> {
+        self.push(other);
+        self
+    }
+}
+
+impl<'a> Add<&'a str> for StyledText<'a> {
+    type Output = StyledText<'a>;
+
+    /// Appends plain, unstyled text.
+    fn add(self, other: &'a str) -> StyledText<'a> {
+        self + StyledString::from(other)
+    }
+}
+
+impl<'a> From<Vec<StyledString<'a>>> for StyledText<'a> {
+    fn from(segments: Vec<StyledString<'a>>) -> StyledText<'a> {
+        StyledText { strings: StyledStrings::from(segments) }
+    }
+}
+
+impl<'a> From<StyledStrings<'a>> for StyledText<'a> {
+    fn from(strings: StyledStrings<'a>) -> StyledText<'a> {
+        StyledText { strings }
+    }
+}
+
+impl<'a> From<StyledText<'a>> for StyledStrings<'a> {
+    fn from(text: StyledText<'a>) -> StyledStrings<'a> {
+        text.strings
+    }
+}
+
+/// Remembers the style that was last switched to in a stream of output, so
+/// that only the escape codes needed to change it are written.
+///
+/// This suits renderers that write text piece by piece, where each piece may
+/// or may not share the style of the one before it. Nothing is written when
+/// the style does not change, and resetting is only done when a style is
+/// actually open.
+///
+/// ```
+/// # tutil::crayon::set_enabled(true);
+/// use tutil::crayon::StyleTracker;
+/// use tutil::crayon::Color::Red;
+///
+/// let mut tracker = StyleTracker::new();
+/// let mut output = String::new();
+///
+/// for c in "abc".chars() {
+///     output += &tracker.set(Red.bold());
+///     output.push(c);
+/// }
+/// output += &tracker.reset();
+///
+/// assert_eq!(output, "\x1b[1;31mabc\x1b[0m");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StyleTracker {
+    current: Style,
+}
+
+impl StyleTracker {
+    /// Creates a tracker that starts from the default, plain style.
+    pub fn new() -> StyleTracker {
+        StyleTracker::default()
+    }
+
+    /// Returns the style that output is currently in.
+    pub fn current(&self) -> Style {
+        self.current
+    }
+
+    /// Switches to `style`, returning the escape codes that change to it from
+    /// the current style, which are empty if it is the same.
+    pub fn set(&mut self, style: Style) -> String {
+        if style == self.current {
+            return String::new();
+        }
+
+        let codes = Transition(self.current, style).to_string();
+        self.current = style;
+        codes
+    }
+
+    /// Switches back to the plain style, returning a reset code if a style is
+    /// open and nothing otherwise.
+    pub fn reset(&mut self) -> String {
+        self.set(Style::default())
+    }
+}
+
+/// An extension to `io::Write` for writing styled strings.
+///
+/// This is implemented for every writer, so it only needs to be imported:
+///
+/// ```
+/// # tutil::crayon::set_enabled(true);
+/// use tutil::crayon::WriteStyledExt;
+/// use tutil::crayon::Color::Red;
+///
+/// let mut output = Vec::new();
+/// output.writeln_styled(&Red.paint("error")).unwrap();
+///
+/// assert_eq!(output, b"\x1b[31merror\x1b[0m\n");
+/// ```
+///
+/// Escape codes are always written, unless colour has been disabled for the
+/// whole thread with [`without_color()`], so that the output is the same as
+/// the `Display` implementation of `StyledString`. To decide whether to use
+/// colour for a particular writer, wrap it in a [`ColorWriter`].
+///
+/// [`without_color()`]: fn.without_color.html
+/// [`ColorWriter`]: struct.ColorWriter.html
+pub trait WriteStyledExt: Write {
+    /// Writes `string` along with the escape codes for its style.
+    fn write_styled(&mut self, string: &StyledString) -> io::Result<()> {
+        write!(self, "{}", string)
+    }
+
+    /// Writes `string` along with the escape codes for its style, followed by
+    /// a newline.
+    fn writeln_styled(&mut self, string: &StyledString) -> io::Result<()> {
+        writeln!(self, "{}", string)
+    }
+}
+
+impl<W: Write + ?Sized> WriteStyledExt for W {}
+
+/// A writer that either passes escape codes through to the writer it wraps or
+/// removes them, depending on whether colour is enabled for it.
+///
+/// This lets the decision to use colour be made once for each destination,
+/// such as using colour for a terminal but not for a log file, while the code
+/// that writes to it stays the same.
+///
+/// ```
+/// use tutil::crayon::{ColorWriter, WriteStyledExt};
+/// use tutil::crayon::Color::Red;
+///
+/// let mut log = ColorWriter::new(Vec::new(), false);
+/// log.write_styled(&Red.paint("error")).unwrap();
+///
+/// assert_eq!(log.into_inner(), b"error");
+/// ```
+#[derive(Debug)]
+pub struct ColorWriter<W: Write> {
+    writer: W,
+    colors_enabled: bool,
+    filter: EscapeFilter,
+}
+
+impl<W: Write> ColorWriter<W> {
+    /// Wraps `writer`, keeping escape codes if `colors_enabled` is true and
+    /// removing them otherwise.
+    pub fn new(writer: W, colors_enabled: bool) -> ColorWriter<W> {
+        ColorWriter { writer, colors_enabled, filter: EscapeFilter::default() }
+    }
+
+    /// Returns true if escape codes are passed through to the writer.
+    pub fn colors_enabled(&self) -> bool {
+        self.colors_enabled
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Unwraps the `ColorWriter`, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Write for ColorWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.colors_enabled {
+            return self.writer.write(buf);
+        }
+
+        // Escape sequences may be split across calls, so the whole buffer is
+        // always consumed and the filter remembers where it got to.
+        let mut filtered = Vec::with_capacity(buf.len());
+        self.filter.filter(buf, &mut filtered);
+
+        try!(self.writer.write_all(&filtered));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// A `Color` is a specific ANSI colour name which can refer to either the
+/// foreground or background.
+///
+/// Each of the eight standard colours, from `Black` to `White`, has a brighter
+/// version, from `BrightBlack` to `BrightWhite`. These are written with their
+/// own escape codes, which every terminal with sixteen colours understands,
+/// rather than as `Fixed(8)` to `Fixed(15)`, which need 256-colour support.
+///
+/// It can also be a custom value from 0 to 255 via the `Fixed(u8)` variant for
+/// terminals that have 256-colour. True-colour is also supported via the
+/// `Rgb(u8, u8, u8)` variant.
+///
+/// For further reading visit this [Wikipedia page][wp].
+///
+/// [wp]: https://en.wikipedia.org/wiki/ANSI_escape_code#Colors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    /// Foreground code `30`, background code `40`.
+    Black,
+
+    /// Foreground code `31`, background code `41`.
+    Red,
+
+    /// Foreground code `32`, background code `42`.
+    Green,
+
+    /// Foreground code `33`, background code `43`.
+    Yellow,
+
+    /// Foreground code `34`, background code `44`.
+    Blue,
+
+    /// Foreground code `35`, background code `45`.
+    Purple,
+
+    /// Foreground code `36`, background code `46`.
+    Cyan,
+
+    /// Foreground code `37`, background code `47`.
+    White,
+
+    /// Foreground code `90`, background code `100`.
+    BrightBlack,
+
+    /// Foreground code `91`, background code `101`.
+    BrightRed,
+
+    /// Foreground code `92`, background code `102`.
+    BrightGreen,
+
+    /// Foreground code `93`, background code `103`.
+    BrightYellow,
+
+    /// Foreground code `94`, background code `104`.
+    BrightBlue,
+
+    /// Foreground code `95`, background code `105`.
+    BrightPurple,
+
+    /// Foreground code `96`, background code `106`.
+    BrightCyan,
+
+    /// Foreground code `97`, background code `107`.
+    BrightWhite,
+
+    /// A value from 0 to 255 for use on terminals that have 256-colour support.
+    ///
+    /// * 0 to 7 are the `Black` through `White` variants. These colours can
+    ///   usually be changed in the terminal emulator.
+    /// * 8 to 15 are the `BrightBlack` through `BrightWhite` variants. These
+    ///   colours can also usually be changed in the terminal emulator,
+    ///   however, it also could be configured to use the original colours and
+    ///   show the text in bold.
+    /// * 16 to 231 contain several palettes of bright colours, arranged in six
+    ///   squares measuring six by six each.
+    /// * 232 to 255 are shades of grey from black to white.
+    ///
+    /// You can view this [colour chart][cc] for a visual representation.
+    /// [cc]: https://upload.wikimedia.org/wikipedia/en/1/15/Xterm_256color_chart.svg
+    Fixed(u8),
+
+    /// A red value, green value and blue value, each between 0 to 255 (the RGB
+    /// colour model) for use on terminals that have true-colour support.
+    ///
+    /// Please note that true-colour support on many terminal emulators is
+    /// patchy, however a fair share of the most common terminal emulators
+    /// support it, such as:
+    ///
+    /// * [Gnome Terminal][gterminal]
+    /// * [Konsole][konsole]
+    /// * [Terminator][terminator]
+    /// * [iTerm 2][iterm2]
+    ///
+    /// For an up-to-date list of true-colour support visit:
+    /// https://gist.github.com/XVilka/8346728.
+    ///
+    /// [gterminal]: https://wiki.gnome.org/Apps/Terminal
+    /// [konsole]: https://konsole.kde.org/
+    /// [terminator]: http://gnometerminator.blogspot.co.nz
+    /// [iterm2]: https://www.iterm2.com/
+    Rgb(u8, u8, u8),
+
+    /// Either of two colours, chosen when the colour is rendered by whether
+    /// the terminal's background is dark, so that one style reads well in
+    /// both light and dark themes.
+    ///
+    /// The background is detected once, the first time an adaptive colour is
+    /// rendered, by [`system::background_is_dark()`]; a dark background is
+    /// assumed if it cannot be detected. Use [`set_dark_background()`] to
+    /// decide it instead.
+    ///
+    /// The colours are `'static` references rather than boxes so that `Color`
+    /// stays `Copy`, which every style and painting method relies on. Constant
+    /// colours can be borrowed for `'static` directly, which suits a theme
+    /// written in the program. Colours only known at run time, such as those
+    /// parsed from a configuration file, cannot be borrowed for `'static`
+    /// without leaking them; use [`crayon::adaptive()`] to choose between
+    /// those instead.
+    ///
+    ///
+    /// ```
+    /// # tutil::crayon::set_enabled(true);
+    /// use tutil::crayon::{self, Color};
+    /// use tutil::crayon::Color::Fixed;
+    ///
+    /// const SUBTLE: Color = Color::Adaptive { on_dark: &Fixed(250), on_light: &Fixed(238) };
+    ///
+    /// crayon::set_dark_background(false);
+    /// assert_eq!(SUBTLE.paint("!").to_string(), "\x1b[38;5;238m!\x1b[0m");
+    /// ```
+    ///
+    /// [`system::background_is_dark()`]: ../system/fn.background_is_dark.html
+    /// [`set_dark_background()`]: fn.set_dark_background.html
+    /// [`crayon::adaptive()`]: fn.adaptive.html
+    Adaptive {
+        /// The colour to use on a dark background.
+        on_dark: &'static Color,
+
+        /// The colour to use on a light background.
+        on_light: &'static Color,
+    },
+}
+
+impl Color {
+    /// Convenience method for creating a [`StyledString`] with the foreground set
+    /// without having to manually create a [`Style`] or use `<color>.normal().paint()`.
+    ///
+    /// As with [`Style::paint()`], a `String` is moved in without being copied.
+    ///
+    /// [`Style::paint()`]: struct.Style.html#method.paint
+    /// [`StyledString`]: struct.StyledString.html
+    /// [`Style`]: struct.Style.html
+    pub fn paint<'a, S>(self, string: S) -> StyledString<'a> where S: Into<Cow<'a, str>> {
+        StyledString { string: string.into(), style: self.normal() }
+    }
+
+    /// Paints `string` with this colour downgraded to what a terminal with the
+    /// given level of colour support can display, so that the colour can be
+    /// declared once in its richest form.
+    ///
+    /// This is the same as `self.normal().adapt(level).paint(string)`; see
+    /// [`Style::adapt()`] for how colours are downgraded. At
+    /// `ColorSupport::None` the string is painted without any colour.
+    ///
+    /// ```
+    /// # tutil::crayon::set_enabled(true);
+    /// use tutil::crayon::ColorSupport;
+    /// use tutil::crayon::Color::Rgb;
+    ///
+    /// let orange = Rgb(255, 135, 0);
+    ///
+    /// assert_eq!(orange.paint_adaptive("!", ColorSupport::Ansi256).to_string(),
+    ///            "\x1b[38;5;208m!\x1b[0m");
+    /// ```
+    ///
+    /// [`Style::adapt()`]: struct.Style.html#method.adapt
+    pub fn paint_adaptive<'a, S>(self, string: S, level: ColorSupport) -> StyledString<'a>
+        where S: Into<Cow<'a, str>>
+    {
+        self.normal().adapt(level).paint(string)
+    }
+
+    /// Returns a [`Style`] with the foreground colour set to this colour.
+    ///
+    /// [`Style`]: struct.Style.html
+    pub fn normal(self) -> Style {
+        Style { foreground: Some(self), ..Style::default() }
+    }
+
+    /// The same as [`Color::normal()`], but also sets the background colour.
+    ///
+    /// [`Color::normal()`]: #method.normal
+    pub fn on(self, background: Color) -> Style {
+        Style { foreground: Some(self), background: Some(background), ..Style::default() }
+    }
+
+    /// Returns a [`Style`] with the 'bold' property set and the foreground colour
+    /// set to this colour.
+    ///
+    /// [`Style`]: struct.Style.html
+    pub fn bold(self) -> Style {
+        Style { foreground: Some(self), bold: true, ..Style::default() }
+    }
+
+    /// Returns a [`Style`] with the 'dimmed' property set and the foreground
+    /// colour set to this colour.
+    ///
+    /// [`Style`]: struct.Style.html
+    pub fn dimmed(self) -> Style {
+        Style { foreground: Some(self), dimmed: true, ..Style::default() }
+    }
+
+    /// Returns a [`Style`] with the 'italic' property set and the foreground
+    /// colour set to this colour.
+    ///
+    /// [`Style`]: struct.Style.html
+    pub fn italic(self) -> Style {
+        Style { foreground: Some(self), italic: true, ..Style::default() }
+    }
+
+    /// Returns a [`Style`] with the 'underline' property set and the foreground
+    /// colour set to this colour.
+    ///
+    /// [`Style`]: struct.Style.html
+    pub fn underline(self) -> Style {
+        Style { foreground: Some(self), underline: true, ..Style::default() }
+    }
+
+    /// Returns a [`Style`] with the 'blink' property set and the foreground
+    /// colour set to this colour.
+    ///
+    /// [`Style`]: struct.Style.html
+    pub fn blink(self) -> Style {
+        Style { foreground: Some(self), blink: true, ..Style::default() }
+    }
+
+    /// Returns a [`Style`] with the 'reverse' property set and the foreground
+    /// colour set to this colour.
+    ///
+    /// [`Style`]: struct.Style.html
+    pub fn reverse(self) -> Style {
+        Style { foreground: Some(self), reverse: true, ..Style::default() }
+    }
+
+    /// Returns a [`Style`] with the 'hidden' property set and the foreground
+    /// colour set to this colour.
+    ///
+    /// [`Style`]: struct.Style.html
+    pub fn hidden(self) -> Style {
+        Style { foreground: Some(self), hidden: true, ..Style::default() }
+    }
+
+    /// Returns a [`Style`] with the 'strikethrough' property set and the
+    /// foreground colour set to this colour.
+    ///
+    /// [`Style`]: struct.Style.html
+    pub fn strikethrough(self) -> Style {
+        Style { foreground: Some(self), strikethrough: true, ..Style::default() }
+    }
+
+    /// Returns the red, green and blue values of this colour.
+    ///
+    /// The named colours and `Fixed` values can be changed in most terminal
+    /// emulators, so these are the values of xterm's default palette.
+    ///
+    /// An `Adaptive` colour gives the values of the colour it resolves to.
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            Black => (0, 0, 0),
+            Red => (205, 0, 0),
+            Green => (0, 205, 0),
+            Yellow => (205, 205, 0),
+            Blue => (0, 0, 238),
+            Purple => (205, 0, 205),
+            Cyan => (0, 205, 205),
+            White => (229, 229, 229),
+            BrightBlack => (127, 127, 127),
+            BrightRed => (255, 0, 0),
+            BrightGreen => (0, 255, 0),
+            BrightYellow => (255, 255, 0),
+            BrightBlue => (92, 92, 255),
+            BrightPurple => (255, 0, 255),
+            BrightCyan => (0, 255, 255),
+            BrightWhite => (255, 255, 255),
+            Fixed(n) => fixed_to_rgb(n),
+            Rgb(r, g, b) => (r, g, b),
+            Adaptive { .. } => self.resolved().to_rgb(),
+        }
+    }
+
+    /// Parses a colour written in CSS notation, returning an `Rgb` colour.
+    ///
+    /// The following forms are accepted:
+    ///
+    /// * Hex notation, such as `#ff8000` or the shorthand `#f80`.
+    /// * Functional notation with integers, such as `rgb(255, 128, 0)`.
+    /// * Functional notation with percentages, such as `rgb(100%, 50%, 0%)`,
+    ///   where each percentage is scaled to the range 0 to 255.
+    /// * Any of the named colours from [CSS Color Module Level 4][css4], such
+    ///   as `orange` or `rebeccapurple`.
+    ///
+    /// As in CSS, names and the `rgb` keyword are case-insensitive and
+    /// components outside of the range 0 to 255 are clamped.
+    ///
+    /// [css4]: https://www.w3.org/TR/css-color-4/#named-colors
+    pub fn from_css(s: &str) -> Result<Color, ParseColorError> {
+        let s = s.trim();
+
+        if let Some(digits) = s.strip_prefix('#') {
+            return parse_hex(digits);
+        }
+
+        let s = s.to_lowercase();
+
+        if s.starts_with("rgb(") && s.ends_with(')') {
+            return parse_rgb_function(&s[4..s.len() - 1]);
+        }
+
+        match CSS_COLORS.binary_search_by(|&(name, ..)| name.cmp(&s)) {
+            Ok(i) => {
+                let (_, r, g, b) = CSS_COLORS[i];
+                Ok(Rgb(r, g, b))
+            }
+            Err(_) => Err(ParseColorError::UnknownName),
+        }
+    }
+
+    /// Parses a colour written as hex digits, such as `#ff8800`, returning an
+    /// `Rgb` colour.
+    ///
+    /// The `#` is optional, and the three digit shorthand, such as `#f80`, is
+    /// accepted as in CSS. Use [`from_css()`](#method.from_css) to accept the
+    /// other CSS notations as well.
+    ///
+    /// ```
+    /// use tutil::crayon::Color;
+    /// use tutil::crayon::Color::Rgb;
+    ///
+    /// assert_eq!(Color::from_hex("#ff8800"), Ok(Rgb(255, 136, 0)));
+    /// assert_eq!(Color::from_hex("f80"), Ok(Rgb(255, 136, 0)));
+    /// ```
+    pub fn from_hex(s: &str) -> Result<Color, ParseColorError> {
+        let s = s.trim();
+        parse_hex(s.strip_prefix('#').unwrap_or(s))
+    }
+
+    /// Returns either `Black` or `White`, whichever is easier to read on a
+    /// background of this colour.
+    ///
+    /// The choice is made by the perceived brightness of the colour's
+    /// [`to_rgb()`](#method.to_rgb) value.
+    ///
+    /// ```
+    /// use tutil::crayon::Color::{Black, Rgb, White, Yellow};
+    ///
+    /// assert_eq!(Yellow.readable_foreground(), Black);
+    /// assert_eq!(Rgb(0, 0, 128).readable_foreground(), White);
+    /// ```
+    pub fn readable_foreground(self) -> Color {
+        let (r, g, b) = self.to_rgb();
+        let brightness = (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000;
+
+        if brightness >= 128 { Black } else { White }
+    }
+
+    /// Returns how different this colour looks from `other`, as a distance
+    /// between their [`to_rgb()`](#method.to_rgb) values.
+    ///
+    /// The metric is the "redmean" approximation of perceptual distance, a
+    /// Euclidean distance whose red and blue components are weighted by the
+    /// average amount of red in the two colours, with green always weighted
+    /// most heavily. It is much cheaper than converting to a perceptual colour
+    /// space such as CIELAB while giving noticeably better matches than a
+    /// plain Euclidean distance. Identical colours have a distance of 0 and
+    /// black and white a distance of about 765.
+    ///
+    /// This is the metric used when downgrading colours for terminals with
+    /// fewer colours, and can be used to match against a palette of your own.
+    ///
+    /// ```
+    /// use tutil::crayon::Color::{Blue, Rgb};
+    ///
+    /// let red = Rgb(255, 0, 0);
+    /// assert!(red.distance(Rgb(255, 165, 0)) < red.distance(Blue));
+    /// ```
+    pub fn distance(self, other: Color) -> f64 {
+        distance(self.to_rgb(), other.to_rgb())
+    }
+
+    /// Returns the named colour, from `Black` to `White`, that is closest to
+    /// this one by [`distance()`](#method.distance). Named colours, including
+    /// the bright ones, are returned unchanged.
+    ///
+    /// Together with [`name()`](#method.name), this can describe any colour
+    /// in words:
+    ///
+    /// ```
+    /// use tutil::crayon::Color::Rgb;
+    ///
+    /// let name = Rgb(200, 30, 40).nearest_named().name().unwrap();
+    /// assert_eq!(format!("approximately {}", name), "approximately red");
+    /// ```
+    pub fn nearest_named(self) -> Color {
+        match self {
+            Fixed(_) | Rgb(..) => nearest_named(self.to_rgb()),
+            Adaptive { .. } => self.resolved().nearest_named(),
+            _ => self,
+        }
+    }
+
+    /// Returns the `Fixed` colour from the 256-colour palette that is closest
+    /// to an `Rgb` colour, for terminals that cannot show every colour. Other
+    /// colours are returned unchanged, except that `Adaptive` colours are
+    /// resolved first.
+    ///
+    /// Only the 6×6×6 colour cube and the greyscale ramp, which are the same in
+    /// every terminal, are considered, so greys map to the ramp.
+    ///
+    /// ```
+    /// use tutil::crayon::Color::{Fixed, Rgb};
+    ///
+    /// assert_eq!(Rgb(255, 128, 0).to_fixed(), Fixed(208));
+    /// assert_eq!(Rgb(128, 128, 128).to_fixed(), Fixed(244));
+    /// ```
+    pub fn to_fixed(self) -> Color {
+        match self {
+            Rgb(r, g, b) => Fixed(nearest_fixed(r, g, b)),
+            Adaptive { .. } => self.resolved().to_fixed(),
+            _ => self,
+        }
+    }
+
+    /// Returns the lowercase name of a named colour, such as `"red"`, or
+    /// `None` for `Fixed`, `Rgb` and `Adaptive` colours.
+    pub fn name(self) -> Option<&'static str> {
+        match self {
+            Black => Some("black"),
+            Red => Some("red"),
+            Green => Some("green"),
+            Yellow => Some("yellow"),
+            Blue => Some("blue"),
+            Purple => Some("purple"),
+            Cyan => Some("cyan"),
+            White => Some("white"),
+            BrightBlack => Some("bright black"),
+            BrightRed => Some("bright red"),
+            BrightGreen => Some("bright green"),
+            BrightYellow => Some("bright yellow"),
+            BrightBlue => Some("bright blue"),
+            BrightPurple => Some("bright purple"),
+            BrightCyan => Some("bright cyan"),
+            BrightWhite => Some("bright white"),
+            Fixed(_) | Rgb(..) | Adaptive { .. } => None,
+        }
+    }
+
+    /// Returns an iterator over every colour in the 256-colour palette, from
+    /// `Fixed(0)` to `Fixed(255)`, which is handy for printing a palette chart.
+    ///
+    /// ```
+    /// use tutil::crayon::Color;
+    ///
+    /// for color in Color::palette_256() {
+    ///     print!("{}", color.paint("█"));
+    /// }
+    /// ```
+    pub fn palette_256() -> impl Iterator<Item = Color> {
+        (0..=255).map(Fixed)
+    }
+
+    /// Returns an iterator over the eight standard named colours, from `Black`
+    /// to `White` in the order of their escape codes, without their bright
+    /// versions.
+    pub fn named() -> impl Iterator<Item = Color> {
+        NAMED_COLORS.iter().cloned()
+    }
+
+    fn write_foreground_code(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Black => write!(f, "30"),
+            Red => write!(f, "31"),
+            Green => write!(f, "32"),
+            Yellow => write!(f, "33"),
+            Blue => write!(f, "34"),
+            Purple => write!(f, "35"),
+            Cyan => write!(f, "36"),
+            White => write!(f, "37"),
+            BrightBlack => write!(f, "90"),
+            BrightRed => write!(f, "91"),
+            BrightGreen => write!(f, "92"),
+            BrightYellow => write!(f, "93"),
+            BrightBlue => write!(f, "94"),
+            BrightPurple => write!(f, "95"),
+            BrightCyan => write!(f, "96"),
+            BrightWhite => write!(f, "97"),
+            Fixed(n) => write!(f, "38;5;{}", &n),
+            Rgb(r, g, b) => write!(f, "38;2;{};{};{}", &r, &g, &b),
+            Adaptive { .. } => self.resolved().write_foreground_code(f),
+        }
+    }
+
+    fn write_background_code(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Black => write!(f, "40"),
+            Red => write!(f, "41"),
+            Green => write!(f, "42"),
+            Yellow => write!(f, "43"),
+            Blue => write!(f, "44"),
+            Purple => write!(f, "45"),
+            Cyan => write!(f, "46"),
+            White => write!(f, "47"),
+            BrightBlack => write!(f, "100"),
+            BrightRed => write!(f, "101"),
+            BrightGreen => write!(f, "102"),
+            BrightYellow => write!(f, "103"),
+            BrightBlue => write!(f, "104"),
+            BrightPurple => write!(f, "105"),
+            BrightCyan => write!(f, "106"),
+            BrightWhite => write!(f, "107"),
+            Fixed(n) => write!(f, "48;5;{}", &n),
+            Rgb(r, g, b) => write!(f, "48;2;{};{};{}", &r, &g, &b),
+            Adaptive { .. } => self.resolved().write_background_code(f),
+        }
+    }
+
+    /// Returns the number of bytes written by `write_background_code()` if
+    /// `background` is true, or by `write_foreground_code()` otherwise.
+    fn code_len(&self, background: bool) -> usize {
+        fn digits(n: u8) -> usize {
+            if n >= 100 { 3 } else if n >= 10 { 2 } else { 1 }
+        }
+
+        match *self {
+            Fixed(n) => 5 + digits(n),
+            Rgb(r, g, b) => 7 + digits(r) + digits(g) + digits(b),
+            Adaptive { .. } => self.resolved().code_len(background),
+            BrightBlack | BrightRed | BrightGreen | BrightYellow | BrightBlue | BrightPurple
+                | BrightCyan | BrightWhite if background => 3,
+            _ => 2,
+        }
+    }
+
+    /// Returns the closest colour to this one that can be shown with the given
+    /// level of colour support.
+    ///
+    /// For `ColorSupport::Ansi16`, the closest of the named colours and their
+    /// bright versions is chosen, unlike [`nearest_named()`], which only
+    /// chooses from the named colours. With `ColorSupport::None` the colour is
+    /// returned unchanged, as there is nothing it could be downgraded to.
+    ///
+    /// An `Adaptive` colour is resolved first, as the downgraded colour has to
+    /// be a single one.
+    ///
+    /// This lets a program use `Rgb` colours throughout and still look right
+    /// in terminals with fewer colours, such as with the level given by
+    /// [`supports_color()`](fn.supports_color.html).
+    ///
+    /// [`nearest_named()`]: #method.nearest_named
+    ///
+    /// ```
+    /// use tutil::crayon::ColorSupport;
+    /// use tutil::crayon::Color::{Fixed, Rgb, Yellow};
+    ///
+    /// let orange = Rgb(255, 128, 0);
+    /// assert_eq!(orange.downgrade(ColorSupport::TrueColor), orange);
+    /// assert_eq!(orange.downgrade(ColorSupport::Ansi256), Fixed(208));
+    /// assert_eq!(orange.downgrade(ColorSupport::Ansi16), Yellow);
+    /// ```
+    pub fn downgrade(self, level: ColorSupport) -> Color {
+        match (level, self) {
+            (ColorSupport::None, _) => self,
+            (_, Adaptive { .. }) => self.resolved().downgrade(level),
+            (ColorSupport::Ansi256, Rgb(..)) => self.to_fixed(),
+            (ColorSupport::Ansi16, Fixed(_)) | (ColorSupport::Ansi16, Rgb(..)) => {
+                nearest_of(&ANSI16_COLORS, self.to_rgb())
+            }
+            _ => self,
+        }
+    }
+
+    /// Returns the colour that an `Adaptive` colour stands for on a dark or
+    /// light background. Other colours are returned unchanged.
+    fn resolve(self, dark: bool) -> Color {
+        match self {
+            Adaptive { on_dark, .. } if dark => on_dark.resolve(dark),
+            Adaptive { on_light, .. } => on_light.resolve(dark),
+            _ => self,
+        }
+    }
+
+    /// Resolves an `Adaptive` colour against the detected background.
+    fn resolved(self) -> Color {
+        match self {
+            Adaptive { .. } => self.resolve(dark_background()),
+            _ => self,
+        }
+    }
+}
+
+/// The named colours, in the order of their escape codes.
+static NAMED_COLORS: [Color; 8] = [Black, Red, Green, Yellow, Blue, Purple, Cyan, White];
+
+/// The bright versions of the named colours, in the same order.
+static BRIGHT_COLORS: [Color; 8] = [BrightBlack, BrightRed, BrightGreen, BrightYellow, BrightBlue,
+                                    BrightPurple, BrightCyan, BrightWhite];
+
+/// Every colour that a terminal with `ColorSupport::Ansi16` can show.
+static ANSI16_COLORS: [Color; 16] = [Black, Red, Green, Yellow, Blue, Purple, Cyan, White,
+                                     BrightBlack, BrightRed, BrightGreen, BrightYellow,
+                                     BrightBlue, BrightPurple, BrightCyan, BrightWhite];
+
+/// An error returned when parsing a [`Color`] from a string fails.
+///
+/// [`Color`]: enum.Color.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParseColorError {
+    /// A hex colour did not have either 3 or 6 digits.
+    InvalidLength,
+
+    /// A hex colour contained a character that is not a hex digit.
+    InvalidDigit,
+
+    /// An `rgb()` colour did not have exactly three numeric components.
+    InvalidComponent,
+
+    /// A `fixed:` colour did not have a number from 0 to 255.
+    InvalidFixed,
+
+    /// The string was not in a recognised format or named an unknown colour.
+    UnknownName,
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match *self {
+            ParseColorError::InvalidLength => "hex colour must have 3 or 6 digits",
+            ParseColorError::InvalidDigit => "invalid digit found in hex colour",
+            ParseColorError::InvalidComponent => "rgb() colour must have three numeric components",
+            ParseColorError::InvalidFixed => "fixed colour must be a number from 0 to 255",
+            ParseColorError::UnknownName => "unknown colour name",
+        };
+
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for ParseColorError {}
+
+/// Parses a colour as it might be written in a configuration file or on the
+/// command line: a colour name, in any case, such as `red` or `RED`; `fixed:`
+/// followed by a palette index, such as `fixed:220`; or a hex colour, such as
+/// `#ff8800`.
+///
+/// The names are those given by [`Color::name()`], so a name always parses
+/// back to the same colour. The bright colours can also be written with an
+/// underscore or nothing in place of the space, such as `bright_red` or
+/// `brightred`, which is easier to pass as a command line argument.
+///
+/// ```
+/// use tutil::crayon::Color::{self, BrightRed, Cyan, Fixed, Rgb};
+///
+/// assert_eq!("Cyan".parse::<Color>(), Ok(Cyan));
+/// assert_eq!("bright_red".parse::<Color>(), Ok(BrightRed));
+/// assert_eq!("fixed:220".parse::<Color>(), Ok(Fixed(220)));
+/// assert_eq!("#ff8800".parse::<Color>(), Ok(Rgb(255, 136, 0)));
+/// ```
+///
+/// [`Color::name()`]: enum.Color.html#method.name
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Color, ParseColorError> {
+        let s = s.trim();
+
+        if let Some(digits) = s.strip_prefix('#') {
+            return parse_hex(digits);
+        }
+
+        let s = s.to_lowercase();
+
+        if let Some(index) = s.strip_prefix("fixed:") {
+            return index.parse().map(Fixed).map_err(|_| ParseColorError::InvalidFixed);
+        }
+
+        let s = s.replace('_', " ");
+
+        NAMED_COLORS.iter()
+            .chain(BRIGHT_COLORS.iter())
+            .cloned()
+            .find(|color| match color.name() {
+                Some(name) => name == s || name.replace(' ', "") == s,
+                None => false,
+            })
+            .ok_or(ParseColorError::UnknownName)
+    }
+}
+
+/// How many colours a terminal is able to display.
+///
+/// The levels are ordered, so `ColorSupport::Ansi16 < ColorSupport::TrueColor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorSupport {
+    /// No colours or other escape codes at all.
+    None,
+
+    /// The eight named colours and their bright versions.
+    Ansi16,
+
+    /// The 256 colours of the `Fixed` palette.
+    Ansi256,
+
+    /// Any `Rgb` colour.
+    TrueColor,
+}
+
+/// Returns the red, green and blue values of a colour from xterm's default
+/// 256-colour palette.
+fn fixed_to_rgb(n: u8) -> (u8, u8, u8) {
+    /// The levels of each component of the 6×6×6 colour cube.
+    const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    match n {
+        0 => Black.to_rgb(),
+        1 => Red.to_rgb(),
+        2 => Green.to_rgb(),
+        3 => Yellow.to_rgb(),
+        4 => Blue.to_rgb(),
+        5 => Purple.to_rgb(),
+        6 => Cyan.to_rgb(),
+        7 => White.to_rgb(),
+        8 => BrightBlack.to_rgb(),
+        9 => BrightRed.to_rgb(),
+        10 => BrightGreen.to_rgb(),
+        11 => BrightYellow.to_rgb(),
+        12 => BrightBlue.to_rgb(),
+        13 => BrightPurple.to_rgb(),
+        14 => BrightCyan.to_rgb(),
+        15 => BrightWhite.to_rgb(),
+        16..=231 => {
+            let i = (n - 16) as usize;
+            (CUBE[i / 36], CUBE[i / 6 % 6], CUBE[i % 6])
+        }
+        _ => {
+            let level = 8 + (n - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+/// Returns the colour in xterm's 256-colour palette that is closest to the
+/// given one, out of the 6×6×6 colour cube and the greyscale ramp.
+fn nearest_fixed(r: u8, g: u8, b: u8) -> u8 {
+    // The cube levels are 0, 95, 135, 175, 215 and 255, so the midpoints
+    // between them are 48, 115, 155, 195 and 235.
+    fn cube_index(v: u8) -> u8 {
+        if v < 48 { 0 } else if v < 115 { 1 } else { (v - 35) / 40 }
+    }
+
+    let cube = 16 + 36 * cube_index(r) + 6 * cube_index(g) + cube_index(b);
+
+    // The greyscale ramp runs from 8 to 238 in steps of 10.
+    let average = (r as u16 + g as u16 + b as u16) / 3;
+    let grey = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+
+    let target = (r, g, b);
+    if distance(fixed_to_rgb(grey), target) < distance(fixed_to_rgb(cube), target) {
+        grey
+    } else {
+        cube
+    }
+}
+
+/// Returns the named colour that is closest to the given red, green and blue
+/// values.
+fn nearest_named(target: (u8, u8, u8)) -> Color {
+    nearest_of(&NAMED_COLORS, target)
+}
+
+/// Returns the colour of `candidates` that is closest to the given red, green
+/// and blue values.
+fn nearest_of(candidates: &[Color], target: (u8, u8, u8)) -> Color {
+    candidates.iter()
+        .cloned()
+        .min_by(|a, b| distance(a.to_rgb(), target).total_cmp(&distance(b.to_rgb(), target)))
+        .unwrap_or(White)
+}
+
+/// Returns the redmean distance between two colours, as described by
+/// `Color::distance()`.
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> f64 {
+    let mean = (r1 as f64 + r2 as f64) / 2.0;
+    let (dr, dg, db) = (r1 as f64 - r2 as f64, g1 as f64 - g2 as f64, b1 as f64 - b2 as f64);
+
+    ((2.0 + mean / 256.0) * dr * dr + 4.0 * dg * dg + (2.0 + (255.0 - mean) / 256.0) * db * db)
+        .sqrt()
+}
+
+/// Parses the digits of a hex colour, without the leading `#`.
+fn parse_hex(digits: &str) -> Result<Color, ParseColorError> {
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ParseColorError::InvalidDigit);
+    }
+
+    // The digits are known to be ASCII at this point, so slicing is safe.
+    let component = |i: usize, len: usize| u8::from_str_radix(&digits[i..i + len], 16).unwrap();
+
+    match digits.len() {
+        6 => Ok(Rgb(component(0, 2), component(2, 2), component(4, 2))),
+        3 => Ok(Rgb(component(0, 1) * 17, component(1, 1) * 17, component(2, 1) * 17)),
+        _ => Err(ParseColorError::InvalidLength),
+    }
+}
+
+/// Parses the comma separated arguments of a CSS `rgb()` colour.
+fn parse_rgb_function(args: &str) -> Result<Color, ParseColorError> {
+    let components: Vec<u8> = try!(args.split(',').map(parse_rgb_component).collect());
+
+    if components.len() != 3 {
+        return Err(ParseColorError::InvalidComponent);
+    }
+
+    Ok(Rgb(components[0], components[1], components[2]))
+}
+
+/// Parses a single integer or percentage component of a CSS `rgb()` colour.
+fn parse_rgb_component(s: &str) -> Result<u8, ParseColorError> {
+    let s = s.trim();
+
+    let value = if let Some(percent) = s.strip_suffix('%') {
+        let percent: f32 = try!(percent.parse().map_err(|_| ParseColorError::InvalidComponent));
+        percent * 255.0 / 100.0
+    } else {
+        try!(s.parse::<i32>().map_err(|_| ParseColorError::InvalidComponent)) as f32
+    };
+
+    Ok(value.round().clamp(0.0, 255.0) as u8)
+}
+
+/// The named colours from CSS Color Module Level 4, sorted by name.
+const CSS_COLORS: &[(&str, u8, u8, u8)] = &[
+    ("aliceblue", 240, 248, 255),
+    ("antiquewhite", 250, 235, 215),
+    ("aqua", 0, 255, 255),
+    ("aquamarine", 127, 255, 212),
+    ("azure", 240, 255, 255),
+    ("beige", 245, 245, 220),
+    ("bisque", 255, 228, 196),
+    ("black", 0, 0, 0),
+    ("blanchedalmond", 255, 235, 205),
+    ("blue", 0, 0, 255),
+    ("blueviolet", 138, 43, 226),
+    ("brown", 165, 42, 42),
+    ("burlywood", 222, 184, 135),
+    ("cadetblue", 95, 158, 160),
+    ("chartreuse", 127, 255, 0),
+    ("chocolate", 210, 105, 30),
+    ("coral", 255, 127, 80),
+    ("cornflowerblue", 100, 149, 237),
+    ("cornsilk", 255, 248, 220),
+    ("crimson", 220, 20, 60),
+    ("cyan", 0, 255, 255),
+    ("darkblue", 0, 0, 139),
+    ("darkcyan", 0, 139, 139),
+    ("darkgoldenrod", 184, 134, 11),
+    ("darkgray", 169, 169, 169),
+    ("darkgreen", 0, 100, 0),
+    ("darkgrey", 169, 169, 169),
+    ("darkkhaki", 189, 183, 107),
+    ("darkmagenta", 139, 0, 139),
+    ("darkolivegreen", 85, 107, 47),
+    ("darkorange", 255, 140, 0),
+    ("darkorchid", 153, 50, 204),
+    ("darkred", 139, 0, 0),
+    ("darksalmon", 233, 150, 122),
+    ("darkseagreen", 143, 188, 143),
+    ("darkslateblue", 72, 61, 139),
+    ("darkslategray", 47, 79, 79),
+    ("darkslategrey", 47, 79, 79),
+    ("darkturquoise", 0, 206, 209),
+    ("darkviolet", 148, 0, 211),
+    ("deeppink", 255, 20, 147),
+    ("deepskyblue", 0, 191, 255),
+    ("dimgray", 105, 105, 105),
+    ("dimgrey", 105, 105, 105),
+    ("dodgerblue", 30, 144, 255),
+    ("firebrick", 178, 34, 34),
+    ("floralwhite", 255, 250, 240),
+    ("forestgreen", 34, 139, 34),
+    ("fuchsia", 255, 0, 255),
+    ("gainsboro", 220, 220, 220),
+    ("ghostwhite", 248, 248, 255),
+    ("gold", 255, 215, 0),
+    ("goldenrod", 218, 165, 32),
+    ("gray", 128, 128, 128),
+    ("green", 0, 128, 0),
+    ("greenyellow", 173, 255, 47),
+    ("grey", 128, 128, 128),
+    ("honeydew", 240, 255, 240),
+    ("hotpink", 255, 105, 180),
+    ("indianred", 205, 92, 92),
+    ("indigo", 75, 0, 130),
+    ("ivory", 255, 255, 240),
+    ("khaki", 240, 230, 140),
+    ("lavender", 230, 230, 250),
+    ("lavenderblush", 255, 240, 245),
+    ("lawngreen", 124, 252, 0),
+    ("lemonchiffon", 255, 250, 205),
+    ("lightblue", 173, 216, 230),
+    ("lightcoral", 240, 128, 128),
+    ("lightcyan", 224, 255, 255),
+    ("lightgoldenrodyellow", 250, 250, 210),
+    ("lightgray", 211, 211, 211),
+    ("lightgreen", 144, 238, 144),
+    ("lightgrey", 211, 211, 211),
+    ("lightpink", 255, 182, 193),
+    ("lightsalmon", 255, 160, 122),
+    ("lightseagreen", 32, 178, 170),
+    ("lightskyblue", 135, 206, 250),
+    ("lightslategray", 119, 136, 153),
+    ("lightslategrey", 119, 136, 153),
+    ("lightsteelblue", 176, 196, 222),
+    ("lightyellow", 255, 255, 224),
+    ("lime", 0, 255, 0),
+    ("limegreen", 50, 205, 50),
+    ("linen", 250, 240, 230),
+    ("magenta", 255, 0, 255),
+    ("maroon", 128, 0, 0),
+    ("mediumaquamarine", 102, 205, 170),
+    ("mediumblue", 0, 0, 205),
+    ("mediumorchid", 186, 85, 211),
+    ("mediumpurple", 147, 112, 219),
+    ("mediumseagreen", 60, 179, 113),
+    ("mediumslateblue", 123, 104, 238),
+    ("mediumspringgreen", 0, 250, 154),
+    ("mediumturquoise", 72, 209, 204),
+    ("mediumvioletred", 199, 21, 133),
+    ("midnightblue", 25, 25, 112),
+    ("mintcream", 245, 255, 250),
+    ("mistyrose", 255, 228, 225),
+    ("moccasin", 255, 228, 181),
+    ("navajowhite", 255, 222, 173),
+    ("navy", 0, 0, 128),
+    ("oldlace", 253, 245, 230),
+    ("olive", 128, 128, 0),
+    ("olivedrab", 107, 142, 35),
+    ("orange", 255, 165, 0),
+    ("orangered", 255, 69, 0),
+    ("orchid", 218, 112, 214),
+    ("palegoldenrod", 238, 232, 170),
+    ("palegreen", 152, 251, 152),
+    ("paleturquoise", 175, 238, 238),
+    ("palevioletred", 219, 112, 147),
+    ("papayawhip", 255, 239, 213),
+    ("peachpuff", 255, 218, 185),
+    ("peru", 205, 133, 63),
+    ("pink", 255, 192, 203),
+    ("plum", 221, 160, 221),
+    ("powderblue", 176, 224, 230),
+    ("purple", 128, 0, 128),
+    ("rebeccapurple", 102, 51, 153),
+    ("red", 255, 0, 0),
+    ("rosybrown", 188, 143, 143),
+    ("royalblue", 65, 105, 225),
+    ("saddlebrown", 139, 69, 19),
+    ("salmon", 250, 128, 114),
+    ("sandybrown", 244, 164, 96),
+    ("seagreen", 46, 139, 87),
+    ("seashell", 255, 245, 238),
+    ("sienna", 160, 82, 45),
+    ("silver", 192, 192, 192),
+    ("skyblue", 135, 206, 235),
+    ("slateblue", 106, 90, 205),
+    ("slategray", 112, 128, 144),
+    ("slategrey", 112, 128, 144),
+    ("snow", 255, 250, 250),
+    ("springgreen", 0, 255, 127),
+    ("steelblue", 70, 130, 180),
+    ("tan", 210, 180, 140),
+    ("teal", 0, 128, 128),
+    ("thistle", 216, 191, 216),
+    ("tomato", 255, 99, 71),
+    ("turquoise", 64, 224, 208),
+    ("violet", 238, 130, 238),
+    ("wheat", 245, 222, 179),
+    ("white", 255, 255, 255),
+    ("whitesmoke", 245, 245, 245),
+    ("yellow", 255, 255, 0),
+    ("yellowgreen", 154, 205, 50),
+];
+
+/// One of the properties of a [`Style`] other than its colours, such as
+/// 'bold' or 'underline'.
+///
+/// [`Style`]: struct.Style.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Attribute {
+    /// The 'bold' property.
+    Bold,
+
+    /// The 'dimmed' property.
+    Dimmed,
+
+    /// The 'italic' property.
+    Italic,
+
+    /// The 'underline' property.
+    Underline,
+
+    /// The 'blink' property.
+    Blink,
+
+    /// The 'reverse' property.
+    Reverse,
+
+    /// The 'hidden' property.
+    Hidden,
+
+    /// The 'strikethrough' property.
+    Strikethrough,
+
+    /// The 'superscript' property, which few terminals support.
+    Superscript,
+
+    /// The 'subscript' property, which few terminals support.
+    Subscript,
+}
+
+/// A collection of properties that are used to format a string.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    foreground: Option<Color>,
+    background: Option<Color>,
+    bold: bool,
+    dimmed: bool,
+    italic: bool,
+    underline: bool,
+    blink: bool,
+    reverse: bool,
+    hidden: bool,
+    strikethrough: bool,
+    superscript: bool,
+    subscript: bool,
+}
+
+impl Style {
+    /// Creates a new `Style` without any formatting.
+    pub fn new() -> Style {
+        Style::default()
+    }
+
+    /// Applies the `Style` to a string, yielding a [`StyledString`].
+    ///
+    /// A `&str` is borrowed and a `String` is moved into the `StyledString`,
+    /// so painting never copies or reallocates the string.
+    ///
+    /// [`StyledString`]: struct.StyledString.html
+    pub fn paint<'a, S>(self, string: S) -> StyledString<'a> where S: Into<Cow<'a, str>> {
+        StyledString { string: string.into(), style: self }
+    }
+
+    /// Formats `value` with its `Display` implementation and applies the
+    /// `Style` to the result, which saves calling `to_string()` before
+    /// [`paint()`](#method.paint).
+    ///
+    /// ```
+    /// # tutil::crayon::set_enabled(true);
+    /// use tutil::crayon::Color::Green;
+    ///
+    /// let passed = 42;
+    /// assert_eq!(Green.bold().paint_value(passed).to_string(), "\x1b[1;32m42\x1b[0m");
+    /// ```
+    pub fn paint_value<T: fmt::Display>(self, value: T) -> StyledString<'static> {
+        StyledString { string: Cow::Owned(value.to_string()), style: self }
+    }
+
+    /// Sets the foreground to the given colour.
+    pub fn foreground(&self, color: Color) -> Style {
+        Style { foreground: Some(color), ..*self }
+    }
+
+    /// Sets the background to the given colour.
+    pub fn background(&self, color: Color) -> Style {
+        Style { background: Some(color), ..*self }
+    }
+
+    /// Applies the 'bold' property.
+    pub fn bold(&self) -> Style {
+        Style { bold: true, ..*self }
+    }
+
+    /// Applies the 'dimmed' property.
+    pub fn dimmed(&self) -> Style {
+        Style { dimmed: true, ..*self }
+    }
+
+    /// Applies the 'italic' property.
+    pub fn italic(&self) -> Style {
+        Style { italic: true, ..*self }
+    }
+
+    /// Applies the 'underline' property.
+    pub fn underline(&self) -> Style {
+        Style { underline: true, ..*self }
+    }
+
+    /// Applies the 'blink' property.
+    pub fn blink(&self) -> Style {
+        Style { blink: true, ..*self }
+    }
+
+    /// Applies the 'reverse' property.
+    pub fn reverse(&self) -> Style {
+        Style { reverse: true, ..*self }
+    }
+
+    /// Applies the 'hidden' property.
+    pub fn hidden(&self) -> Style {
+        Style { hidden: true, ..*self }
+    }
+
+    /// Applies the 'strikethrough' property, which draws a line through the
+    /// text, such as for a completed or deprecated item.
+    pub fn strikethrough(&self) -> Style {
+        Style { strikethrough: true, ..*self }
+    }
+
+    /// Applies the 'superscript' property, removing 'subscript' as text
+    /// cannot be both.
+    ///
+    /// This is experimental: it is written as SGR code 73, which only a few
+    /// terminals, such as mintty, understand. Most others ignore it and show
+    /// the text at its usual size and position.
+    pub fn superscript(&self) -> Style {
+        Style { superscript: true, subscript: false, ..*self }
+    }
+
+    /// Applies the 'subscript' property, removing 'superscript' as text
+    /// cannot be both.
+    ///
+    /// Like [`superscript()`](#method.superscript), this is experimental. It
+    /// is written as SGR code 74.
+    pub fn subscript(&self) -> Style {
+        Style { subscript: true, superscript: false, ..*self }
+    }
+
+    /// Removes the foreground colour, so that the terminal's default is used.
+    ///
+    /// This and the other `without_` and `not_` methods make it possible to
+    /// derive a variant of a shared style that has fewer properties.
+    ///
+    /// ```
+    /// use tutil::crayon::Style;
+    /// use tutil::crayon::Color::{Blue, Red};
+    ///
+    /// let heading = Red.on(Blue).bold().underline();
+    /// let subheading = heading.without_foreground().not_underline();
+    /// assert_eq!(subheading, Style::new().background(Blue).bold());
+    /// ```
+    pub fn without_foreground(&self) -> Style {
+        Style { foreground: None, ..*self }
+    }
+
+    /// Removes the background colour, so that the terminal's default is used.
+    pub fn without_background(&self) -> Style {
+        Style { background: None, ..*self }
+    }
+
+    /// Removes the 'bold' property.
+    pub fn not_bold(&self) -> Style {
+        Style { bold: false, ..*self }
+    }
+
+    /// Removes the 'dimmed' property.
+    pub fn not_dimmed(&self) -> Style {
+        Style { dimmed: false, ..*self }
+    }
+
+    /// Removes the 'italic' property.
+    pub fn not_italic(&self) -> Style {
+        Style { italic: false, ..*self }
+    }
+
+    /// Removes the 'underline' property.
+    pub fn not_underline(&self) -> Style {
+        Style { underline: false, ..*self }
+    }
+
+    /// Removes the 'blink' property.
+    pub fn not_blink(&self) -> Style {
+        Style { blink: false, ..*self }
+    }
+
+    /// Removes the 'reverse' property.
+    pub fn not_reverse(&self) -> Style {
+        Style { reverse: false, ..*self }
+    }
+
+    /// Removes the 'hidden' property.
+    pub fn not_hidden(&self) -> Style {
+        Style { hidden: false, ..*self }
+    }
+
+    /// Removes the 'strikethrough' property.
+    pub fn not_strikethrough(&self) -> Style {
+        Style { strikethrough: false, ..*self }
+    }
+
+    /// Removes the 'superscript' property.
+    pub fn not_superscript(&self) -> Style {
+        Style { superscript: false, ..*self }
+    }
+
+    /// Removes the 'subscript' property.
+    pub fn not_subscript(&self) -> Style {
+        Style { subscript: false, ..*self }
+    }
+
+    /// Returns a style for a selected item of a menu that is otherwise drawn
+    /// with this style, so that the highlight matches the rest of the theme.
+    ///
+    /// The foreground and background colours are swapped. If neither is set,
+    /// the 'reverse' property is toggled instead, which makes the terminal
+    /// swap its default colours. Other properties are kept, so chain `bold()`
+    /// to make the selection stand out further.
+    ///
+    /// ```
+    /// use tutil::crayon::Color::{Black, Green};
+    ///
+    /// let item = Green.on(Black);
+    /// assert_eq!(item.as_selected(), Black.on(Green));
+    /// ```
+    pub fn as_selected(&self) -> Style {
+        match (self.foreground, self.background) {
+            (None, None) => Style { reverse: !self.reverse, ..*self },
+            (foreground, background) => {
+                Style { foreground: background, background: foreground, ..*self }
+            }
+        }
+    }
+
+    /// Applies every property in `attributes` at once, which is convenient
+    /// when they come from a list, such as one read from a configuration file.
+    ///
+    /// ```
+    /// use tutil::crayon::{Attribute, Style};
+    ///
+    /// let style = Style::new().with_attributes(&[Attribute::Bold, Attribute::Underline]);
+    /// assert_eq!(style, Style::new().bold().underline());
+    /// ```
+    pub fn with_attributes(&self, attributes: &[Attribute]) -> Style {
+        let mut style = *self;
+
+        for attribute in attributes {
+            match *attribute {
+                Attribute::Bold => style.bold = true,
+                Attribute::Dimmed => style.dimmed = true,
+                Attribute::Italic => style.italic = true,
+                Attribute::Underline => style.underline = true,
+                Attribute::Blink => style.blink = true,
+                Attribute::Reverse => style.reverse = true,
+                Attribute::Hidden => style.hidden = true,
+                Attribute::Strikethrough => style.strikethrough = true,
+                Attribute::Superscript => style = style.superscript(),
+                Attribute::Subscript => style = style.subscript(),
+            }
+        }
+
+        style
+    }
+
+    /// Returns the properties that are set, such as 'bold' or 'underline', in
+    /// the order that they are declared in [`Attribute`], which is also the
+    /// order of their escape codes.
+    ///
+    /// This is the reverse of [`with_attributes()`].
+    ///
+    /// ```
+    /// use tutil::crayon::Attribute;
+    /// use tutil::crayon::Color::Red;
+    ///
+    /// assert_eq!(Red.underline().bold().active_attributes(),
+    ///            [Attribute::Bold, Attribute::Underline]);
+    /// ```
+    ///
+    /// [`Attribute`]: enum.Attribute.html
+    /// [`with_attributes()`]: #method.with_attributes
+    pub fn active_attributes(&self) -> Vec<Attribute> {
+        let flags = [(self.bold, Attribute::Bold),
+                     (self.dimmed, Attribute::Dimmed),
+                     (self.italic, Attribute::Italic),
+                     (self.underline, Attribute::Underline),
+                     (self.blink, Attribute::Blink),
+                     (self.reverse, Attribute::Reverse),
+                     (self.hidden, Attribute::Hidden),
+                     (self.strikethrough, Attribute::Strikethrough),
+                     (self.superscript, Attribute::Superscript),
+                     (self.subscript, Attribute::Subscript)];
+
+        flags.iter().filter(|&&(set, _)| set).map(|&(_, attribute)| attribute).collect()
+    }
+
+    /// Returns true if both styles have the same properties set, such as
+    /// 'bold' or 'underline', regardless of their colours.
+    pub fn attributes_eq(&self, other: &Style) -> bool {
+        let uncolored = |style: &Style| Style { foreground: None, background: None, ..*style };
+        uncolored(self) == uncolored(other)
+    }
+
+    /// Returns true if both styles have the same foreground and background
+    /// colours, regardless of their properties.
+    pub fn colors_eq(&self, other: &Style) -> bool {
+        self.foreground == other.foreground && self.background == other.background
+    }
+
+    /// Returns this style with `other` layered on top of it: the colours that
+    /// `other` sets replace those of this style, and the properties of both
+    /// are kept.
+    ///
+    /// This lets a base style be shared and overridden where needed. The `+`
+    /// operator does the same.
+    ///
+    /// ```
+    /// use tutil::crayon::Style;
+    /// use tutil::crayon::Color::Red;
+    ///
+    /// let base = Style::new().bold();
+    /// assert_eq!(base.combine(&Red.normal()), Red.bold());
+    /// assert_eq!(base + Red.underline(), Red.bold().underline());
+    /// ```
+    pub fn combine(&self, other: &Style) -> Style {
+        Style {
+            foreground: other.foreground.or(self.foreground),
+            background: other.background.or(self.background),
+            ..self.with_attributes(&other.active_attributes())
+        }
+    }
+
+    /// Returns the style made up of only the colours and properties that every
+    /// one of `styles` has in common, which is plain if there are none.
+    ///
+    /// A run of segments can be written by switching to the common style once
+    /// and then only changing what differs for each segment.
+    ///
+    /// ```
+    /// use tutil::crayon::Style;
+    /// use tutil::crayon::Color::{Blue, Red};
+    ///
+    /// let styles = [Red.bold().underline(), Blue.bold(), Red.bold()];
+    /// assert_eq!(Style::common(&styles), Style::new().bold());
+    /// ```
+    pub fn common(styles: &[Style]) -> Style {
+        let shared = |a: Option<Color>, b: Option<Color>| if a == b { a } else { None };
+
+        match styles.split_first() {
+            Some((first, rest)) => rest.iter().fold(*first, |common, style| Style {
+                foreground: shared(common.foreground, style.foreground),
+                background: shared(common.background, style.background),
+                bold: common.bold && style.bold,
+                dimmed: common.dimmed && style.dimmed,
+                italic: common.italic && style.italic,
+                underline: common.underline && style.underline,
+                blink: common.blink && style.blink,
+                reverse: common.reverse && style.reverse,
+                hidden: common.hidden && style.hidden,
+                strikethrough: common.strikethrough && style.strikethrough,
+                superscript: common.superscript && style.superscript,
+                subscript: common.subscript && style.subscript,
+            }),
+            None => Style::default(),
+        }
+    }
+
+    /// Returns a CSS declaration block that gives HTML text the same appearance
+    /// as this `Style`, such as `color:#cd0000;font-weight:bold`.
+    ///
+    /// Colours are converted with [`Color::to_rgb()`] and the 'reverse'
+    /// property is applied by swapping the foreground and background colours.
+    /// A plain `Style` gives an empty string.
+    ///
+    /// [`Color::to_rgb()`]: enum.Color.html#method.to_rgb
+    pub fn to_css(&self) -> String {
+        let (foreground, background) = if self.reverse {
+            (self.background, self.foreground)
+        } else {
+            (self.foreground, self.background)
+        };
+
+        let mut declarations = Vec::new();
+
+        if let Some(color) = foreground {
+            let (r, g, b) = color.to_rgb();
+            declarations.push(format!("color:#{:02x}{:02x}{:02x}", r, g, b));
+        }
+
+        if let Some(color) = background {
+            let (r, g, b) = color.to_rgb();
+            declarations.push(format!("background-color:#{:02x}{:02x}{:02x}", r, g, b));
+        }
+
+        if self.bold { declarations.push("font-weight:bold".to_string()); }
+        if self.dimmed { declarations.push("opacity:0.5".to_string()); }
+        if self.italic { declarations.push("font-style:italic".to_string()); }
+
+        let mut decorations = Vec::new();
+        if self.underline { decorations.push("underline"); }
+        if self.blink { decorations.push("blink"); }
+        if self.strikethrough { decorations.push("line-through"); }
+
+        if !decorations.is_empty() {
+            declarations.push(format!("text-decoration:{}", decorations.join(" ")));
+        }
+
+        if self.hidden { declarations.push("visibility:hidden".to_string()); }
+        if self.superscript { declarations.push("vertical-align:super".to_string()); }
+        if self.subscript { declarations.push("vertical-align:sub".to_string()); }
+
+        declarations.join(";")
+    }
+
+    /// Returns a copy of this `Style` with its colours downgraded to the
+    /// closest ones that can be shown with the given level of colour support.
+    ///
+    /// `Rgb` colours become `Fixed` colours for `ColorSupport::Ansi256`, and
+    /// both become named or bright colours for `ColorSupport::Ansi16`. For
+    /// `ColorSupport::None` the colours are removed, but properties such as
+    /// 'bold' are kept.
+    ///
+    /// ```
+    /// use tutil::crayon::{ColorSupport, Style};
+    /// use tutil::crayon::Color::{BrightRed, Fixed, Rgb};
+    ///
+    /// let style = Rgb(255, 0, 0).bold();
+    ///
+    /// assert_eq!(style.adapt(ColorSupport::TrueColor), style);
+    /// assert_eq!(style.adapt(ColorSupport::Ansi256), Fixed(196).bold());
+    /// assert_eq!(style.adapt(ColorSupport::Ansi16), BrightRed.bold());
+    /// assert_eq!(style.adapt(ColorSupport::None), Style::new().bold());
+    /// ```
+    pub fn adapt(&self, level: ColorSupport) -> Style {
+        if level == ColorSupport::None {
+            return Style { foreground: None, background: None, ..*self };
+        }
+
+        Style {
+            foreground: self.foreground.map(|color| color.downgrade(level)),
+            background: self.background.map(|color| color.downgrade(level)),
+            ..*self
+        }
+    }
+
+    /// Returns a copy of this `Style` with `f` applied to the foreground and
+    /// background colours, leaving the properties and any unset colour alone.
+    ///
+    /// This is useful for changing every colour of a theme at once, such as
+    /// swapping one palette for another.
+    ///
+    /// ```
+    /// use tutil::crayon::Color::{Black, White};
+    ///
+    /// let inverted = White.on(Black).bold().map_colors(|color| match color {
+    ///     White => Black,
+    ///     Black => White,
+    ///     other => other,
+    /// });
+    ///
+    /// assert_eq!(inverted, Black.on(White).bold());
+    /// ```
+    pub fn map_colors<F>(&self, f: F) -> Style where F: Fn(Color) -> Color {
+        Style {
+            foreground: self.foreground.map(&f),
+            background: self.background.map(&f),
+            ..*self
+        }
+    }
+
+    /// Returns the number of bytes that the escape codes surrounding a string
+    /// painted with this `Style` will occupy, without rendering them.
+    ///
+    /// A plain `Style` emits no escape codes, so its length is 0. The current
+    /// [blink policy](fn.set_blink_policy.html) is taken into account.
+    pub fn byte_len(&self) -> usize {
+        self.with_blink_policy(blink_policy()).policy_byte_len()
+    }
+
+    /// Returns the escape codes that go before a string painted with this
+    /// `Style`, such as `"\x1b[1;31m"`, or an empty string if it is plain.
+    ///
+    /// Together with [`suffix()`](#method.suffix), this is for building output
+    /// by hand, such as when interleaving styled text with cursor movements.
+    /// The current [blink policy](fn.set_blink_policy.html) is taken into
+    /// account, but unlike painting, the codes are returned even if colour is
+    /// disabled.
+    ///
+    /// ```
+    /// use tutil::crayon::Color::Red;
+    ///
+    /// let style = Red.bold();
+    /// let line = format!("{}error{}: not found", style.prefix(), style.suffix());
+    /// assert_eq!(line, "\x1b[1;31merror\x1b[0m: not found");
+    /// ```
+    pub fn prefix(&self) -> String {
+        Prefix(self.with_blink_policy(blink_policy())).to_string()
+    }
+
+    /// Returns the escape code that goes after a string painted with this
+    /// `Style`, which resets the style, or an empty string if it is plain.
+    pub fn suffix(&self) -> String {
+        Suffix(self.with_blink_policy(blink_policy())).to_string()
+    }
+
+    /// The implementation of `byte_len()`, once the blink policy has been
+    /// applied.
+    fn policy_byte_len(&self) -> usize {
+        if self.is_plain() {
+            return 0;
+        }
+
+        let properties = [self.bold, self.dimmed, self.italic, self.underline, self.blink,
+                          self.reverse, self.hidden, self.strikethrough];
+        let colors = self.foreground.map(|color| (color, false)).into_iter()
+            .chain(self.background.map(|color| (color, true)));
+
+        // Each property code is a single byte, except for the two bytes of
+        // superscript or subscript, only one of which is written.
+        let mut codes = properties.iter().filter(|&&set| set).count();
+        let mut codes_len = codes;
+
+        if self.superscript || self.subscript {
+            codes += 1;
+            codes_len += 2;
+        }
+
+        for (color, background) in colors {
+            codes += 1;
+            codes_len += color.code_len(background);
+        }
+
+        // `ESC [`, the codes separated by semicolons and `m`, then `ESC [ 0 m`.
+        2 + codes_len + (codes - 1) + 1 + 4
+    }
+
+    /// Returns this `Style` with the blink property handled according to
+    /// `policy`.
+    fn with_blink_policy(&self, policy: BlinkPolicy) -> Style {
+        match policy {
+            BlinkPolicy::Emit => *self,
+            BlinkPolicy::Strip => Style { blink: false, ..*self },
+            BlinkPolicy::BoldSubstitute if self.blink => {
+                Style { blink: false, bold: true, ..*self }
+            }
+            BlinkPolicy::BoldSubstitute => *self,
+        }
+    }
+
+    /// Returns true if this `Style` has no colours or properties set.
+    fn is_plain(self) -> bool {
+        self == Style::default()
+    }
+
+    /// Write any ANSI escape codes that go before the given text, such as
+    /// colour or style codes.
+    fn write_prefix(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_plain() {
+            return Ok(());
+        }
+
+        self.write_changes(&Style::default(), false, f)
+    }
+
+    /// Write the escape codes that change the terminal from `previous` to this
+    /// style, writing as little as possible.
+    ///
+    /// Properties and colours can only be turned off by resetting everything,
+    /// so in that case the reset code is written followed by the whole style.
+    fn write_transition(&self, previous: &Style, f: &mut fmt::Formatter) -> fmt::Result {
+        if self == previous {
+            return Ok(());
+        }
+
+        if self.is_plain() {
+            return previous.write_suffix(f);
+        }
+
+        let cleared = |before: bool, after: bool| before && !after;
+        let reset = cleared(previous.bold, self.bold)
+            || cleared(previous.dimmed, self.dimmed)
+            || cleared(previous.italic, self.italic)
+            || cleared(previous.underline, self.underline)
+            || cleared(previous.blink, self.blink)
+            || cleared(previous.reverse, self.reverse)
+            || cleared(previous.hidden, self.hidden)
+            || cleared(previous.strikethrough, self.strikethrough)
+            || cleared(previous.superscript, self.superscript)
+            || cleared(previous.subscript, self.subscript)
+            || cleared(previous.foreground.is_some(), self.foreground.is_some())
+            || cleared(previous.background.is_some(), self.background.is_some());
+
+        if reset {
+            self.write_changes(&Style::default(), true, f)
+        } else {
+            self.write_changes(previous, false, f)
+        }
+    }
+
+    /// Write a single escape sequence that turns on the properties and colours
+    /// of this style that differ from `base`, starting with the reset code if
+    /// `reset` is true.
+    fn write_changes(&self, base: &Style, reset: bool, f: &mut fmt::Formatter) -> fmt::Result {
+        use std::fmt::Write;
+
+        debug_assert!(!(self.superscript && self.subscript),
+                      "a style cannot be both superscript and subscript");
+
+        try!(write!(f, "\x1b["));
+        let mut written_anything = reset;
+
+        if reset {
+            try!(f.write_char('0'));
+        }
+
+        {
+            let mut write_code = |code| {
+                if written_anything {
+                    try!(f.write_str(";"));
+                }
+                written_anything = true;
+                f.write_str(code)
+            };
+
+            if self.bold && !base.bold { try!(write_code("1")); }
+            if self.dimmed && !base.dimmed { try!(write_code("2")); }
+            if self.italic && !base.italic { try!(write_code("3")); }
+            if self.underline && !base.underline { try!(write_code("4")); }
+            if self.blink && !base.blink { try!(write_code("5")); }
+            if self.reverse && !base.reverse { try!(write_code("7")); }
+            if self.hidden && !base.hidden { try!(write_code("8")); }
+            if self.strikethrough && !base.strikethrough { try!(write_code("9")); }
+
+            // Only one of these is written, as they cannot both apply.
+            if self.superscript && !base.superscript {
+                try!(write_code("73"));
+            } else if self.subscript && !base.subscript {
+                try!(write_code("74"));
+            }
+        }
+
+        if let Some(fg) = self.foreground {
+            if base.foreground != Some(fg) {
+                if written_anything { try!(f.write_char(';')); }
+                written_anything = true;
+
+                try!(fg.write_foreground_code(f));
+            }
+        }
+
+        if let Some(bg) = self.background {
+            if base.background != Some(bg) {
+                if written_anything { try!(f.write_char(';')); }
+
+                try!(bg.write_background_code(f));
+            }
+        }
+
+        try!(f.write_char('m'));
+        Ok(())
+    }
+
+    /// Write any ANSI escape codes that go after the given text, typically the
+    /// reset code.
+    fn write_suffix(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_plain() {
+            return Ok(());
+        } else {
+            write!(f, "\x1b[0m")
+        }
+    }
+}
+
+/// Displays the escape codes written by `Style::write_prefix()`.
+struct Prefix(Style);
+
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.write_prefix(f)
+    }
+}
+
+/// Displays the escape code written by `Style::write_suffix()`.
+struct Suffix(Style);
+
+impl fmt::Display for Suffix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.write_suffix(f)
+    }
+}
+
+impl Add for Style {
+    type Output = Style;
+
+    /// Combines two styles, as with [`Style::combine()`].
+    ///
+    /// [`Style::combine()`]: struct.Style.html#method.combine
+    fn add(self, other: Style) -> Style {
+        self.combine(&other)
+    }
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style {
+            foreground: None,
+            background: None,
+            bold: false,
+            dimmed: false,
+            italic: false,
+            underline: false,
+            blink: false,
+            reverse: false,
+            hidden: false,
+            strikethrough: false,
+            superscript: false,
+            subscript: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::Color::*;
+    use system::test::fake_env;
+
+    use std::collections::HashSet;
+
+    // Convenience macro for creating test cases.
+    macro_rules! test {
+        ($name: ident: $style: expr; $input: expr => $result: expr) => {
+            #[test]
+            fn $name() {
+                set_enabled(true);
+                assert_eq!($style.paint($input).to_string(), $result.to_string())
+            }
+        }
+    }
+
+    test!(plain:             Style::default(); "TEST" => "TEST");
+    test!(black:             Black;            "TEST" => "\x1b[30mTEST\x1b[0m");
+    test!(black_background:  White.on(Black);  "TEST" => "\x1b[37;40mTEST\x1b[0m");
+    test!(red:               Red;              "TEST" => "\x1b[31mTEST\x1b[0m");
+    test!(red_background:    Black.on(Red);    "TEST" => "\x1b[30;41mTEST\x1b[0m");
+    test!(green:             Green;            "TEST" => "\x1b[32mTEST\x1b[0m");
+    test!(green_background:  Black.on(Green);  "TEST" => "\x1b[30;42mTEST\x1b[0m");
+    test!(yellow:            Yellow;           "TEST" => "\x1b[33mTEST\x1b[0m");
+    test!(yellow_background: Black.on(Yellow); "TEST" => "\x1b[30;43mTEST\x1b[0m");
+    test!(blue:              Blue;             "TEST" => "\x1b[34mTEST\x1b[0m");
+    test!(blue_background:   Black.on(Blue);   "TEST" => "\x1b[30;44mTEST\x1b[0m");
+    test!(purple:            Purple;           "TEST" => "\x1b[35mTEST\x1b[0m");
+    test!(purple_background: Black.on(Purple); "TEST" => "\x1b[30;45mTEST\x1b[0m");
+    test!(cyan:              Cyan;             "TEST" => "\x1b[36mTEST\x1b[0m");
+    test!(cyan_background:   Black.on(Cyan);   "TEST" => "\x1b[30;46mTEST\x1b[0m");
+    test!(white:             White;            "TEST" => "\x1b[37mTEST\x1b[0m");
+    test!(white_background:  Black.on(White);  "TEST" => "\x1b[30;47mTEST\x1b[0m");
+    test!(bright_red:        BrightRed;        "TEST" => "\x1b[91mTEST\x1b[0m");
+    test!(bright_black_background: White.on(BrightBlack);
+          "TEST" => "\x1b[37;100mTEST\x1b[0m");
+    test!(bright_white_on_bright_blue: BrightWhite.on(BrightBlue);
+          "TEST" => "\x1b[97;104mTEST\x1b[0m");
+    test!(bright_builders:   BrightCyan.bold().underline().strikethrough();
+          "TEST" => "\x1b[1;4;9;96mTEST\x1b[0m");
+    test!(fixed:             Fixed(220);       "TEST" => "\x1b[38;5;220mTEST\x1b[0m");
+    test!(fixed_background:  Fixed(220).on(Fixed(245));
+          "TEST" => "\x1b[38;5;220;48;5;245mTEST\x1b[0m");
+    test!(rgb: Rgb(105, 245, 238);
+          "TEST" => "\x1b[38;2;105;245;238mTEST\x1b[0m");
+    test!(rgb_background: Black.on(Rgb(100, 245, 238));
+          "TEST" => "\x1b[30;48;2;100;245;238mTEST\x1b[0m");
+
+    test!(bold:      Style::new().bold();      "TEST" => "\x1b[1mTEST\x1b[0m");
+    test!(dimmed:    Style::new().dimmed();    "TEST" => "\x1b[2mTEST\x1b[0m");
+    test!(italic:    Style::new().italic();    "TEST" => "\x1b[3mTEST\x1b[0m");
+    test!(underline: Style::new().underline(); "TEST" => "\x1b[4mTEST\x1b[0m");
+    test!(blink:     Style::new().blink();     "TEST" => "\x1b[5mTEST\x1b[0m");
+    test!(reverse:   Style::new().reverse();   "TEST" => "\x1b[7mTEST\x1b[0m");
+    test!(hidden:    Style::new().hidden();    "TEST" => "\x1b[8mTEST\x1b[0m");
+    test!(strikethrough: Style::new().strikethrough(); "TEST" => "\x1b[9mTEST\x1b[0m");
+    test!(red_strikethrough: Red.strikethrough(); "TEST" => "\x1b[9;31mTEST\x1b[0m");
+    test!(strikethrough_on_background: Red.on(Blue).strikethrough();
+          "TEST" => "\x1b[9;31;44mTEST\x1b[0m");
+    test!(every_property: Style::new().bold().dimmed().italic().underline().blink().reverse()
+                                      .hidden().strikethrough();
+          "TEST" => "\x1b[1;2;3;4;5;7;8;9mTEST\x1b[0m");
+    test!(superscript: Style::new().superscript(); "TEST" => "\x1b[73mTEST\x1b[0m");
+    test!(subscript:   Style::new().subscript();   "TEST" => "\x1b[74mTEST\x1b[0m");
+    test!(superscript_with_others: Red.bold().superscript();
+          "TEST" => "\x1b[1;73;31mTEST\x1b[0m");
+
+    #[test]
+    fn superscript_and_subscript_exclusive() {
+        set_enabled(true);
+        assert_eq!(Style::new().subscript().superscript(), Style::new().superscript());
+        assert_eq!(Style::new().superscript().subscript(), Style::new().subscript());
+
+        let style = Style::new().with_attributes(&[Attribute::Subscript, Attribute::Superscript]);
+        assert_eq!(style.active_attributes(), [Attribute::Superscript]);
+        assert_eq!(style.paint("2").to_string(), "\x1b[73m2\x1b[0m");
+        assert_eq!(style.byte_len(), "\x1b[73m\x1b[0m".len());
+    }
+
+    #[test]
+    fn superscript_to_subscript_resets() {
+        set_enabled(true);
+        let line = Style::new().superscript().paint("a") + Style::new().subscript().paint("b");
+        assert_eq!(line.to_string(), "\x1b[73ma\x1b[0;74mb\x1b[0m");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "both superscript and subscript")]
+    fn superscript_and_subscript_together_asserts() {
+        set_enabled(true);
+        let style = Style { superscript: true, subscript: true, ..Style::default() };
+        let _ = style.paint("2").to_string();
+    }
+
+    #[test]
+    fn css_hex() {
+        assert_eq!(Color::from_css("#ff8000"), Ok(Rgb(255, 128, 0)));
+        assert_eq!(Color::from_css("#f80"), Ok(Rgb(255, 136, 0)));
+    }
+
+    #[test]
+    fn to_fixed_uses_cube_and_greyscale_ramp() {
+        assert_eq!(Rgb(255, 255, 255).to_fixed(), Fixed(231));
+        assert_eq!(Rgb(0, 0, 0).to_fixed(), Fixed(16));
+        assert_eq!(Rgb(95, 135, 175).to_fixed(), Fixed(67));
+        assert_eq!(Rgb(128, 128, 128).to_fixed(), Fixed(244));
+        assert_eq!(Rgb(60, 61, 59).to_fixed(), Fixed(237));
+        assert_eq!(Red.to_fixed(), Red);
+        assert_eq!(Fixed(3).to_fixed(), Fixed(3));
+    }
+
+    #[test]
+    fn hex_with_and_without_hash() {
+        assert_eq!(Color::from_hex("#ff8800"), Ok(Rgb(255, 136, 0)));
+        assert_eq!(Color::from_hex("1E90FF"), Ok(Rgb(30, 144, 255)));
+        assert_eq!(Color::from_hex("#f80"), Ok(Rgb(255, 136, 0)));
+        assert_eq!(Color::from_hex("000"), Ok(Rgb(0, 0, 0)));
+    }
+
+    #[test]
+    fn hex_errors() {
+        assert_eq!(Color::from_hex("#ff880"), Err(ParseColorError::InvalidLength));
+        assert_eq!(Color::from_hex(""), Err(ParseColorError::InvalidLength));
+        assert_eq!(Color::from_hex("#gg8800"), Err(ParseColorError::InvalidDigit));
+        assert_eq!(Color::from_hex("##ff8800"), Err(ParseColorError::InvalidDigit));
+    }
+
+    #[test]
+    fn parse_names_fixed_and_hex() {
+        assert_eq!("cyan".parse::<Color>(), Ok(Cyan));
+        assert_eq!(" RED ".parse::<Color>(), Ok(Red));
+        assert_eq!("Fixed:220".parse::<Color>(), Ok(Fixed(220)));
+        assert_eq!("#0a0a0a".parse::<Color>(), Ok(Rgb(10, 10, 10)));
+    }
+
+    #[test]
+    fn parse_bright_names() {
+        assert_eq!("bright red".parse::<Color>(), Ok(BrightRed));
+        assert_eq!("Bright_Cyan".parse::<Color>(), Ok(BrightCyan));
+        assert_eq!("brightwhite".parse::<Color>(), Ok(BrightWhite));
+    }
+
+    #[test]
+    fn every_name_parses_back() {
+        for &color in NAMED_COLORS.iter().chain(&BRIGHT_COLORS) {
+            let name = color.name().unwrap();
+            assert_eq!(name.parse::<Color>(), Ok(color), "{}", name);
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("magenta".parse::<Color>(), Err(ParseColorError::UnknownName));
+        assert_eq!("bright".parse::<Color>(), Err(ParseColorError::UnknownName));
+        assert_eq!("bright-red".parse::<Color>(), Err(ParseColorError::UnknownName));
+        assert_eq!("fixed:256".parse::<Color>(), Err(ParseColorError::InvalidFixed));
+        assert_eq!("fixed:".parse::<Color>(), Err(ParseColorError::InvalidFixed));
+        assert_eq!("#0a0a0".parse::<Color>(), Err(ParseColorError::InvalidLength));
+    }
+
+    #[test]
+    fn css_rgb_integers() {
+        assert_eq!(Color::from_css("rgb(255, 128, 0)"), Ok(Rgb(255, 128, 0)));
+        assert_eq!(Color::from_css("RGB(300,-5,7)"), Ok(Rgb(255, 0, 7)));
+    }
+
+    #[test]
+    fn css_rgb_percentages() {
+        assert_eq!(Color::from_css("rgb(100%, 50%, 0%)"), Ok(Rgb(255, 128, 0)));
+    }
+
+    #[test]
+    fn css_named() {
+        assert_eq!(Color::from_css("orange"), Ok(Rgb(255, 165, 0)));
+        assert_eq!(Color::from_css("RebeccaPurple"), Ok(Rgb(102, 51, 153)));
+    }
+
+    #[test]
+    fn css_malformed() {
+        assert_eq!(Color::from_css("rgb(255, 128)"), Err(ParseColorError::InvalidComponent));
+        assert_eq!(Color::from_css("rgb(a, b, c)"), Err(ParseColorError::InvalidComponent));
+        assert_eq!(Color::from_css("#ff80"), Err(ParseColorError::InvalidLength));
+        assert_eq!(Color::from_css("#gg8000"), Err(ParseColorError::InvalidDigit));
+        assert_eq!(Color::from_css("not a colour"), Err(ParseColorError::UnknownName));
+    }
+
+    #[test]
+    fn same_attributes_different_colors() {
+        let a = Red.on(Black).bold().underline();
+        let b = Blue.bold().underline();
+
+        assert!(a.attributes_eq(&b));
+        assert!(!a.colors_eq(&b));
+    }
+
+    #[test]
+    fn same_colors_different_attributes() {
+        let a = Red.on(Black).bold();
+        let b = Red.on(Black).italic();
+
+        assert!(a.colors_eq(&b));
+        assert!(!a.attributes_eq(&b));
+    }
+
+    #[test]
+    fn visible_width_of_colored_text() {
+        let styled = format!("{} and {}", Red.paint("red"), Blue.on(White).bold().paint("blue"));
+        assert_eq!(visible_width(&styled), 12);
+    }
+
+    #[test]
+    fn visible_width_of_wide_characters() {
+        assert_eq!(visible_width("日本語"), 6);
+        assert_eq!(visible_width(&Red.paint("日本").to_string()), 4);
+    }
+
+    #[test]
+    fn visible_width_with_hyperlink() {
+        let link = "see \x1b]8;;https://example.com\x1b\\here\x1b]8;;\x1b\\ for more";
+        assert_eq!(visible_width(link), 17);
+    }
+
+    #[test]
+    fn paint_moves_owned_string() {
+        let string = String::from("TEST");
+        let pointer = string.as_ptr();
+
+        assert_eq!(Red.paint(string).as_ptr(), pointer);
+    }
+
+    #[test]
+    fn paint_borrows_str() {
+        let string = "TEST";
+        assert_eq!(Style::new().bold().paint(string).as_ptr(), string.as_ptr());
+    }
+
+    #[test]
+    fn paint_integer_value() {
+        set_enabled(true);
+        assert_eq!(Red.normal().paint_value(-7).to_string(), "\x1b[31m-7\x1b[0m");
+        assert_eq!(Style::new().paint_value(1.5).to_string(), "1.5");
+    }
+
+    #[test]
+    fn paint_display_value() {
+        set_enabled(true);
+        struct Version(u8, u8);
+
+        impl fmt::Display for Version {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "v{}.{}", self.0, self.1)
+            }
+        }
+
+        let painted = Blue.underline().paint_value(Version(0, 2));
+        assert_eq!(&*painted, "v0.2");
+        assert_eq!(painted.to_string(), "\x1b[4;34mv0.2\x1b[0m");
+    }
+
+    #[test]
+    fn plain_without_color() {
+        set_enabled(true);
+        let inside = without_color(|| {
+            assert!(!colors_enabled());
+            Red.bold().paint("TEST").to_string()
+        });
+
+        assert_eq!(inside, "TEST");
+        assert!(colors_enabled());
+        assert_eq!(Red.paint("TEST").to_string(), "\x1b[31mTEST\x1b[0m");
+    }
+
+    #[test]
+    fn without_color_restores_after_panic() {
+        set_enabled(true);
+        use std::panic;
+
+        let result = panic::catch_unwind(|| without_color(|| panic!("TEST")));
+
+        assert!(result.is_err());
+        assert!(colors_enabled());
+    }
+
+    #[test]
+    fn colors_enabled_for_each_stream() {
+        let env = fake_env(&[]);
+        let is_terminal = |stream| stream == Stream::Stderr;
+
+        assert!(!colors_enabled_for_with(Stream::Stdout, None, &env, is_terminal));
+        assert!(colors_enabled_for_with(Stream::Stderr, None, &env, is_terminal));
+        assert!(!without_color(|| {
+            colors_enabled_for_with(Stream::Stderr, None, &env, is_terminal)
+        }));
+    }
+
+    #[test]
+    fn verbosity_below_threshold() {
+        let mut output = Vec::new();
+        Verbosity::new(1).vwriteln(&mut output, 2, &Red.paint("debug")).unwrap();
+        Verbosity::default().vwriteln(&mut output, 1, &Red.paint("info")).unwrap();
+
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn verbosity_at_and_above_threshold() {
+        set_enabled(true);
+        let mut output = Vec::new();
+        Verbosity::new(2).vwriteln(&mut output, 2, &Red.paint("debug")).unwrap();
+        Verbosity::new(2).vwriteln(&mut output, 0, &Style::new().paint("note")).unwrap();
+
+        assert_eq!(output, b"\x1b[31mdebug\x1b[0m\nnote\n");
+    }
+
+    #[test]
+    fn color_overrides_for_streams() {
+        let no_color = fake_env(&[("NO_COLOR", "1")]);
+        let forced = fake_env(&[("FORCE_COLOR", "1"), ("NO_COLOR", "1")]);
+        let forced_off = fake_env(&[("FORCE_COLOR", "0")]);
+
+        assert!(!colors_enabled_for_with(Stream::Stdout, None, &no_color, |_| true));
+        assert!(colors_enabled_for_with(Stream::Stdout, None, &forced, |_| false));
+        assert!(!colors_enabled_for_with(Stream::Stderr, None, &forced_off, |_| true));
+    }
+
+    #[test]
+    fn set_enabled_overrides_the_environment_for_streams() {
+        let no_color = fake_env(&[("NO_COLOR", "1")]);
+        let forced = fake_env(&[("FORCE_COLOR", "1")]);
+
+        assert!(colors_enabled_for_with(Stream::Stdout, Some(true), &no_color, |_| false));
+        assert!(!without_color(|| {
+            colors_enabled_for_with(Stream::Stdout, Some(true), &no_color, |_| false)
+        }));
+        assert!(!colors_enabled_for_with(Stream::Stderr, Some(false), &forced, |_| true));
+    }
+
+    #[test]
+    fn enabled_detected_from_environment() {
+        assert!(enabled_with(fake_env(&[])));
+        assert!(!enabled_with(fake_env(&[("NO_COLOR", "1")])));
+        assert!(enabled_with(fake_env(&[("NO_COLOR", "1"), ("FORCE_COLOR", "1")])));
+        assert!(!enabled_with(fake_env(&[("FORCE_COLOR", "false")])));
+    }
+
+    #[test]
+    fn rgb_of_named_and_fixed_colors() {
+        assert_eq!(Red.to_rgb(), (205, 0, 0));
+        assert_eq!(Fixed(1).to_rgb(), Red.to_rgb());
+        assert_eq!(Fixed(196).to_rgb(), (255, 0, 0));
+        assert_eq!(Fixed(244).to_rgb(), (128, 128, 128));
+        assert_eq!(Rgb(1, 2, 3).to_rgb(), (1, 2, 3));
+    }
+
+    #[test]
+    fn css_for_bold_red() {
+        assert_eq!(Rgb(255, 0, 0).bold().to_css(), "color:#ff0000;font-weight:bold");
+        assert_eq!(Red.bold().to_css(), "color:#cd0000;font-weight:bold");
+    }
+
+    #[test]
+    fn css_text_decorations() {
+        assert_eq!(Style::new().underline().blink().to_css(), "text-decoration:underline blink");
+        assert_eq!(Style::new().underline().strikethrough().to_css(),
+                   "text-decoration:underline line-through");
+    }
+
+    #[test]
+    fn css_reverse_swaps_colors() {
+        assert_eq!(Black.on(White).reverse().to_css(),
+                   "color:#e5e5e5;background-color:#000000");
+    }
+
+    #[test]
+    fn css_for_plain_style() {
+        assert_eq!(Style::new().to_css(), "");
+    }
+
+    #[test]
+    fn html_escapes_special_characters() {
+        assert_eq!(Rgb(255, 0, 0).bold().paint("<a href=\"x\">Tom & Jerry's</a>").to_html(),
+                   "<span style=\"color:#ff0000;font-weight:bold\">\
+                    &lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;</span>");
+    }
+
+    #[test]
+    fn html_for_plain_string() {
+        assert_eq!(Style::new().paint("1 < 2").to_html(), "1 &lt; 2");
+    }
+
+    #[test]
+    fn join_with_styled_separator() {
+        set_enabled(true);
+        let segments = [Red.paint("home"), Green.paint("docs"), Blue.paint("tutil")];
+        let separator = Fixed(244).paint(" › ");
+        let joined = join(&segments, &separator);
+
+        let visible: String = ::ansi::segments(&joined)
+            .filter_map(|segment| match segment {
+                ::ansi::Segment::Text(text) => Some(text),
+                ::ansi::Segment::Escape(_) => None,
+            })
+            .collect();
+
+        assert_eq!(visible, "home › docs › tutil");
+        assert_eq!(joined, "\x1b[31mhome\x1b[38;5;244m › \x1b[32mdocs\x1b[38;5;244m › \
+                            \x1b[34mtutil\x1b[0m");
+    }
+
+    #[test]
+    fn join_only_writes_changes() {
+        set_enabled(true);
+        let segments = [Red.paint("a"), Blue.paint("b"), "c".into()];
+        assert_eq!(join(&segments, &Red.bold().paint("/")),
+                   "\x1b[31ma\x1b[1m/\x1b[0;34mb\x1b[1;31m/\x1b[0mc");
+    }
+
+    #[test]
+    fn join_without_color() {
+        let segments = [Red.paint("a"), Blue.paint("b")];
+        assert_eq!(without_color(|| join(&segments, &Green.paint("/"))), "a/b");
+    }
+
+    #[test]
+    fn add_styled_strings() {
+        set_enabled(true);
+        let line = Red.paint("a") + Blue.paint("b");
+
+        assert_eq!(line.unstyled(), "ab");
+        assert_eq!(line.segments()[0].style(), Red.normal());
+        assert_eq!(line.segments()[1].style(), Blue.normal());
+        assert_eq!(line.to_string(), "\x1b[31ma\x1b[34mb\x1b[0m");
+    }
+
+    #[test]
+    fn join_two_styled_strings() {
+        set_enabled(true);
+        let line = Red.paint("a").join(Blue.paint("b"));
+
+        assert_eq!(line.segments(), [Red.paint("a"), Blue.paint("b")]);
+        assert_eq!(line.unstyled(), "ab");
+        assert_eq!(line.to_string(), "\x1b[31ma\x1b[0m\x1b[34mb\x1b[0m");
+    }
+
+    #[test]
+    fn styled_text_and_strings_convert() {
+        set_enabled(true);
+        let strings = Red.paint("a") + Red.paint("b");
+        let text = StyledText::from(strings.clone());
+
+        assert_eq!(text.segments(), strings.segments());
+        assert_eq!(text.to_string(), "\x1b[31ma\x1b[0m\x1b[31mb\x1b[0m");
+        assert_eq!(StyledStrings::from(text), strings);
+    }
+
+    #[test]
+    fn styled_text_resets_after_every_segment() {
+        set_enabled(true);
+        let mut line = StyledText::new();
+        line.push(Red.underline().paint("a"));
+        line.push(Red.underline().paint("b"));
+        let line = line + "c" + Blue.paint("d");
+
+        assert_eq!(line.to_string(),
+                   "\x1b[4;31ma\x1b[0m\x1b[4;31mb\x1b[0mc\x1b[34md\x1b[0m");
+        assert_eq!(without_color(|| line.to_string()), "abcd");
+        assert_eq!(StyledText::from(vec![Green.paint("x")]).to_string(), "\x1b[32mx\x1b[0m");
+    }
+
+    #[test]
+    fn add_plain_text() {
+        set_enabled(true);
+        let line = Red.paint("a") + "b" + Red.paint("c");
+
+        assert_eq!(line.unstyled(), "abc");
+        assert_eq!(line.segments()[1].style(), Style::default());
+        assert_eq!(line.to_string(), "\x1b[31ma\x1b[0mb\x1b[31mc\x1b[0m");
+        assert_eq!(without_color(|| line.to_string()), "abc");
+    }
+
+    #[test]
+    fn collect_styled_strings() {
+        set_enabled(true);
+        let line: StyledStrings = vec![Green.paint("x"), Green.paint("y")].into_iter().collect();
+
+        assert_eq!(line.to_string(), "\x1b[32mxy\x1b[0m");
+        assert_eq!(StyledStrings::new().to_string(), "");
+    }
+
+    #[test]
+    fn render_for_each_color_support_level() {
+        set_enabled(true);
+        let string = Rgb(255, 128, 0).paint("x");
+
+        assert_eq!(string.render_for(ColorSupport::TrueColor), "\x1b[38;2;255;128;0mx\x1b[0m");
+        assert_eq!(string.render_for(ColorSupport::Ansi256), "\x1b[38;5;208mx\x1b[0m");
+        assert_eq!(string.render_for(ColorSupport::Ansi16), "\x1b[33mx\x1b[0m");
+        assert_eq!(string.render_for(ColorSupport::None), "x");
+    }
+
+    #[test]
+    fn downgrade_for_each_color_support_level() {
+        let colors = [Rgb(10, 10, 200), Fixed(196), BrightGreen, Red];
+
+        let downgrade = |level| colors.iter().map(|c| c.downgrade(level)).collect::<Vec<_>>();
+        assert_eq!(downgrade(ColorSupport::TrueColor), colors);
+        assert_eq!(downgrade(ColorSupport::Ansi256), [Fixed(20), Fixed(196), BrightGreen, Red]);
+        assert_eq!(downgrade(ColorSupport::Ansi16), [Blue, BrightRed, BrightGreen, Red]);
+        assert_eq!(downgrade(ColorSupport::None), colors);
+    }
+
+    #[test]
+    fn ansi16_downgrade_uses_bright_colors() {
+        let ansi16 = |color: Color| color.downgrade(ColorSupport::Ansi16);
+
+        assert_eq!(ansi16(Rgb(255, 255, 255)), BrightWhite);
+        assert_eq!(ansi16(Rgb(229, 229, 229)), White);
+        assert_eq!(ansi16(Fixed(8)), BrightBlack);
+        assert_eq!(ansi16(Rgb(10, 10, 10)), Black);
+        assert_eq!(Rgb(255, 255, 255).nearest_named(), White);
+    }
+
+    #[test]
+    fn downgraded_for_each_color_support_level() {
+        set_enabled(true);
+        let string = Rgb(255, 128, 0).on(Fixed(21)).bold().paint("x");
+        let downgraded = |level| string.clone().downgraded_to(level);
+
+        assert_eq!(downgraded(ColorSupport::TrueColor), string);
+        assert_eq!(downgraded(ColorSupport::Ansi256), Fixed(208).on(Fixed(21)).bold().paint("x"));
+        assert_eq!(downgraded(ColorSupport::Ansi16), Yellow.on(Blue).bold().paint("x"));
+        assert_eq!(downgraded(ColorSupport::None), Style::new().bold().paint("x"));
+        assert_eq!(downgraded(ColorSupport::Ansi16).to_string(), "\x1b[1;33;44mx\x1b[0m");
+    }
+
+    #[test]
+    fn render_for_keeps_properties() {
+        set_enabled(true);
+        let string = Rgb(0, 0, 0).on(Fixed(231)).underline().paint("x");
+
+        assert_eq!(string.render_for(ColorSupport::Ansi256), "\x1b[4;38;5;16;48;5;231mx\x1b[0m");
+        assert_eq!(string.render_for(ColorSupport::Ansi16), "\x1b[4;30;107mx\x1b[0m");
+    }
+
+    #[test]
+    fn adapt_rgb_greys_to_greyscale_ramp() {
+        assert_eq!(Rgb(128, 128, 128).normal().adapt(ColorSupport::Ansi256), Fixed(244).normal());
+        assert_eq!(Rgb(255, 255, 255).normal().adapt(ColorSupport::Ansi256), Fixed(231).normal());
+    }
+
+    #[test]
+    fn with_attributes_matches_builders() {
+        let style = Style::new().with_attributes(&[Attribute::Bold, Attribute::Underline]);
+        assert_eq!(style, Style::new().bold().underline());
+
+        let style = Red.normal().with_attributes(&[Attribute::Italic, Attribute::Hidden]);
+        assert_eq!(style, Red.italic().hidden());
+    }
+
+    #[test]
+    fn selected_swaps_colors() {
+        assert_eq!(Green.on(Black).as_selected(), Black.on(Green));
+        assert_eq!(Green.bold().as_selected(), Style::new().background(Green).bold());
+    }
+
+    #[test]
+    fn selected_plain_style_is_reversed() {
+        assert_eq!(Style::new().as_selected(), Style::new().reverse());
+        assert_eq!(Style::new().underline().as_selected(), Style::new().underline().reverse());
+        assert_eq!(Style::new().reverse().as_selected(), Style::new());
+    }
+
+    #[test]
+    fn active_attributes_in_order() {
+        assert_eq!(Style::new().italic().bold().active_attributes(),
+                   [Attribute::Bold, Attribute::Italic]);
+        assert_eq!(Red.normal().active_attributes(), []);
+
+        let all = [Attribute::Bold, Attribute::Dimmed, Attribute::Italic, Attribute::Underline,
+                   Attribute::Blink, Attribute::Reverse, Attribute::Hidden,
+                   Attribute::Strikethrough];
+        assert_eq!(Style::new().with_attributes(&all).active_attributes(), all);
+    }
+
+    #[test]
+    fn with_no_attributes() {
+        assert_eq!(Blue.bold().with_attributes(&[]), Blue.bold());
+    }
+
+    #[test]
+    fn strip_osc_title() {
+        assert_eq!(strip_osc("\x1b]0;My title\x07text"), "text");
+        assert_eq!(strip_osc("\x1b]2;My title\x1b\\text"), "text");
+    }
+
+    #[test]
+    fn strip_osc_hyperlink_keeps_text() {
+        let link = "see \x1b]8;;https://example.com\x1b\\the docs\x1b]8;;\x1b\\.";
+        assert_eq!(strip_osc(link), "see the docs.");
+    }
+
+    #[test]
+    fn strip_osc_keeps_colors() {
+        set_enabled(true);
+        let link = format!("\x1b]8;;https://example.com\x07{}\x1b]8;;\x07", Red.paint("red"));
+        assert_eq!(strip_osc(&link), "\x1b[31mred\x1b[0m");
+    }
+
+    #[test]
+    fn strip_ansi_removes_everything() {
+        let link = format!("\x1b]8;;https://example.com\x07{}\x1b]8;;\x07", Red.paint("red"));
+        assert_eq!(strip_ansi(&link), "red");
+    }
+
+    #[test]
+    fn strip_sgr_keeps_hyperlinks() {
+        let link = format!("\x1b]8;;https://example.com\x07{}\x1b]8;;\x07", Red.paint("red"));
+        assert_eq!(strip_sgr(&link), "\x1b]8;;https://example.com\x07red\x1b]8;;\x07");
+        assert_eq!(strip_sgr(&Red.on(Black).bold().paint("hi").to_string()), "hi");
+    }
+
+    #[test]
+    fn strip_sgr_with_truncated_escapes() {
+        assert_eq!(strip_sgr("\x1b[1ma\x1b[0mb\x1b"), "ab\x1b");
+        assert_eq!(strip_sgr("a\x1b[31"), "a\x1b[31");
+        assert_eq!(strip_ansi("a\x1b[1mb\x1b"), "ab");
+    }
+
+    #[test]
+    fn strip_without_escapes_borrows() {
+        assert!(matches!(strip_osc("\x1b[1mplain"), Cow::Borrowed(_)));
+        assert!(matches!(strip_ansi("plain"), Cow::Borrowed(_)));
+        assert!(matches!(strip_sgr("\x1b]0;title\x07plain"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn palette_iterators() {
+        let palette: Vec<Color> = Color::palette_256().collect();
+        assert_eq!(palette.len(), 256);
+        assert_eq!((palette[0], palette[255]), (Fixed(0), Fixed(255)));
+
+        let named: Vec<Color> = Color::named().collect();
+        assert_eq!(named.len(), 8);
+        assert_eq!((named[0], named[7]), (Black, White));
+    }
+
+    #[test]
+    fn swatch_has_every_background() {
+        set_enabled(true);
+        let swatch = swatch_256();
+
+        let backgrounds: HashSet<&str> = ::ansi::segments(&swatch)
+            .filter_map(|segment| match segment {
+                ::ansi::Segment::Escape(escape) => escape.find("48;5;").map(|i| &escape[i..]),
+                ::ansi::Segment::Text(_) => None,
+            })
+            .collect();
+
+        assert_eq!(backgrounds.len(), 256);
+        // Two rows of standard colours, twelve of the cube and two of greys,
+        // with a blank line between each section.
+        assert_eq!(swatch.lines().count(), 2 + 12 + 2 + 2);
+    }
+
+    #[test]
+    fn readable_foreground_on_extremes() {
+        assert_eq!(White.readable_foreground(), Black);
+        assert_eq!(Black.readable_foreground(), White);
+        assert_eq!(Fixed(231).readable_foreground(), Black);
+        assert_eq!(Fixed(232).readable_foreground(), White);
+    }
+
+    #[test]
+    fn write_styled_matches_display() {
+        let string = Red.on(Blue).bold().paint("styled");
+
+        let mut output = Vec::new();
+        output.write_styled(&string).unwrap();
+        output.writeln_styled(&string).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), format!("{0}{0}\n", string));
+    }
+
+    #[test]
+    fn color_writer_enabled_keeps_codes() {
+        set_enabled(true);
+        let mut writer = ColorWriter::new(Vec::new(), true);
+        writer.write_styled(&Red.paint("a")).unwrap();
+
+        assert_eq!(writer.into_inner(), b"\x1b[31ma\x1b[0m");
+    }
+
+    #[test]
+    fn color_writer_disabled_removes_codes() {
+        let mut writer = ColorWriter::new(Vec::new(), false);
+        writer.write_styled(&Red.paint("a")).unwrap();
+        writer.writeln_styled(&Blue.underline().paint("b")).unwrap();
+
+        assert_eq!(writer.into_inner(), b"ab\n");
+    }
+
+    #[test]
+    fn distance_between_colors() {
+        assert_eq!(Rgb(12, 34, 56).distance(Rgb(12, 34, 56)), 0.0);
+        assert_eq!(Red.distance(Fixed(1)), 0.0);
+
+        let red = Rgb(255, 0, 0);
+        assert!(red.distance(Rgb(255, 165, 0)) < red.distance(Rgb(0, 0, 255)));
+        assert_eq!(red.distance(Blue), Blue.distance(red));
+    }
+
+    #[test]
+    fn nearest_named_color() {
+        assert_eq!(Fixed(196).nearest_named(), Red);
+        assert_eq!(Rgb(10, 10, 10).nearest_named(), Black);
+        assert_eq!(Fixed(6).nearest_named(), Cyan);
+        assert_eq!(Purple.nearest_named(), Purple);
+    }
+
+    #[test]
+    fn bright_colors_byte_len() {
+        set_enabled(true);
+        let styles = [BrightRed.normal(), Red.on(BrightYellow), BrightPurple.on(BrightGreen).bold()];
+
+        for &style in &styles {
+            assert_eq!(style.byte_len(), style.paint("").to_string().len());
+        }
+    }
+
+    #[test]
+    fn bright_colors_match_fixed_palette() {
+        let bright = [BrightBlack, BrightRed, BrightGreen, BrightYellow, BrightBlue, BrightPurple,
+                      BrightCyan, BrightWhite];
+
+        for (i, color) in bright.iter().enumerate() {
+            assert_eq!(color.to_rgb(), Fixed(8 + i as u8).to_rgb());
+            assert_eq!(color.nearest_named(), *color);
+            assert_eq!(color.downgrade(ColorSupport::Ansi16), *color);
+        }
+
+        assert_eq!(BrightRed.name(), Some("bright red"));
+    }
+
+    #[test]
+    fn names_of_colors() {
+        assert_eq!(White.name(), Some("white"));
+        assert_eq!(Purple.name(), Some("purple"));
+        assert_eq!(Fixed(7).name(), None);
+        assert_eq!(Rgb(255, 255, 255).name(), None);
+    }
+
+    #[test]
+    fn tracker_writes_repeated_style_once() {
+        set_enabled(true);
+        let mut tracker = StyleTracker::new();
+
+        assert_eq!(tracker.set(Red.normal()), "\x1b[31m");
+        assert_eq!(tracker.set(Red.normal()), "");
+        assert_eq!(tracker.set(Red.bold()), "\x1b[1m");
+        assert_eq!(tracker.current(), Red.bold());
+        assert_eq!(tracker.set(Blue.normal()), "\x1b[0;34m");
+    }
+
+    #[test]
+    fn tracker_resets_only_when_open() {
+        set_enabled(true);
+        let mut tracker = StyleTracker::new();
+        assert_eq!(tracker.reset(), "");
+
+        tracker.set(Red.underline());
+        assert_eq!(tracker.reset(), "\x1b[0m");
+        assert_eq!(tracker.reset(), "");
+        assert_eq!(tracker.set(Style::default()), "");
+    }
+
+    #[test]
+    fn unset_properties_and_colors() {
+        set_enabled(true);
+        assert_eq!(Red.bold().not_bold().paint("x").to_string(), "\x1b[31mx\x1b[0m");
+        assert_eq!(Red.on(Blue).without_background(), Red.normal());
+        assert_eq!(Red.on(Blue).without_foreground(), Style::new().background(Blue));
+        assert_eq!(Red.italic().underline().not_italic(), Red.underline());
+        assert_eq!(Red.normal().not_strikethrough(), Red.normal());
+    }
+
+    #[test]
+    fn combine_overrides_colors_and_keeps_properties() {
+        assert_eq!(Red.bold().combine(&Blue.normal()), Blue.bold());
+        assert_eq!(Red.on(Black).combine(&Style::new().italic()), Red.on(Black).italic());
+        assert_eq!(Style::new().combine(&Style::new()), Style::new());
+
+        let sub = Blue.normal().subscript();
+        assert_eq!(Red.normal().superscript() + sub, sub);
+    }
+
+    #[test]
+    fn common_style_of_bold_styles() {
+        let styles = [Red.bold(), Blue.bold().italic(), Green.on(Red).bold()];
+        assert_eq!(Style::common(&styles), Style::new().bold());
+    }
+
+    #[test]
+    fn common_style_keeps_shared_colours() {
+        let styles = [Red.on(Blue), Red.on(Blue).underline(), Green.on(Blue).underline()];
+        assert_eq!(Style::common(&styles), Style::new().background(Blue));
+        assert_eq!(Style::common(&[Red.bold()]), Red.bold());
+    }
+
+    #[test]
+    fn common_style_of_disjoint_styles_is_plain() {
+        assert_eq!(Style::common(&[Red.bold(), Blue.italic(), Style::new().background(Red)]),
+                   Style::new());
+        assert_eq!(Style::common(&[]), Style::new());
+    }
+
+    const SUBTLE: Color = Adaptive { on_dark: &Fixed(250), on_light: &Fixed(238) };
+
+    #[test]
+    fn adaptive_colors_resolve_by_background() {
+        assert_eq!(SUBTLE.resolve(true), Fixed(250));
+        assert_eq!(SUBTLE.resolve(false), Fixed(238));
+        assert_eq!(Red.resolve(false), Red);
+
+        let nested = Adaptive { on_dark: &SUBTLE, on_light: &Blue };
+        assert_eq!(nested.resolve(true), Fixed(250));
+        assert_eq!(nested.resolve(false), Blue);
+    }
+
+    #[test]
+    fn adaptive_colors_render_for_forced_background() {
+        set_enabled(true);
+        let style = Red.on(SUBTLE);
+
+        set_dark_background(true);
+        assert!(dark_background());
+        assert_eq!(adaptive(Red, SUBTLE), Red);
+        assert_eq!(SUBTLE.paint("!").to_string(), "\x1b[38;5;250m!\x1b[0m");
+        assert_eq!(style.paint("!").to_string(), "\x1b[31;48;5;250m!\x1b[0m");
+        assert_eq!(SUBTLE.downgrade(ColorSupport::Ansi16), White);
+
+        set_dark_background(false);
+        assert!(!dark_background());
+        assert_eq!(adaptive(Red, SUBTLE), Fixed(238));
+        assert_eq!(SUBTLE.paint("!").to_string(), "\x1b[38;5;238m!\x1b[0m");
+        assert_eq!(style.byte_len(), "\x1b[31;48;5;238m\x1b[0m".len());
+        assert_eq!(SUBTLE.to_rgb(), (68, 68, 68));
+    }
+
+    #[test]
+    fn blink_policies() {
+        set_enabled(true);
+        let style = Red.blink().underline();
+        let render = |policy| style.with_blink_policy(policy).paint("TEST").to_string();
+
+        assert_eq!(render(BlinkPolicy::Emit), "\x1b[4;5;31mTEST\x1b[0m");
+        assert_eq!(render(BlinkPolicy::Strip), "\x1b[4;31mTEST\x1b[0m");
+        assert_eq!(render(BlinkPolicy::BoldSubstitute), "\x1b[1;4;31mTEST\x1b[0m");
+    }
+
+    #[test]
+    fn blink_policies_without_blink() {
+        set_enabled(true);
+        let style = Red.bold();
+
+        assert_eq!(style.with_blink_policy(BlinkPolicy::Strip), style);
+        assert_eq!(style.with_blink_policy(BlinkPolicy::BoldSubstitute), style);
+        assert_eq!(Blue.blink().with_blink_policy(BlinkPolicy::Strip).paint("TEST").to_string(),
+                   "\x1b[34mTEST\x1b[0m");
+    }
+
+    #[test]
+    fn center_with_even_remainder() {
+        set_enabled(true);
+        assert_eq!(Red.paint("ab").center_in(6), "  \x1b[31mab\x1b[0m  ");
+    }
+
+    #[test]
+    fn center_with_odd_remainder() {
+        set_enabled(true);
+        assert_eq!(Red.paint("ab").center_in(7), "  \x1b[31mab\x1b[0m   ");
+        assert_eq!(Style::new().paint("日本").center_in(5), "日本 ");
+    }
+
+    #[test]
+    fn center_wider_than_width() {
+        set_enabled(true);
+        assert_eq!(Red.paint("abc").center_in(2), "\x1b[31mabc\x1b[0m");
+    }
+
+    #[test]
+    fn sanitized_neutralises_escapes() {
+        set_enabled(true);
+        let styled = Red.bold().paint("a\x1b[32mb\x07c").sanitized();
+
+        assert_eq!(styled.style(), Red.bold());
+        assert_eq!(styled.to_string(), "\x1b[1;31ma^[[32mb^Gc\x1b[0m");
+    }
+
+    #[test]
+    fn sanitized_other_control_characters() {
+        let sanitized = |s| Style::new().paint(s).sanitized().to_string();
+
+        assert_eq!(sanitized("\0\r\x7f\u{9b}31m"), "^@^M^?\\x9b31m");
+        assert_eq!(sanitized("tab\tline\n"), "tab\tline\n");
+    }
+
+    #[test]
+    fn sanitized_borrows_clean_string() {
+        match Red.paint("clean").sanitized().string {
+            Cow::Borrowed(s) => assert_eq!(s, "clean"),
+            Cow::Owned(_) => panic!("clean string was copied"),
+        }
+    }
+
+    #[test]
+    fn paint_adaptive_at_each_level() {
+        set_enabled(true);
+        let color = Rgb(250, 10, 10);
+        let paint = |level| color.paint_adaptive("TEST", level).to_string();
+
+        assert_eq!(paint(ColorSupport::TrueColor), "\x1b[38;2;250;10;10mTEST\x1b[0m");
+        assert_eq!(paint(ColorSupport::Ansi256), "\x1b[38;5;196mTEST\x1b[0m");
+        assert_eq!(paint(ColorSupport::Ansi16), "\x1b[91mTEST\x1b[0m");
+        assert_eq!(paint(ColorSupport::None), "TEST");
+    }
+
+    #[test]
+    fn map_colors_with_identity() {
+        let style = Red.on(Fixed(17)).italic().underline();
+        assert_eq!(style.map_colors(|color| color), style);
+        assert_eq!(Style::new().bold().map_colors(|_| Blue), Style::new().bold());
+    }
+
+    #[test]
+    fn map_colors_darkens_both_colors() {
+        // Blends each colour 50% towards black.
+        let darken = |color: Color| {
+            let (r, g, b) = color.to_rgb();
+            Rgb(r / 2, g / 2, b / 2)
+        };
+
+        let style = Rgb(200, 100, 50).on(Rgb(10, 20, 30)).bold();
+        assert_eq!(style.map_colors(darken), Rgb(100, 50, 25).on(Rgb(5, 10, 15)).bold());
+
+        let foreground_only = Rgb(200, 100, 50).normal().map_colors(darken);
+        assert_eq!(foreground_only, Rgb(100, 50, 25).normal());
+    }
+
+    #[test]
+    fn prefix_and_suffix() {
+        set_enabled(true);
+        assert_eq!(Red.bold().prefix(), "\x1b[1;31m");
+        assert_eq!(Red.bold().suffix(), "\x1b[0m");
+        assert_eq!(Style::new().prefix(), "");
+        assert_eq!(Style::new().suffix(), "");
+
+        let style = Rgb(0, 45, 255).on(Fixed(220)).italic();
+        assert_eq!(style.prefix() + "x" + &style.suffix(), style.paint("x").to_string());
+        assert_eq!(style.prefix().len() + style.suffix().len(), style.byte_len());
+    }
+
+    #[test]
+    fn byte_len_of_plain_style() {
+        assert_eq!(Style::new().byte_len(), 0);
+    }
+
+    #[test]
+    fn byte_len_matches_rendered_codes() {
+        set_enabled(true);
+        let styles = [
+            Red.normal(),
+            Red.bold(),
+            White.on(Black).underline().blink(),
+            Fixed(7).on(Fixed(220)),
+            Rgb(0, 45, 255).on(Rgb(100, 5, 10)).italic(),
+            Style::new().dimmed().reverse().hidden(),
+            Style::new().strikethrough(),
+            Red.on(Blue).underline().strikethrough(),
+            BrightRed.on(BrightBlack),
+            BrightWhite.on(Fixed(200)).bold(),
+            Red.normal().superscript(),
+            Style::new().subscript().italic(),
+        ];
+
+        for style in &styles {
+            assert_eq!(style.byte_len(), style.paint("").to_string().len(), "{:?}", style);
+            assert_eq!(style.byte_len(), style.prefix().len() + style.suffix().len(),
+                       "{:?}", style);
+        }
+    }
+}
@@ -0,0 +1,317 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Parsing text that already contains SGR escape codes back into styled
+//! strings.
+
+use std::borrow::Cow;
+
+use ansi::{self, Segment};
+
+use super::{Color, Style, StyledString};
+
+/// Parses `input`, such as the captured output of another program, into a
+/// list of styled strings by interpreting its SGR (colour and property)
+/// escape codes.
+///
+/// Every code that sets a property or colour is understood, along with the
+/// codes that turn a single property off again, such as 24 for underline or
+/// 28 for hidden, and the full reset. The experimental codes 73 and 74 for
+/// superscript and subscript are understood too, along with 75 for neither.
+/// Colon-separated sub-parameters, such as `4:3` for a curly underline, are
+/// read as part of the code before them, and parameters that are not valid
+/// are ignored. Other escape sequences, such as cursor movement and OSC
+/// hyperlinks, are removed. Consecutive runs of text with the same style are
+/// merged into one `StyledString`.
+///
+/// ```
+/// use tutil::crayon;
+/// use tutil::crayon::Color::Red;
+///
+/// let segments = crayon::parse_ansi("plain \x1b[1;31mbold red\x1b[0m");
+///
+/// assert_eq!(&*segments[0], "plain ");
+/// assert_eq!(segments[1].style(), Red.bold());
+/// ```
+pub fn parse_ansi(input: &str) -> Vec<StyledString<'_>> {
+    let mut output: Vec<StyledString> = Vec::new();
+    let mut style = Style::default();
+
+    for segment in ansi::segments(input) {
+        match segment {
+            Segment::Escape(escape) => {
+                if escape.starts_with("\x1b[") && escape.ends_with('m') {
+                    style = apply_sgr(style, &escape[2..escape.len() - 1]);
+                }
+            }
+            Segment::Text(text) => {
+                match output.last_mut() {
+                    Some(last) if last.style == style => last.string.to_mut().push_str(text),
+                    _ => output.push(StyledString { string: Cow::Borrowed(text), style }),
+                }
+            }
+        }
+    }
+
+    output
+}
+
+/// Returns `style` with the SGR parameters in `params`, such as `1;31`,
+/// applied to it.
+///
+/// Sub-parameters separated by colons belong to the code before them, as in
+/// `4:3` for a curly underline or `38:2::255:0:0` for an `Rgb` foreground.
+/// Underline colours, set by code 58, are read so that their parameters are
+/// skipped, but are not kept.
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let mut params = params.split(';');
+
+    while let Some(param) = params.next() {
+        let mut parts = param.split(':');
+
+        // An empty parameter is treated as 0, as terminals do, but one that is
+        // not a number, or too large to be a code, is ignored.
+        let code = match parts.next() {
+            Some("") | None => 0,
+            Some(code) => match code.parse::<u8>() {
+                Ok(code) => code,
+                Err(_) => continue,
+            },
+        };
+        let sub: Vec<&str> = parts.collect();
+
+        match code {
+            0 => style = Style::default(),
+            1 => style.bold = true,
+            2 => style.dimmed = true,
+            3 => style.italic = true,
+            // `4:0` turns underline off, and the other styles, such as `4:3`
+            // for a curly underline, are all shown as an underline.
+            4 => style.underline = sub.first() != Some(&"0"),
+            5 | 6 => style.blink = true,
+            7 => style.reverse = true,
+            8 => style.hidden = true,
+            9 => style.strikethrough = true,
+            22 => {
+                style.bold = false;
+                style.dimmed = false;
+            }
+            23 => style.italic = false,
+            24 => style.underline = false,
+            25 => style.blink = false,
+            27 => style.reverse = false,
+            28 => style.hidden = false,
+            29 => style.strikethrough = false,
+            n @ 30..=37 => style.foreground = Some(standard(n - 30)),
+            38 => {
+                if let Some(color) = extended_color(&sub, &mut params) {
+                    style.foreground = Some(color);
+                }
+            }
+            39 => style.foreground = None,
+            n @ 40..=47 => style.background = Some(standard(n - 40)),
+            48 => {
+                if let Some(color) = extended_color(&sub, &mut params) {
+                    style.background = Some(color);
+                }
+            }
+            49 => style.background = None,
+            58 => {
+                let _ = extended_color(&sub, &mut params);
+            }
+            73 => style = style.superscript(),
+            74 => style = style.subscript(),
+            75 => {
+                style.superscript = false;
+                style.subscript = false;
+            }
+            n @ 90..=97 => style.foreground = Some(bright(n - 90)),
+            n @ 100..=107 => style.background = Some(bright(n - 100)),
+            _ => {}
+        }
+    }
+
+    style
+}
+
+/// Reads the colour given to a `38`, `48` or `58` code, which is either `5`
+/// and `n` for a `Fixed` colour or `2`, `r`, `g` and `b` for an `Rgb` one.
+///
+/// The colour is taken from the code's sub-parameters, `sub`, if it has any,
+/// as in `38:5:208`, or otherwise from the parameters after it, as in
+/// `38;5;208`. `None` is returned if the colour is not valid.
+fn extended_color<'a, I>(sub: &[&str], params: &mut I) -> Option<Color>
+    where I: Iterator<Item = &'a str>
+{
+    fn number(s: &str) -> Option<u8> {
+        s.parse().ok()
+    }
+
+    fn rgb(r: Option<u8>, g: Option<u8>, b: Option<u8>) -> Option<Color> {
+        match (r, g, b) {
+            (Some(r), Some(g), Some(b)) => Some(Color::Rgb(r, g, b)),
+            _ => None,
+        }
+    }
+
+    if !sub.is_empty() {
+        return match *sub {
+            ["5", n] => number(n).map(Color::Fixed),
+            // The colour space that may come before the components is ignored,
+            // as it almost always is by terminals.
+            ["2", _, r, g, b] | ["2", r, g, b] => rgb(number(r), number(g), number(b)),
+            _ => None,
+        };
+    }
+
+    match params.next() {
+        Some("5") => params.next().and_then(number).map(Color::Fixed),
+        Some("2") => {
+            let (r, g, b) = (params.next(), params.next(), params.next());
+            rgb(r.and_then(number), g.and_then(number), b.and_then(number))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the named colour with the given index, as in codes 30 to 37 and
+/// 40 to 47.
+fn standard(index: u8) -> Color {
+    Color::named().nth(index as usize).unwrap_or(Color::Fixed(index))
+}
+
+/// Returns the bright colour with the given index, as in codes 90 to 97 and
+/// 100 to 107.
+fn bright(index: u8) -> Color {
+    use super::Color::*;
+
+    match index {
+        0 => BrightBlack,
+        1 => BrightRed,
+        2 => BrightGreen,
+        3 => BrightYellow,
+        4 => BrightBlue,
+        5 => BrightPurple,
+        6 => BrightCyan,
+        7 => BrightWhite,
+        _ => Fixed(index + 8),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::Color::*;
+    use super::super::set_enabled;
+
+    /// Returns the text and style of each segment, for comparing in tests.
+    fn parts<'a>(segments: &'a [StyledString]) -> Vec<(&'a str, Style)> {
+        segments.iter().map(|segment| (&*segment.string, segment.style)).collect()
+    }
+
+    #[test]
+    fn parse_plain_text() {
+        assert_eq!(parts(&parse_ansi("TEST")), [("TEST", Style::default())]);
+        assert!(parse_ansi("").is_empty());
+    }
+
+    #[test]
+    fn parse_round_trip() {
+        set_enabled(true);
+        let input = format!("{}{}", Red.on(Blue).bold().paint("a"), Fixed(208).italic().paint("b"));
+        assert_eq!(parts(&parse_ansi(&input)),
+                   [("a", Red.on(Blue).bold()), ("b", Fixed(208).italic())]);
+
+        let rgb = Rgb(1, 2, 3).paint("c").to_string();
+        assert_eq!(parts(&parse_ansi(&rgb)), [("c", Rgb(1, 2, 3).normal())]);
+    }
+
+    #[test]
+    fn parse_round_trip_reverse_and_hidden() {
+        set_enabled(true);
+        // Parsing follows the codes that terminals use, so a style only
+        // survives the round trip if it is written with those codes too.
+        for &style in &[Style::new().blink(), Style::new().reverse(), Style::new().hidden(),
+                        Red.on(Blue).blink().reverse().hidden()] {
+            let rendered = style.paint("x").to_string();
+            assert_eq!(parts(&parse_ansi(&rendered)), [("x", style)], "{:?}", rendered);
+        }
+    }
+
+    #[test]
+    fn disable_underline_only() {
+        let segments = parse_ansi("\x1b[1;4mboth\x1b[24mbold\x1b[22mplain");
+
+        assert_eq!(parts(&segments), [("both", Style::new().bold().underline()),
+                                      ("bold", Style::new().bold()),
+                                      ("plain", Style::default())]);
+    }
+
+    #[test]
+    fn conceal_and_reveal() {
+        let segments = parse_ansi("\x1b[31;8msecret\x1b[28mshown");
+        assert_eq!(parts(&segments), [("secret", Red.hidden()), ("shown", Red.normal())]);
+    }
+
+    #[test]
+    fn every_disable_code() {
+        let all = "\x1b[1;2;3;4;5;7;8;9;31;42m";
+
+        assert_eq!(apply_sgr(Style::default(), "1;2;22"), Style::default());
+        assert_eq!(parts(&parse_ansi(&format!("{}a\x1b[22;23;24;25;27;28;29;39;49mb", all))),
+                   [("a", Red.on(Green).bold().dimmed().italic().underline().blink().reverse()
+                              .hidden().strikethrough()),
+                    ("b", Style::default())]);
+    }
+
+    #[test]
+    fn superscript_and_subscript() {
+        assert_eq!(apply_sgr(Style::default(), "73"), Style::new().superscript());
+        assert_eq!(apply_sgr(Style::default(), "73;74"), Style::new().subscript());
+        assert_eq!(apply_sgr(Style::new().bold().superscript(), "75"), Style::new().bold());
+    }
+
+    #[test]
+    fn bright_and_default_colors() {
+        assert_eq!(apply_sgr(Red.on(Blue), "39;49"), Style::default());
+        assert_eq!(apply_sgr(Style::default(), "91;104"), BrightRed.on(BrightBlue));
+        assert_eq!(apply_sgr(Style::default(), "90;107"), BrightBlack.on(BrightWhite));
+        assert_eq!(apply_sgr(Style::default(), "38;5;1"), Fixed(1).normal());
+    }
+
+    #[test]
+    fn underline_styles_are_sub_parameters() {
+        assert_eq!(apply_sgr(Style::default(), "4:3"), Style::new().underline());
+        assert_eq!(apply_sgr(Style::new().underline(), "4:0"), Style::default());
+        assert_eq!(apply_sgr(Style::default(), "1;4:3;31"), Red.bold().underline());
+    }
+
+    #[test]
+    fn underline_colors_are_skipped() {
+        assert_eq!(apply_sgr(Style::default(), "58;5;1"), Style::default());
+        assert_eq!(apply_sgr(Style::default(), "58;2;255;0;0;1"), Style::new().bold());
+        assert_eq!(apply_sgr(Red.bold(), "58:2::255:0:0"), Red.bold());
+        assert_eq!(apply_sgr(Red.bold(), "4;58:5:9;59"), Red.bold().underline());
+    }
+
+    #[test]
+    fn colon_separated_colors() {
+        assert_eq!(apply_sgr(Style::default(), "38:5:208"), Fixed(208).normal());
+        assert_eq!(apply_sgr(Style::default(), "38:2::1:2:3;48:2:4:5:6"),
+                   Rgb(1, 2, 3).on(Rgb(4, 5, 6)));
+    }
+
+    #[test]
+    fn invalid_parameters_are_ignored() {
+        assert_eq!(apply_sgr(Red.bold(), "x;300"), Red.bold());
+        assert_eq!(apply_sgr(Red.bold(), "38;5;256"), Red.bold());
+        assert_eq!(apply_sgr(Red.bold(), ";4"), Style::new().underline());
+    }
+
+    #[test]
+    fn same_style_merged_and_other_escapes_removed() {
+        let segments = parse_ansi("\x1b[31ma\x1b[1Kb\x1b]8;;https://example.com\x07c\x1b[0m\x1b[m");
+        assert_eq!(parts(&segments), [("abc", Red.normal())]);
+    }
+}
@@ -0,0 +1,259 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Rendering styled strings to the Windows console.
+//!
+//! Windows 10 and later can interpret ANSI escape codes once virtual terminal
+//! processing has been enabled for the console, but older consoles (such as
+//! `cmd.exe` on Windows 7) can only be styled through the console API by
+//! setting the attribute word that applies to text as it is written.
+//! [`WindowsRenderer`] picks between the two when it is created.
+//!
+//! [`WindowsRenderer`]: struct.WindowsRenderer.html
+
+use std::io::{self, Write};
+use std::mem;
+
+use winapi::{HANDLE, DWORD, WORD, STD_OUTPUT_HANDLE, COORD, SMALL_RECT, CONSOLE_SCREEN_BUFFER_INFO};
+use winapi::{FOREGROUND_RED, FOREGROUND_GREEN, FOREGROUND_BLUE, FOREGROUND_INTENSITY};
+use winapi::COMMON_LVB_UNDERSCORE;
+use kernel32::{GetStdHandle, GetConsoleMode, SetConsoleMode, GetConsoleScreenBufferInfo};
+use kernel32::SetConsoleTextAttribute;
+
+use super::{colors_enabled, Color, Style, StyledString};
+use super::Color::*;
+
+/// Makes the console interpret ANSI escape codes written to it. Not yet
+/// defined by `winapi`.
+const ENABLE_VIRTUAL_TERMINAL_PROCESSING: DWORD = 0x0004;
+
+/// The bits of an attribute word that hold the foreground colour.
+const FOREGROUND_MASK: WORD = 0x000f;
+
+/// Renders styled strings to the console attached to standard output.
+///
+/// If virtual terminal processing can be enabled, strings are written with
+/// ANSI escape codes exactly as they are on other platforms. Otherwise each
+/// string's `Style` is translated into a console attribute word, which is set
+/// with `SetConsoleTextAttribute()` while the string is written and then
+/// restored.
+///
+/// The legacy console has no equivalent of italic, blinking or struck through
+/// text, and only has sixteen colours, so `Fixed` and `Rgb` colours are shown
+/// as the closest of those sixteen.
+///
+/// ```no_run
+/// use tutil::crayon::Color::Red;
+/// use tutil::crayon::windows::WindowsRenderer;
+///
+/// let renderer = WindowsRenderer::new().unwrap();
+/// renderer.render(&Red.paint("Hello, cmd.exe!")).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct WindowsRenderer {
+    handle: HANDLE,
+    vt_enabled: bool,
+    default_attributes: WORD,
+}
+
+impl WindowsRenderer {
+    /// Creates a renderer for the console attached to standard output,
+    /// enabling virtual terminal processing if the console supports it.
+    ///
+    /// Fails if standard output is not a console.
+    pub fn new() -> io::Result<WindowsRenderer> {
+        let handle: HANDLE = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) };
+
+        let coord = COORD { X: 0, Y: 0 };
+        let mut csbi = CONSOLE_SCREEN_BUFFER_INFO {
+            dwSize: coord,
+            dwCursorPosition: coord,
+            wAttributes: 0,
+            srWindow: SMALL_RECT { Left: 0, Top: 0, Right: 0, Bottom: 0 },
+            dwMaximumWindowSize: coord,
+        };
+
+        if unsafe { GetConsoleScreenBufferInfo(handle, &mut csbi) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut mode: DWORD = 0;
+        let vt_enabled = unsafe {
+            GetConsoleMode(handle, &mut mode) != 0 &&
+                (mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0 ||
+                 SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0)
+        };
+
+        Ok(WindowsRenderer { handle, vt_enabled, default_attributes: csbi.wAttributes })
+    }
+
+    /// Returns true if the console interprets ANSI escape codes, or false if
+    /// it is a legacy console that is styled through the console API.
+    pub fn is_vt_enabled(&self) -> bool {
+        self.vt_enabled
+    }
+
+    /// Writes `string` to standard output in its style.
+    pub fn render(&self, string: &StyledString) -> io::Result<()> {
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+
+        if self.vt_enabled || !colors_enabled() {
+            try!(write!(stdout, "{}", string));
+            return stdout.flush();
+        }
+
+        // Anything still buffered must be written before the attributes are
+        // changed, or it would be shown in the wrong style.
+        try!(stdout.flush());
+
+        let attributes = attributes(&string.style, self.default_attributes);
+        if unsafe { SetConsoleTextAttribute(self.handle, attributes) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let result = stdout.write_all(string.string.as_bytes()).and_then(|_| stdout.flush());
+
+        if unsafe { SetConsoleTextAttribute(self.handle, self.default_attributes) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        result
+    }
+}
+
+/// Translates `style` into a console attribute word, keeping the colours of
+/// `default` wherever the style does not set one.
+fn attributes(style: &Style, default: WORD) -> WORD {
+    let mut foreground = style.foreground.map_or(default & FOREGROUND_MASK, color_bits);
+    let mut background = style.background.map_or((default >> 4) & FOREGROUND_MASK, color_bits);
+
+    if style.bold {
+        foreground |= FOREGROUND_INTENSITY as WORD;
+    } else if style.dimmed {
+        foreground &= !(FOREGROUND_INTENSITY as WORD);
+    }
+
+    if style.reverse {
+        mem::swap(&mut foreground, &mut background);
+    }
+
+    if style.hidden {
+        foreground = background;
+    }
+
+    let mut attributes = foreground | (background << 4);
+
+    if style.underline {
+        attributes |= COMMON_LVB_UNDERSCORE as WORD;
+    }
+
+    attributes
+}
+
+/// Returns the attribute bits for `color` as a foreground colour, which are
+/// shifted left by four bits to give the background bits.
+fn color_bits(color: Color) -> WORD {
+    let index = match color {
+        Black => 0,
+        Red => 1,
+        Green => 2,
+        Yellow => 3,
+        Blue => 4,
+        Purple => 5,
+        Cyan => 6,
+        White => 7,
+        BrightBlack => 8,
+        BrightRed => 9,
+        BrightGreen => 10,
+        BrightYellow => 11,
+        BrightBlue => 12,
+        BrightPurple => 13,
+        BrightCyan => 14,
+        BrightWhite => 15,
+        Fixed(n) if n < 16 => n,
+        Adaptive { .. } => return color_bits(color.resolved()),
+        _ => nearest_index(color.to_rgb()),
+    };
+
+    // The ANSI palette puts red in the lowest bit and blue in the third,
+    // whereas the console puts them the other way around.
+    let mut bits = 0;
+    if index & 1 != 0 { bits |= FOREGROUND_RED; }
+    if index & 2 != 0 { bits |= FOREGROUND_GREEN; }
+    if index & 4 != 0 { bits |= FOREGROUND_BLUE; }
+    if index & 8 != 0 { bits |= FOREGROUND_INTENSITY; }
+
+    bits as WORD
+}
+
+/// Returns the index of the colour out of the first sixteen `Fixed` colours
+/// that is closest to `(r, g, b)`.
+fn nearest_index((r, g, b): (u8, u8, u8)) -> u8 {
+    let distance = |n: u8| Fixed(n).distance(Rgb(r, g, b));
+
+    (0..16).min_by(|&a, &b| distance(a).total_cmp(&distance(b))).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use winapi::{BACKGROUND_RED, BACKGROUND_BLUE, BACKGROUND_INTENSITY};
+
+    /// Light grey on black, the usual default for `cmd.exe`.
+    const DEFAULT: WORD = 0x0007;
+
+    fn word(bits: DWORD) -> WORD {
+        bits as WORD
+    }
+
+    #[test]
+    fn plain_style_keeps_default() {
+        assert_eq!(attributes(&Style::new(), DEFAULT), DEFAULT);
+        assert_eq!(attributes(&Style::new(), 0x001e), 0x001e);
+    }
+
+    #[test]
+    fn named_colors() {
+        assert_eq!(attributes(&Red.normal(), DEFAULT), word(FOREGROUND_RED));
+        assert_eq!(attributes(&Yellow.on(Blue), DEFAULT),
+                   word(FOREGROUND_RED | FOREGROUND_GREEN | BACKGROUND_BLUE));
+        assert_eq!(attributes(&Style::new().background(Red), DEFAULT),
+                   DEFAULT | word(BACKGROUND_RED));
+    }
+
+    #[test]
+    fn bright_colors_set_intensity() {
+        assert_eq!(attributes(&BrightRed.normal(), DEFAULT),
+                   word(FOREGROUND_RED | FOREGROUND_INTENSITY));
+        assert_eq!(attributes(&Black.on(BrightBlack), DEFAULT), word(BACKGROUND_INTENSITY));
+    }
+
+    #[test]
+    fn bold_and_dimmed_set_intensity() {
+        assert_eq!(attributes(&Red.bold(), DEFAULT), word(FOREGROUND_RED | FOREGROUND_INTENSITY));
+        assert_eq!(attributes(&Style::new().dimmed(), 0x000f), DEFAULT);
+    }
+
+    #[test]
+    fn fixed_and_rgb_use_nearest_color() {
+        assert_eq!(attributes(&Fixed(9).normal(), DEFAULT),
+                   word(FOREGROUND_RED | FOREGROUND_INTENSITY));
+        assert_eq!(attributes(&Black.on(Rgb(250, 10, 240)), DEFAULT),
+                   word(BACKGROUND_RED | BACKGROUND_BLUE | BACKGROUND_INTENSITY));
+        assert_eq!(attributes(&Fixed(196).normal(), DEFAULT),
+                   word(FOREGROUND_RED | FOREGROUND_INTENSITY));
+    }
+
+    #[test]
+    fn reverse_hidden_and_underline() {
+        assert_eq!(attributes(&Red.on(Blue).reverse(), DEFAULT),
+                   word(FOREGROUND_BLUE | BACKGROUND_RED));
+        assert_eq!(attributes(&Red.on(Blue).hidden(), DEFAULT),
+                   word(FOREGROUND_BLUE | BACKGROUND_BLUE));
+        assert_eq!(attributes(&Style::new().underline(), DEFAULT),
+                   DEFAULT | word(COMMON_LVB_UNDERSCORE));
+    }
+}
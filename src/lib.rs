@@ -31,6 +31,11 @@
 #![warn(trivial_casts, trivial_numeric_casts)]
 #![warn(unused_extern_crates, unused_qualifications)]
 
+extern crate unicode_width;
+
+#[cfg(feature = "ansi_term")]
+extern crate ansi_term;
+
 #[cfg(unix)]
 extern crate libc;
 
@@ -39,5 +44,12 @@ extern crate winapi;
 #[cfg(windows)]
 extern crate kernel32;
 
+mod ansi;
+mod terminfo;
+
 pub mod crayon;
+pub mod progress;
+pub mod prompt;
 pub mod screen;
+pub mod system;
+pub mod text;
@@ -0,0 +1,554 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A module for showing the progress of long-running tasks, with a progress
+//! bar when the amount of work is known and a spinner when it is not.
+//!
+//! Both write to standard error by default, so that they do not mix with the
+//! program's output, but can be given any other writer. When standard error
+//! is not a terminal, such as when it is redirected to a file, nothing is
+//! drawn until the task is finished.
+//!
+//! # Basic Usage
+//!
+//! ```no_run
+//! use tutil::progress::Bar;
+//!
+//! let mut bar = Bar::new(100);
+//!
+//! for _ in 0..100 {
+//!     // Do some work...
+//!     bar.inc(1).unwrap();
+//! }
+//!
+//! bar.finish().unwrap();
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use crayon::visible_width;
+use screen::{self, Size, Width};
+use text::{default_glyphs, GlyphSet};
+
+/// The number of cells in a progress bar, not counting the brackets.
+const BAR_WIDTH: usize = 30;
+
+/// The frames of a spinner drawn with Unicode characters.
+const UNICODE_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// The frames of a spinner drawn with ASCII characters.
+const ASCII_FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+
+/// A progress bar for a task with a known amount of work.
+///
+/// The bar is redrawn in place every time the position changes, showing how
+/// much of the work is done along with a percentage, such as
+/// `[███████░░░░░░░░] 47%`.
+#[derive(Debug)]
+pub struct Bar<W: Write + Send = io::Stderr> {
+    writer: W,
+    total: u64,
+    position: u64,
+    glyphs: GlyphSet,
+    interactive: bool,
+    template: Option<Vec<Piece>>,
+    message: String,
+    started: Instant,
+}
+
+impl Bar<io::Stderr> {
+    /// Creates a progress bar for `total` units of work that draws to
+    /// standard error.
+    ///
+    /// If standard error is not a terminal, the bar is only drawn once, by
+    /// `finish()`.
+    pub fn new(total: u64) -> Bar<io::Stderr> {
+        let interactive = io::stderr().is_terminal();
+        Bar { interactive, ..Bar::with_writer(io::stderr(), total) }
+    }
+}
+
+impl<W: Write + Send> Bar<W> {
+    /// Creates a progress bar for `total` units of work that draws to
+    /// `writer`, which is always assumed to be able to redraw the bar in
+    /// place.
+    pub fn with_writer(writer: W, total: u64) -> Bar<W> {
+        Bar {
+            writer,
+            total,
+            position: 0,
+            glyphs: default_glyphs(),
+            interactive: true,
+            template: None,
+            message: String::new(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Sets the characters used to draw the bar, which default to
+    /// [`default_glyphs()`](../text/fn.default_glyphs.html).
+    pub fn set_glyphs(&mut self, glyphs: GlyphSet) -> &mut Bar<W> {
+        self.glyphs = glyphs;
+        self
+    }
+
+    /// Sets the layout of the bar, replacing the default of a 30-cell bar in
+    /// brackets followed by the percentage.
+    ///
+    /// The template is text containing any of these placeholders:
+    ///
+    /// * `{bar}`: the bar itself, which is as wide as the terminal allows
+    ///   after the rest of the template has been laid out.
+    /// * `{percent}`: the percentage of the work that is done, such as ` 47%`.
+    /// * `{pos}`: the number of units of work that are done.
+    /// * `{len}`: the total number of units of work.
+    /// * `{eta}`: the estimated time until the work is done, such as `01:05`.
+    /// * `{elapsed}`: the time since the bar was created.
+    /// * `{msg}`: the message set with `set_message()`.
+    ///
+    /// An error is returned, and the layout left unchanged, if the template
+    /// contains any other placeholder or a `{` without a matching `}`.
+    ///
+    /// ```
+    /// use tutil::progress::Bar;
+    ///
+    /// let mut bar = Bar::with_writer(Vec::new(), 100);
+    /// bar.template("{msg} [{bar}] {pos}/{len} ({eta})").unwrap();
+    /// bar.set_message("Downloading");
+    ///
+    /// assert!(bar.template("{speed}").is_err());
+    /// ```
+    pub fn template(&mut self, tmpl: &str) -> Result<&mut Bar<W>, TemplateError> {
+        self.template = Some(try!(parse_template(tmpl)));
+        Ok(self)
+    }
+
+    /// Sets the message shown in place of `{msg}` in the template. It is
+    /// drawn the next time the bar is redrawn.
+    pub fn set_message<S: Into<String>>(&mut self, message: S) -> &mut Bar<W> {
+        self.message = message.into();
+        self
+    }
+
+    /// Returns the number of units of work that are done.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Sets the number of units of work that are done, which is limited to
+    /// the total, and redraws the bar.
+    pub fn set_position(&mut self, position: u64) -> io::Result<()> {
+        self.position = position.min(self.total);
+
+        if self.interactive {
+            try!(write!(self.writer, "\r{}", self.frame()));
+            try!(self.writer.flush());
+        }
+
+        Ok(())
+    }
+
+    /// Adds `delta` to the number of units of work that are done and redraws
+    /// the bar.
+    pub fn inc(&mut self, delta: u64) -> io::Result<()> {
+        let position = self.position.saturating_add(delta);
+        self.set_position(position)
+    }
+
+    /// Draws the bar for the last time and moves on to the next line, leaving
+    /// the bar on the screen.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.interactive {
+            try!(write!(self.writer, "\r"));
+        }
+
+        try!(writeln!(self.writer, "{}", self.frame()));
+        self.writer.flush()
+    }
+
+    /// Consumes the `Bar`, returning the writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Renders the bar at its current position, without the carriage return.
+    fn frame(&self) -> String {
+        if let Some(ref pieces) = self.template {
+            let Size(Width(width), _) = screen::size_or_default();
+            let progress = Progress {
+                position: self.position,
+                total: self.total,
+                elapsed: self.started.elapsed(),
+                message: &self.message,
+            };
+
+            return render_template(pieces, &progress, width as usize, self.glyphs);
+        }
+
+        let (done, remaining) = match self.glyphs {
+            GlyphSet::Unicode => ('█', '░'),
+            GlyphSet::Ascii => ('#', '-'),
+        };
+
+        let fraction = if self.total == 0 {
+            1.0
+        } else {
+            self.position as f64 / self.total as f64
+        };
+
+        let filled = (fraction * BAR_WIDTH as f64) as usize;
+        let mut frame = String::with_capacity(BAR_WIDTH * 3 + 8);
+
+        frame.push('[');
+        frame.extend((0..filled).map(|_| done));
+        frame.extend((filled..BAR_WIDTH).map(|_| remaining));
+        frame.push_str(&format!("] {:>3}%", (fraction * 100.0) as u32));
+        frame
+    }
+}
+
+/// An error returned by `Bar::template()` for a template that cannot be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// The template contains a placeholder that is not recognised, which is
+    /// given without its braces.
+    UnknownPlaceholder(String),
+
+    /// The template contains a `{` without a matching `}`.
+    Unclosed,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TemplateError::UnknownPlaceholder(ref name) => {
+                write!(f, "unknown placeholder {{{}}} in progress bar template", name)
+            }
+            TemplateError::Unclosed => write!(f, "unclosed placeholder in progress bar template"),
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+/// A part of a progress bar template.
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Text(String),
+    Bar,
+    Percent,
+    Position,
+    Length,
+    Eta,
+    Elapsed,
+    Message,
+}
+
+/// Splits a template into text and placeholders.
+fn parse_template(template: &str) -> Result<Vec<Piece>, TemplateError> {
+    let mut pieces = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            pieces.push(Piece::Text(rest[..start].to_string()));
+        }
+
+        let end = try!(rest[start..].find('}').ok_or(TemplateError::Unclosed)) + start;
+
+        pieces.push(match &rest[start + 1..end] {
+            "bar" => Piece::Bar,
+            "percent" => Piece::Percent,
+            "pos" => Piece::Position,
+            "len" => Piece::Length,
+            "eta" => Piece::Eta,
+            "elapsed" => Piece::Elapsed,
+            "msg" => Piece::Message,
+            name => return Err(TemplateError::UnknownPlaceholder(name.to_string())),
+        });
+
+        rest = &rest[end + 1..];
+    }
+
+    if !rest.is_empty() {
+        pieces.push(Piece::Text(rest.to_string()));
+    }
+
+    Ok(pieces)
+}
+
+/// The values that are substituted into a template.
+struct Progress<'a> {
+    position: u64,
+    total: u64,
+    elapsed: Duration,
+    message: &'a str,
+}
+
+/// Renders a parsed template in `width` columns, with each `{bar}` sharing
+/// whatever width is left over after everything else.
+fn render_template(pieces: &[Piece], progress: &Progress, width: usize, glyphs: GlyphSet)
+                   -> String {
+    let fraction = if progress.total == 0 {
+        1.0
+    } else {
+        progress.position as f64 / progress.total as f64
+    };
+
+    let eta = if progress.position == 0 {
+        None
+    } else {
+        let remaining = progress.total - progress.position;
+        Some(progress.elapsed.mul_f64(remaining as f64 / progress.position as f64))
+    };
+
+    let text = |piece: &Piece| match *piece {
+        Piece::Text(ref text) => text.clone(),
+        Piece::Bar => String::new(),
+        Piece::Percent => format!("{:>3}%", (fraction * 100.0) as u32),
+        Piece::Position => progress.position.to_string(),
+        Piece::Length => progress.total.to_string(),
+        Piece::Eta => eta.map_or_else(|| "--:--".to_string(), format_duration),
+        Piece::Elapsed => format_duration(progress.elapsed),
+        Piece::Message => progress.message.to_string(),
+    };
+
+    let texts: Vec<String> = pieces.iter().map(text).collect();
+    let bars = pieces.iter().filter(|&piece| *piece == Piece::Bar).count();
+    let used: usize = texts.iter().map(|text| visible_width(text)).sum();
+    let bar_width = width.saturating_sub(used).checked_div(bars).unwrap_or(0);
+
+    let (done, remaining) = match glyphs {
+        GlyphSet::Unicode => ('█', '░'),
+        GlyphSet::Ascii => ('#', '-'),
+    };
+    let filled = (fraction * bar_width as f64) as usize;
+
+    pieces.iter().zip(texts).map(|(piece, text)| {
+        if *piece == Piece::Bar {
+            (0..bar_width).map(|i| if i < filled { done } else { remaining }).collect()
+        } else {
+            text
+        }
+    }).collect()
+}
+
+/// Formats a duration as minutes and seconds, such as `03:07`, with hours in
+/// front if there are any, such as `1:03:07`.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}", minutes, seconds)
+    }
+}
+
+/// A spinner for a task with an unknown amount of work, shown next to a
+/// message describing the task.
+#[derive(Debug)]
+pub struct Spinner<W: Write + Send = io::Stderr> {
+    writer: W,
+    message: String,
+    frame: usize,
+    glyphs: GlyphSet,
+    interactive: bool,
+}
+
+impl Spinner<io::Stderr> {
+    /// Creates a spinner with the given message that draws to standard error.
+    ///
+    /// If standard error is not a terminal, the spinner is never drawn, and
+    /// only the final message given to `finish()` is written.
+    pub fn new<S: Into<String>>(message: S) -> Spinner<io::Stderr> {
+        let interactive = io::stderr().is_terminal();
+        Spinner { interactive, ..Spinner::with_writer(io::stderr(), message) }
+    }
+}
+
+impl<W: Write + Send> Spinner<W> {
+    /// Creates a spinner with the given message that draws to `writer`, which
+    /// is always assumed to be able to redraw the spinner in place.
+    pub fn with_writer<S: Into<String>>(writer: W, message: S) -> Spinner<W> {
+        Spinner {
+            writer,
+            message: message.into(),
+            frame: 0,
+            glyphs: default_glyphs(),
+            interactive: true,
+        }
+    }
+
+    /// Sets the characters used to draw the spinner, which default to
+    /// [`default_glyphs()`](../text/fn.default_glyphs.html).
+    pub fn set_glyphs(&mut self, glyphs: GlyphSet) -> &mut Spinner<W> {
+        self.glyphs = glyphs;
+        self
+    }
+
+    /// Advances the spinner to its next frame and redraws it.
+    pub fn tick(&mut self) -> io::Result<()> {
+        if !self.interactive {
+            return Ok(());
+        }
+
+        let frames: &[char] = match self.glyphs {
+            GlyphSet::Unicode => &UNICODE_FRAMES,
+            GlyphSet::Ascii => &ASCII_FRAMES,
+        };
+
+        let frame = frames[self.frame % frames.len()];
+        self.frame = self.frame.wrapping_add(1);
+
+        try!(write!(self.writer, "\r{} {}", frame, self.message));
+        self.writer.flush()
+    }
+
+    /// Replaces the spinner and its message with `message`, followed by a
+    /// newline.
+    pub fn finish(&mut self, message: &str) -> io::Result<()> {
+        if self.interactive {
+            // Clear the whole line, in case the message is shorter.
+            try!(write!(self.writer, "\r\x1b[2K"));
+        }
+
+        try!(writeln!(self.writer, "{}", message));
+        self.writer.flush()
+    }
+
+    /// Consumes the `Spinner`, returning the writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bar_frames_written_to_buffer() {
+        let mut bar = Bar::with_writer(Vec::new(), 4);
+        bar.set_glyphs(GlyphSet::Ascii);
+
+        bar.inc(1).unwrap();
+        bar.inc(2).unwrap();
+        bar.finish().unwrap();
+
+        let output = String::from_utf8(bar.into_inner()).unwrap();
+        let frames: Vec<&str> = output.split('\r').collect();
+
+        assert_eq!(frames, ["",
+                            "[#######-----------------------]  25%",
+                            "[######################--------]  75%",
+                            "[######################--------]  75%\n"]);
+    }
+
+    #[test]
+    fn bar_limited_to_total() {
+        let mut bar = Bar::with_writer(Vec::new(), 10);
+        bar.set_glyphs(GlyphSet::Unicode);
+        bar.inc(25).unwrap();
+
+        assert_eq!(bar.position(), 10);
+        assert_eq!(bar.frame(), format!("[{}] 100%", "█".repeat(30)));
+    }
+
+    #[test]
+    fn bar_without_work() {
+        let mut bar = Bar::with_writer(Vec::new(), 0);
+        bar.set_glyphs(GlyphSet::Ascii);
+
+        assert_eq!(bar.frame(), format!("[{}] 100%", "#".repeat(30)));
+    }
+
+    #[test]
+    fn non_interactive_bar_only_drawn_when_finished() {
+        let mut bar = Bar { interactive: false, ..Bar::with_writer(Vec::new(), 2) };
+        bar.set_glyphs(GlyphSet::Ascii);
+
+        bar.inc(1).unwrap();
+        assert!(bar.writer.is_empty());
+
+        bar.finish().unwrap();
+        assert_eq!(bar.into_inner(), format!("[{}{}]  50%\n", "#".repeat(15), "-".repeat(15))
+                                         .as_bytes());
+    }
+
+    #[test]
+    fn render_template_with_known_values() {
+        let pieces = parse_template("{msg} [{bar}] {percent} {pos}/{len} {elapsed} eta {eta}")
+            .unwrap();
+        let progress = Progress {
+            position: 25,
+            total: 100,
+            elapsed: Duration::from_secs(30),
+            message: "Copying",
+        };
+
+        assert_eq!(render_template(&pieces, &progress, 60, GlyphSet::Ascii),
+                   "Copying [#####-----------------]  25% 25/100 00:30 eta 01:30");
+    }
+
+    #[test]
+    fn render_template_shares_width_between_bars() {
+        let pieces = parse_template("{bar}|{bar}").unwrap();
+        let progress = Progress { position: 1, total: 2, elapsed: Duration::new(0, 0), message: "" };
+
+        assert_eq!(render_template(&pieces, &progress, 9, GlyphSet::Unicode), "██░░|██░░");
+        assert_eq!(render_template(&pieces, &progress, 0, GlyphSet::Unicode), "|");
+    }
+
+    #[test]
+    fn render_template_without_progress() {
+        let pieces = parse_template("{eta} {percent}").unwrap();
+        let progress = Progress {
+            position: 0,
+            total: 10,
+            elapsed: Duration::from_secs(3725),
+            message: "",
+        };
+
+        assert_eq!(render_template(&pieces, &progress, 80, GlyphSet::Ascii), "--:--   0%");
+        assert_eq!(format_duration(progress.elapsed), "1:02:05");
+    }
+
+    #[test]
+    fn invalid_templates() {
+        let mut bar = Bar::with_writer(Vec::new(), 1);
+
+        assert_eq!(bar.template("{msg} {speed}").err(),
+                   Some(TemplateError::UnknownPlaceholder("speed".to_string())));
+        assert_eq!(bar.template("[{bar]").err(), Some(TemplateError::Unclosed));
+        assert!(bar.template.is_none());
+    }
+
+    #[test]
+    fn spinner_frames_written_to_buffer() {
+        let mut spinner = Spinner::with_writer(Vec::new(), "Working");
+        spinner.set_glyphs(GlyphSet::Ascii);
+
+        for _ in 0..5 {
+            spinner.tick().unwrap();
+        }
+        spinner.finish("Done").unwrap();
+
+        assert_eq!(spinner.into_inner(),
+                   &b"\r| Working\r/ Working\r- Working\r\\ Working\r| Working\r\x1b[2KDone\n"[..]);
+    }
+
+    #[test]
+    fn non_interactive_spinner_only_writes_final_message() {
+        let mut spinner = Spinner { interactive: false, ..Spinner::with_writer(Vec::new(), "") };
+        spinner.tick().unwrap();
+        spinner.finish("Done").unwrap();
+
+        assert_eq!(spinner.into_inner(), b"Done\n");
+    }
+}
@@ -0,0 +1,1316 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A module for asking the user questions and reading their answers.
+//!
+//! Interactive prompts put the terminal into raw mode so that they can react
+//! to individual key presses. When standard input is not a terminal, such as
+//! when input is piped in from another program, they fall back to reading
+//! whole lines so that they can still be used in scripts.
+//!
+//! # Basic Usage
+//!
+//! ```no_run
+//! use tutil::prompt;
+//!
+//! let name = prompt::edit("Project name: ", "tutil").unwrap();
+//!
+//! if prompt::confirm("Are you sure? [y/n] ").unwrap() {
+//!     println!("Creating {}...", name);
+//! }
+//! ```
+//!
+//! To test code that asks questions, use a [`Prompt`] that reads answers from
+//! memory instead of standard input.
+//!
+//! # Errors
+//!
+//! Every prompt returns a [`PromptError`] on failure, which distinguishes the
+//! user cancelling the prompt from an I/O failure:
+//!
+//! ```no_run
+//! use std::process;
+//! use tutil::prompt::{self, PromptError};
+//!
+//! match prompt::edit("Project name: ", "tutil") {
+//!     Ok(name) => println!("Creating {}...", name),
+//!     Err(PromptError::Interrupted) | Err(PromptError::Eof) => process::exit(130),
+//!     Err(PromptError::Io(e)) => panic!("failed to read the answer: {}", e),
+//! }
+//! ```
+//!
+//! [`Prompt`]: struct.Prompt.html
+//! [`PromptError`]: enum.PromptError.html
+
+use std::cmp::Reverse;
+use std::fmt;
+use std::error::Error;
+use std::io::{self, BufRead, Read, Write};
+
+use unicode_width::UnicodeWidthChar;
+
+use crayon::{visible_width, Style};
+use crayon::Color::Red;
+use screen::{self, RawMode};
+use text;
+
+/// The ways in which a prompt can fail.
+#[derive(Debug)]
+pub enum PromptError {
+    /// The user pressed Ctrl-C.
+    Interrupted,
+
+    /// The input ended before an answer was given, either because the user
+    /// pressed Ctrl-D or standard input was closed.
+    Eof,
+
+    /// Reading from or writing to the terminal failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for PromptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PromptError::Interrupted => write!(f, "prompt interrupted"),
+            PromptError::Eof => write!(f, "input ended before an answer was given"),
+            PromptError::Io(ref e) => write!(f, "prompt failed: {}", e),
+        }
+    }
+}
+
+impl Error for PromptError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            PromptError::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PromptError {
+    fn from(error: io::Error) -> PromptError {
+        PromptError::Io(error)
+    }
+}
+
+/// A source of answers to prompts, made up of an input to read answers from
+/// and an output to write questions to.
+///
+/// `Prompt::new()` uses standard input and output, which is what the functions
+/// in this module do. Any other input and output can be given with
+/// `Prompt::with_io()`, which makes it possible to test code that asks
+/// questions without a terminal:
+///
+/// ```
+/// use std::io::Cursor;
+/// use tutil::prompt::Prompt;
+///
+/// let mut prompt = Prompt::with_io(Cursor::new("y\n"), Vec::new());
+/// assert_eq!(prompt.confirm("Continue? [y/n] ").unwrap(), true);
+///
+/// let (_, output) = prompt.into_inner();
+/// assert_eq!(output, b"Continue? [y/n] ");
+/// ```
+#[derive(Debug)]
+pub struct Prompt<R, W> {
+    input: R,
+    output: W,
+    /// Whether to try to put the terminal into raw mode for interactive
+    /// prompts, which is only possible for standard input.
+    interactive: bool,
+}
+
+impl Prompt<io::StdinLock<'static>, io::Stdout> {
+    /// Creates a `Prompt` that reads from standard input and writes to
+    /// standard output.
+    ///
+    /// Standard input is locked for as long as the `Prompt` is alive.
+    pub fn new() -> Prompt<io::StdinLock<'static>, io::Stdout> {
+        Prompt { input: io::stdin().lock(), output: io::stdout(), interactive: true }
+    }
+}
+
+impl Default for Prompt<io::StdinLock<'static>, io::Stdout> {
+    fn default() -> Prompt<io::StdinLock<'static>, io::Stdout> {
+        Prompt::new()
+    }
+}
+
+impl<R: BufRead, W: Write> Prompt<R, W> {
+    /// Creates a `Prompt` that reads answers from `input` and writes questions
+    /// to `output`.
+    ///
+    /// The input is always read a line at a time, as it is when standard input
+    /// is not a terminal.
+    pub fn with_io(input: R, output: W) -> Prompt<R, W> {
+        Prompt { input, output, interactive: false }
+    }
+
+    /// Consumes the `Prompt`, returning the input and output.
+    pub fn into_inner(self) -> (R, W) {
+        (self.input, self.output)
+    }
+
+    /// Asks `question` and returns the line that the user enters, without the
+    /// line ending.
+    pub fn ask(&mut self, question: &str) -> Result<String, PromptError> {
+        try!(write!(self.output, "{}", question));
+        try!(self.output.flush());
+
+        self.read_line()
+    }
+
+    /// Asks a yes or no `question`, returning true for `y` or `yes` and false
+    /// for `n` or `no`, ignoring case.
+    ///
+    /// The question is asked again until one of those answers is given.
+    pub fn confirm(&mut self, question: &str) -> Result<bool, PromptError> {
+        loop {
+            match &try!(self.ask(question)).trim().to_lowercase()[..] {
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+
+    /// Asks `question` and reads lines until one that is exactly `end`,
+    /// returning the lines before it joined with `\n`.
+    ///
+    /// The end of the input also ends the answer, so that text can be piped
+    /// in without the terminating line.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use tutil::prompt::Prompt;
+    ///
+    /// let mut prompt = Prompt::with_io(Cursor::new("Fix a bug\n\nIn detail.\n.\n"), Vec::new());
+    /// let message = prompt.ask_multiline("Message (end with '.'):\n", ".").unwrap();
+    /// assert_eq!(message, "Fix a bug\n\nIn detail.");
+    /// ```
+    pub fn ask_multiline(&mut self, question: &str, end: &str) -> Result<String, PromptError> {
+        try!(write!(self.output, "{}", question));
+        try!(self.output.flush());
+
+        let mut lines = Vec::new();
+
+        loop {
+            match self.read_line() {
+                Ok(ref line) if line == end => break,
+                Ok(line) => lines.push(line),
+                Err(PromptError::Eof) => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(lines.join("\n"))
+    }
+
+    /// Asks `question` and lets the user edit `initial` in place, returning
+    /// the edited line once Enter is pressed.
+    ///
+    /// See [`edit()`](fn.edit.html) for the keys that can be used. If the
+    /// terminal cannot be put into raw mode, a line is read instead and
+    /// `initial` is returned if that line is empty.
+    pub fn edit(&mut self, question: &str, initial: &str) -> Result<String, PromptError> {
+        if !self.interactive {
+            return self.edit_line(question, initial);
+        }
+
+        let raw = match RawMode::enable() {
+            Ok(raw) => raw,
+            Err(_) => return self.edit_line(question, initial),
+        };
+
+        let result = edit_raw(question, initial, &mut self.input, self.interactive,
+                              &mut self.output);
+
+        drop(raw);
+        try!(writeln!(self.output));
+
+        result
+    }
+
+    /// Asks `question` and returns the line that the user enters once
+    /// `validate` accepts it, showing the error that `validate` returns for a
+    /// line that it rejects.
+    ///
+    /// See [`ask_validated()`](fn.ask_validated.html) for how the error is
+    /// shown. If the terminal cannot be put into raw mode, a line is read
+    /// instead, and the error is written after any line that is rejected
+    /// before asking again.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use tutil::prompt::Prompt;
+    ///
+    /// let mut prompt = Prompt::with_io(Cursor::new("eighty\n80\n"), Vec::new());
+    /// let port = prompt.ask_validated("Port: ", |answer| {
+    ///     answer.parse::<u16>().map(|_| ()).map_err(|_| "Enter a number".to_string())
+    /// });
+    /// assert_eq!(port.unwrap(), "80");
+    /// ```
+    pub fn ask_validated<F>(&mut self, question: &str, validate: F) -> Result<String, PromptError>
+        where F: Fn(&str) -> Result<(), String>
+    {
+        if !self.interactive {
+            return self.ask_validated_line(question, validate);
+        }
+
+        let raw = match RawMode::enable() {
+            Ok(raw) => raw,
+            Err(_) => return self.ask_validated_line(question, validate),
+        };
+
+        let result = ask_validated_raw(question, validate, &mut self.input, self.interactive,
+                                       &mut self.output);
+
+        drop(raw);
+        try!(writeln!(self.output));
+
+        result
+    }
+
+    /// The line based fallback for `ask_validated()`, used when the input is
+    /// not a terminal.
+    fn ask_validated_line<F>(&mut self, question: &str, validate: F)
+                             -> Result<String, PromptError>
+        where F: Fn(&str) -> Result<(), String>
+    {
+        loop {
+            let answer = try!(self.ask(question));
+
+            match validate(&answer) {
+                Ok(()) => return Ok(answer),
+                Err(error) => try!(writeln!(self.output, "{}", Red.paint(error))),
+            }
+        }
+    }
+
+    /// Asks `question` and lets the user pick one of `options` by typing part
+    /// of it, returning the index of the chosen option.
+    ///
+    /// See [`fuzzy_select()`](fn.fuzzy_select.html) for how options are
+    /// matched. If the terminal cannot be put into raw mode, a line is read
+    /// instead and the best match for it is chosen, asking again until some
+    /// option matches.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use tutil::prompt::Prompt;
+    ///
+    /// let options = ["apple", "banana", "cherry"];
+    /// let mut prompt = Prompt::with_io(Cursor::new("bn\n"), Vec::new());
+    /// assert_eq!(prompt.fuzzy_select("Fruit: ", &options).unwrap(), 1);
+    /// ```
+    pub fn fuzzy_select<S>(&mut self, question: &str, options: &[S]) -> Result<usize, PromptError>
+        where S: AsRef<str>
+    {
+        if !self.interactive {
+            return self.fuzzy_select_line(question, options);
+        }
+
+        let raw = match RawMode::enable() {
+            Ok(raw) => raw,
+            Err(_) => return self.fuzzy_select_line(question, options),
+        };
+
+        let result = fuzzy_select_raw(question, options, &mut self.input, self.interactive,
+                                      &mut self.output);
+
+        drop(raw);
+        try!(writeln!(self.output));
+
+        result
+    }
+
+    /// The line based fallback for `fuzzy_select()`, used when the input is
+    /// not a terminal.
+    fn fuzzy_select_line<S>(&mut self, question: &str, options: &[S])
+                            -> Result<usize, PromptError>
+        where S: AsRef<str>
+    {
+        loop {
+            let query = try!(self.ask(question));
+
+            if let Some(&(index, _)) = rank(&query, options).first() {
+                return Ok(index);
+            }
+        }
+    }
+
+    /// The line based fallback for `edit()`, used when the input is not a
+    /// terminal.
+    fn edit_line(&mut self, question: &str, initial: &str) -> Result<String, PromptError> {
+        let answer = try!(self.ask(question));
+
+        if answer.is_empty() {
+            Ok(initial.to_string())
+        } else {
+            Ok(answer)
+        }
+    }
+
+    /// Reads a line of input, without the line ending or a leading byte order
+    /// mark.
+    fn read_line(&mut self) -> Result<String, PromptError> {
+        let mut line = String::new();
+
+        if try!(self.input.read_line(&mut line)) == 0 {
+            return Err(PromptError::Eof);
+        }
+
+        Ok(text::normalize_input(&line).to_string())
+    }
+}
+
+/// Asks `question` and returns the line that the user enters, without the line
+/// ending.
+pub fn ask(question: &str) -> Result<String, PromptError> {
+    Prompt::new().ask(question)
+}
+
+/// Asks a yes or no `question`, returning true for `y` or `yes` and false for
+/// `n` or `no`, ignoring case.
+///
+/// The question is asked again until one of those answers is given.
+pub fn confirm(question: &str) -> Result<bool, PromptError> {
+    Prompt::new().confirm(question)
+}
+
+/// Asks `question` and reads lines until one that is exactly `end`, such as
+/// `"."`, or the end of the input, returning the lines before it joined with
+/// `\n`.
+pub fn ask_multiline(question: &str, end: &str) -> Result<String, PromptError> {
+    Prompt::new().ask_multiline(question, end)
+}
+
+/// Asks `question` and lets the user edit `initial` in place, returning the
+/// edited line once Enter is pressed.
+///
+/// The cursor starts at the end of `initial` and can be moved with the arrow
+/// keys, Home and End (or Ctrl-A and Ctrl-E), while Backspace and Delete
+/// remove characters. Ctrl-C fails with `PromptError::Interrupted` and Ctrl-D
+/// on an empty line fails with `PromptError::Eof`.
+///
+/// If standard input is not a terminal, a line is read instead and `initial`
+/// is returned if that line is empty.
+pub fn edit(question: &str, initial: &str) -> Result<String, PromptError> {
+    Prompt::new().edit(question, initial)
+}
+
+/// Asks `question` and returns the line that the user enters once `validate`
+/// accepts it.
+///
+/// The line is edited as with [`edit()`]. Once the user starts typing, the
+/// error that `validate` returns for the line so far is shown in red beneath
+/// it, and disappears as soon as the line is valid. Enter does nothing while
+/// the line is invalid.
+///
+/// If standard input is not a terminal, lines are read until one is valid,
+/// writing the error after each one that is not.
+///
+/// ```no_run
+/// use tutil::prompt;
+///
+/// let name = prompt::ask_validated("Crate name: ", |name| {
+///     if name.is_empty() {
+///         Err("The name cannot be empty".to_string())
+///     } else if name.contains(' ') {
+///         Err("The name cannot contain spaces".to_string())
+///     } else {
+///         Ok(())
+///     }
+/// }).unwrap();
+/// ```
+///
+/// [`edit()`]: fn.edit.html
+pub fn ask_validated<F>(question: &str, validate: F) -> Result<String, PromptError>
+    where F: Fn(&str) -> Result<(), String>
+{
+    Prompt::new().ask_validated(question, validate)
+}
+
+/// Asks `question` and lets the user pick one of `options` by typing part
+/// of it, returning the index of the chosen option.
+///
+/// Only the options that contain the typed characters in order, though not
+/// necessarily next to each other, are listed, with the best matches first and
+/// the matched characters highlighted. While nothing has been typed, every
+/// option is listed in its original order. The Up and Down arrows move between
+/// the listed options and Enter chooses one, which does nothing while no
+/// option matches. The typed text can be edited as with [`edit()`].
+///
+/// If standard input is not a terminal, a line is read instead and the best
+/// match for it is chosen.
+///
+/// ```no_run
+/// use tutil::prompt;
+///
+/// let licenses = ["Apache-2.0", "GPL-3.0-only", "MIT", "MPL-2.0"];
+/// let choice = prompt::fuzzy_select("License: ", &licenses).unwrap();
+/// println!("Using the {} license", licenses[choice]);
+/// ```
+///
+/// [`edit()`]: fn.edit.html
+pub fn fuzzy_select<S: AsRef<str>>(question: &str, options: &[S]) -> Result<usize, PromptError> {
+    Prompt::new().fuzzy_select(question, options)
+}
+
+/// The interactive implementation of `edit()`, reading key presses from
+/// `input`, which must be a terminal in raw mode. `stdin` says whether `input`
+/// reads standard input, as described for `read_key()`.
+fn edit_raw<R, W>(question: &str, initial: &str, input: &mut R, stdin: bool, output: &mut W)
+                  -> Result<String, PromptError>
+    where R: BufRead, W: Write
+{
+    let mut line = LineBuffer::new(initial);
+
+    loop {
+        try!(redraw(output, question, &line));
+
+        match try!(read_key(input, stdin)) {
+            Key::Enter => return Ok(line.to_string()),
+            Key::Char(c) => line.insert(c),
+            Key::Backspace => line.backspace(),
+            Key::Delete => line.delete(),
+            Key::Left => line.move_left(),
+            Key::Right => line.move_right(),
+            Key::Home => line.move_home(),
+            Key::End => line.move_end(),
+            Key::Interrupt => return Err(PromptError::Interrupted),
+            Key::Eof => return Err(PromptError::Eof),
+            Key::EndOfTransmission if line.is_empty() => return Err(PromptError::Eof),
+            Key::EndOfTransmission | Key::Up | Key::Down | Key::Escape | Key::Unknown => {}
+        }
+    }
+}
+
+/// Redraws the question and the line being edited, then places the cursor.
+fn redraw<W: Write>(output: &mut W, question: &str, line: &LineBuffer) -> io::Result<()> {
+    try!(write!(output, "\r\x1b[K{}{}\r", question, line));
+
+    let column = visible_width(question) + line.cursor_width();
+    if column > 0 {
+        try!(write!(output, "\x1b[{}C", column));
+    }
+
+    output.flush()
+}
+
+/// The interactive implementation of `ask_validated()`, reading key presses
+/// from `input`, which must be a terminal in raw mode. `stdin` says whether
+/// `input` reads standard input, as described for `read_key()`.
+fn ask_validated_raw<F, R, W>(question: &str, validate: F, input: &mut R, stdin: bool,
+                              output: &mut W)
+                              -> Result<String, PromptError>
+    where F: Fn(&str) -> Result<(), String>, R: BufRead, W: Write
+{
+    let mut line = LineBuffer::new("");
+    // The error is not shown until the user has had a chance to type.
+    let mut edited = false;
+
+    loop {
+        let text = line.to_string();
+        let result = validate(&text);
+        let error = match result {
+            Err(ref error) if edited => Some(&error[..]),
+            _ => None,
+        };
+
+        try!(redraw_validated(output, question, &line, error));
+
+        match try!(read_key(input, stdin)) {
+            Key::Enter if result.is_ok() => {
+                try!(write!(output, "\r\x1b[J{}{}", question, text));
+                return Ok(text);
+            }
+            Key::Enter => {}
+            Key::Char(c) => line.insert(c),
+            Key::Backspace => line.backspace(),
+            Key::Delete => line.delete(),
+            Key::Left => line.move_left(),
+            Key::Right => line.move_right(),
+            Key::Home => line.move_home(),
+            Key::End => line.move_end(),
+            Key::Interrupt => return Err(PromptError::Interrupted),
+            Key::Eof => return Err(PromptError::Eof),
+            Key::EndOfTransmission if line.is_empty() => return Err(PromptError::Eof),
+            Key::EndOfTransmission | Key::Up | Key::Down | Key::Escape | Key::Unknown => {}
+        }
+
+        edited = true;
+    }
+}
+
+/// Redraws the question and the line being edited, with `error` in red on the
+/// line below if there is one, then places the cursor.
+fn redraw_validated<W: Write>(output: &mut W, question: &str, line: &LineBuffer,
+                              error: Option<&str>) -> io::Result<()> {
+    try!(write!(output, "\r\x1b[J{}{}", question, line));
+
+    if let Some(error) = error {
+        try!(write!(output, "\r\n{}\x1b[1A", Red.paint(error)));
+    }
+
+    try!(write!(output, "\r"));
+
+    let column = visible_width(question) + line.cursor_width();
+    if column > 0 {
+        try!(write!(output, "\x1b[{}C", column));
+    }
+
+    output.flush()
+}
+
+/// The most options that `fuzzy_select()` lists at once.
+const MAX_LISTED: usize = 10;
+
+/// The interactive implementation of `fuzzy_select()`, reading key presses
+/// from `input`, which must be a terminal in raw mode. `stdin` says whether
+/// `input` reads standard input, as described for `read_key()`.
+fn fuzzy_select_raw<S, R, W>(question: &str, options: &[S], input: &mut R, stdin: bool,
+                             output: &mut W)
+                             -> Result<usize, PromptError>
+    where S: AsRef<str>, R: BufRead, W: Write
+{
+    let mut query = LineBuffer::new("");
+    let mut selected = 0;
+
+    loop {
+        let query_text = query.to_string();
+        let matches = rank(&query_text, options);
+        selected = selected.min(matches.len().saturating_sub(1));
+
+        try!(redraw_matches(output, question, &query, options, &matches, selected));
+
+        match try!(read_key(input, stdin)) {
+            Key::Enter if !matches.is_empty() => {
+                let (index, _) = matches[selected];
+                try!(write!(output, "\r\x1b[J{}{}", question, options[index].as_ref()));
+                return Ok(index);
+            }
+            Key::Char(c) => {
+                query.insert(c);
+                selected = 0;
+            }
+            Key::Backspace => {
+                query.backspace();
+                selected = 0;
+            }
+            Key::Delete => {
+                query.delete();
+                selected = 0;
+            }
+            Key::Up => selected = selected.saturating_sub(1),
+            Key::Down => selected += 1,
+            Key::Left => query.move_left(),
+            Key::Right => query.move_right(),
+            Key::Home => query.move_home(),
+            Key::End => query.move_end(),
+            Key::Interrupt => return Err(PromptError::Interrupted),
+            Key::Eof => return Err(PromptError::Eof),
+            Key::EndOfTransmission if query.is_empty() => return Err(PromptError::Eof),
+            Key::Enter | Key::EndOfTransmission | Key::Escape | Key::Unknown => {}
+        }
+    }
+}
+
+/// Redraws the question, the query and the options that match it below, then
+/// places the cursor back in the query.
+fn redraw_matches<S, W>(output: &mut W, question: &str, query: &LineBuffer, options: &[S],
+                        matches: &[(usize, FuzzyMatch)], selected: usize)
+                        -> io::Result<()>
+    where S: AsRef<str>, W: Write
+{
+    try!(write!(output, "\r\x1b[J{}{}", question, query));
+
+    // Scroll so that the selected option is always listed.
+    let first = (selected + 1).saturating_sub(MAX_LISTED);
+    let listed = &matches[first..matches.len().min(first + MAX_LISTED)];
+
+    for (i, &(index, ref found)) in listed.iter().enumerate() {
+        let marker = if first + i == selected { ">" } else { " " };
+        try!(write!(output, "\r\n{} {}", marker, highlight(options[index].as_ref(), found)));
+    }
+
+    let lines = if matches.is_empty() {
+        try!(write!(output, "\r\n  {}", Style::new().dimmed().paint("No matches")));
+        1
+    } else {
+        listed.len()
+    };
+
+    try!(write!(output, "\x1b[{}A\r", lines));
+
+    let column = visible_width(question) + query.cursor_width();
+    if column > 0 {
+        try!(write!(output, "\x1b[{}C", column));
+    }
+
+    output.flush()
+}
+
+/// Renders `option` with the characters that matched the query highlighted.
+fn highlight(option: &str, found: &FuzzyMatch) -> String {
+    let style = Style::new().bold().underline();
+    let mut highlighted = String::with_capacity(option.len());
+    let mut run = String::new();
+    let mut matched = found.positions.iter().peekable();
+
+    for (i, c) in option.chars().enumerate() {
+        if matched.peek() == Some(&&i) {
+            matched.next();
+            run.push(c);
+        } else {
+            if !run.is_empty() {
+                highlighted.push_str(&style.paint(&run[..]).to_string());
+                run.clear();
+            }
+
+            highlighted.push(c);
+        }
+    }
+
+    if !run.is_empty() {
+        highlighted.push_str(&style.paint(run).to_string());
+    }
+
+    highlighted
+}
+
+/// A key press read from the terminal in raw mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    /// Ctrl-C.
+    Interrupt,
+    /// Ctrl-D.
+    EndOfTransmission,
+    /// The end of the input.
+    Eof,
+    /// Escape on its own, rather than as the start of an escape sequence.
+    Escape,
+    Unknown,
+}
+
+/// How long to wait after an `ESC` for the rest of an escape sequence before
+/// taking it as the Escape key, in milliseconds.
+const ESCAPE_TIMEOUT: i32 = 50;
+
+/// Reads a single key press, decoding escape sequences and UTF-8.
+///
+/// `stdin` must be true only if `input` reads standard input, which is then
+/// waited on briefly after an `ESC`. Any other input is taken to hold all of
+/// its key presses already.
+fn read_key<R: BufRead>(input: &mut R, stdin: bool) -> io::Result<Key> {
+    let byte = match try!(read_byte(input)) {
+        Some(byte) => byte,
+        None => return Ok(Key::Eof),
+    };
+
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        0x01 => Key::Home,
+        0x02 => Key::Left,
+        0x03 => Key::Interrupt,
+        0x04 => Key::EndOfTransmission,
+        0x05 => Key::End,
+        0x06 => Key::Right,
+        0x1b => try!(read_escape(input, stdin)),
+        0x00..=0x1f => Key::Unknown,
+        0x20..=0x7e => Key::Char(byte as char),
+        _ => try!(read_utf8(input, byte)),
+    };
+
+    Ok(key)
+}
+
+/// Reads the rest of an escape sequence, after the `ESC`.
+///
+/// If nothing follows, waiting up to `ESCAPE_TIMEOUT` if `stdin` is true, or
+/// what follows does not start a CSI or SS3 sequence, the Escape key was
+/// pressed on its own and anything after it is left to be read as a key of its
+/// own.
+fn read_escape<R: BufRead>(input: &mut R, stdin: bool) -> io::Result<Key> {
+    let follows = if stdin {
+        try!(screen::input_follows(input, ESCAPE_TIMEOUT))
+    } else {
+        !try!(input.fill_buf()).is_empty()
+    };
+
+    if !follows {
+        return Ok(Key::Escape);
+    }
+
+    match try!(input.fill_buf()).first() {
+        Some(&b'[') | Some(&b'O') => input.consume(1),
+        _ => return Ok(Key::Escape),
+    }
+
+    let mut params = Vec::new();
+
+    loop {
+        match try!(read_byte(input)) {
+            Some(byte @ 0x40..=0x7e) => {
+                let key = match (&params[..], byte) {
+                    (b"", b'A') => Key::Up,
+                    (b"", b'B') => Key::Down,
+                    (b"", b'C') => Key::Right,
+                    (b"", b'D') => Key::Left,
+                    (b"", b'H') | (b"1", b'~') | (b"7", b'~') => Key::Home,
+                    (b"", b'F') | (b"4", b'~') | (b"8", b'~') => Key::End,
+                    (b"3", b'~') => Key::Delete,
+                    _ => Key::Unknown,
+                };
+
+                return Ok(key);
+            }
+            Some(byte) => params.push(byte),
+            None => return Ok(Key::Unknown),
+        }
+    }
+}
+
+/// Reads the continuation bytes of a UTF-8 character starting with `first`.
+fn read_utf8<R: Read>(input: &mut R, first: u8) -> io::Result<Key> {
+    let len = match first {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return Ok(Key::Unknown),
+    };
+
+    let mut bytes = vec![first];
+
+    for _ in 1..len {
+        match try!(read_byte(input)) {
+            Some(byte) => bytes.push(byte),
+            None => return Ok(Key::Unknown),
+        }
+    }
+
+    Ok(String::from_utf8(bytes).ok()
+        .and_then(|s| s.chars().next())
+        .map_or(Key::Unknown, Key::Char))
+}
+
+/// Reads a single byte, returning `None` at the end of the input.
+fn read_byte<R: Read>(input: &mut R) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(byte[0])),
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// A line of text being edited, along with the position of the cursor.
+#[derive(Debug, Clone, PartialEq)]
+struct LineBuffer {
+    chars: Vec<char>,
+    /// The index in `chars` that the cursor is before.
+    cursor: usize,
+}
+
+impl LineBuffer {
+    /// Creates a buffer containing `initial`, with the cursor at the end.
+    fn new(initial: &str) -> LineBuffer {
+        let chars: Vec<char> = initial.chars().collect();
+        let cursor = chars.len();
+
+        LineBuffer { chars, cursor }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    /// Inserts a character before the cursor.
+    fn insert(&mut self, c: char) {
+        self.chars.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    /// Removes the character before the cursor.
+    fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    /// Removes the character under the cursor.
+    fn delete(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.chars.len());
+    }
+
+    fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    fn move_end(&mut self) {
+        self.cursor = self.chars.len();
+    }
+
+    /// Returns the number of columns taken up by the text before the cursor.
+    fn cursor_width(&self) -> usize {
+        self.chars[..self.cursor].iter().map(|c| c.width().unwrap_or(0)).sum()
+    }
+}
+
+impl fmt::Display for LineBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in &self.chars {
+            try!(write!(f, "{}", c));
+        }
+
+        Ok(())
+    }
+}
+
+/// How well a query matched an option, as found by `fuzzy_match()`.
+#[derive(Debug, Clone, PartialEq)]
+struct FuzzyMatch {
+    /// Higher scores are better matches.
+    score: i32,
+    /// The indices of the characters in the option that matched the query,
+    /// counted in `char`s.
+    positions: Vec<usize>,
+}
+
+/// The score for each character of the query that is matched.
+const MATCH_SCORE: i32 = 1;
+/// The extra score for a character that directly follows the previous match.
+const CONSECUTIVE_BONUS: i32 = 5;
+/// The extra score for a character that starts a word in the option.
+const WORD_START_BONUS: i32 = 8;
+
+/// Matches `query` against `option` as a subsequence, ignoring case,
+/// returning `None` if some character of the query cannot be found.
+///
+/// Matches score more for characters that are next to each other or that
+/// start words, and lose a point for every character skipped before the first
+/// match, so `"fb"` matches `"foo bar"` better than `"fabulous"`. Every place
+/// that the first character could match is tried, keeping the best result.
+fn fuzzy_match(query: &str, option: &str) -> Option<FuzzyMatch> {
+    let lowercase = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let query: Vec<char> = query.chars().map(lowercase).collect();
+    let option: Vec<char> = option.chars().collect();
+    let lower: Vec<char> = option.iter().cloned().map(lowercase).collect();
+
+    let first = match query.first() {
+        Some(&first) => first,
+        None => return Some(FuzzyMatch { score: 0, positions: Vec::new() }),
+    };
+
+    let mut best: Option<FuzzyMatch> = None;
+
+    for start in (0..lower.len()).filter(|&i| lower[i] == first) {
+        let mut positions = vec![start];
+
+        for &c in &query[1..] {
+            let from = positions[positions.len() - 1] + 1;
+
+            match lower[from..].iter().position(|&o| o == c) {
+                Some(offset) => positions.push(from + offset),
+                None => break,
+            }
+        }
+
+        // Later starts can only find fewer characters, so there is no point
+        // in trying them once one start fails.
+        if positions.len() < query.len() {
+            break;
+        }
+
+        let score = positions.iter().enumerate().fold(-(start as i32), |score, (i, &p)| {
+            let consecutive = i > 0 && positions[i - 1] + 1 == p;
+            let word_start = p == 0 || !option[p - 1].is_alphanumeric();
+
+            score + MATCH_SCORE
+                + if consecutive { CONSECUTIVE_BONUS } else { 0 }
+                + if word_start { WORD_START_BONUS } else { 0 }
+        });
+
+        let better = match best {
+            Some(ref best) => score > best.score,
+            None => true,
+        };
+        if better {
+            best = Some(FuzzyMatch { score, positions });
+        }
+    }
+
+    best
+}
+
+/// Returns the index and match of every option that `query` matches, best
+/// first. Options that score the same keep their original order, so every
+/// option is returned in order for an empty query.
+fn rank<S: AsRef<str>>(query: &str, options: &[S]) -> Vec<(usize, FuzzyMatch)> {
+    let mut matches: Vec<_> = options.iter()
+        .enumerate()
+        .filter_map(|(i, option)| fuzzy_match(query, option.as_ref()).map(|found| (i, found)))
+        .collect();
+
+    matches.sort_by_key(|(_, found)| Reverse(found.score));
+    matches
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crayon;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn buffer_starts_at_end() {
+        let line = LineBuffer::new("abc");
+        assert_eq!(line.cursor, 3);
+        assert_eq!(line.to_string(), "abc");
+    }
+
+    #[test]
+    fn buffer_insert() {
+        let mut line = LineBuffer::new("ac");
+        line.move_left();
+        line.insert('b');
+
+        assert_eq!(line.to_string(), "abc");
+        assert_eq!(line.cursor, 2);
+    }
+
+    #[test]
+    fn buffer_backspace_and_delete() {
+        let mut line = LineBuffer::new("abcd");
+        line.move_left();
+        line.move_left();
+        line.backspace();
+        assert_eq!(line.to_string(), "acd");
+
+        line.delete();
+        assert_eq!(line.to_string(), "ad");
+        assert_eq!(line.cursor, 1);
+
+        line.move_home();
+        line.backspace();
+        line.move_end();
+        line.delete();
+        assert_eq!(line.to_string(), "ad");
+    }
+
+    #[test]
+    fn buffer_cursor_bounds() {
+        let mut line = LineBuffer::new("ab");
+        line.move_right();
+        assert_eq!(line.cursor, 2);
+
+        line.move_home();
+        line.move_left();
+        assert_eq!(line.cursor, 0);
+    }
+
+    #[test]
+    fn buffer_cursor_width() {
+        let mut line = LineBuffer::new("a日本");
+        assert_eq!(line.cursor_width(), 5);
+
+        line.move_left();
+        assert_eq!(line.cursor_width(), 3);
+    }
+
+    #[test]
+    fn read_keys() {
+        let mut input = Cursor::new("a\u{e9}\x1b[D\x1b[3~\x1bOH\x1b[4~\x7f\x03\x04\r".as_bytes());
+        let keys = [Key::Char('a'), Key::Char('\u{e9}'), Key::Left, Key::Delete, Key::Home,
+                    Key::End, Key::Backspace, Key::Interrupt, Key::EndOfTransmission, Key::Enter,
+                    Key::Eof];
+
+        for &key in &keys {
+            assert_eq!(read_key(&mut input, false).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn lone_escape_keeps_the_next_key() {
+        let mut input = Cursor::new("\x1bx\x1b\x1b[A\x1b".as_bytes());
+        let keys = [Key::Escape, Key::Char('x'), Key::Escape, Key::Up, Key::Escape, Key::Eof];
+
+        for &key in &keys {
+            assert_eq!(read_key(&mut input, false).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn multiline_until_sentinel() {
+        let mut prompt = Prompt::with_io(Cursor::new("one\ntwo\r\n\nthree\n.\nafter\n"),
+                                         Vec::new());
+        assert_eq!(prompt.ask_multiline("> ", ".").unwrap(), "one\ntwo\n\nthree");
+        assert_eq!(prompt.ask("").unwrap(), "after");
+    }
+
+    #[test]
+    fn multiline_until_eof() {
+        let mut prompt = Prompt::with_io(Cursor::new("one\n. \ntwo"), Vec::new());
+        assert_eq!(prompt.ask_multiline("> ", ".").unwrap(), "one\n. \ntwo");
+
+        let mut prompt = Prompt::with_io(Cursor::new(""), Vec::new());
+        assert_eq!(prompt.ask_multiline("> ", "EOF").unwrap(), "");
+    }
+
+    #[test]
+    fn edit_raw_returns_edited_line() {
+        let answer = edit_raw("", "tutl", &mut Cursor::new("\x1b[Di\r"), false, &mut Vec::new());
+        assert_eq!(answer.unwrap(), "tutil");
+    }
+
+    #[test]
+    fn ctrl_c_interrupts() {
+        let result = edit_raw("", "tutil", &mut Cursor::new("ab\x03"), false, &mut Vec::new());
+
+        match result {
+            Err(PromptError::Interrupted) => {}
+            other => panic!("expected Interrupted, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ctrl_d_or_closed_input_is_eof() {
+        for &(initial, input) in &[("", "\x04"), ("", ""), ("tutil", "")] {
+            let result = edit_raw("", initial, &mut Cursor::new(input), false, &mut Vec::new());
+
+            match result {
+                Err(PromptError::Eof) => {}
+                other => panic!("expected Eof, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn closed_input_is_eof_without_terminal() {
+        match Prompt::with_io(Cursor::new(""), Vec::new()).edit("", "tutil") {
+            Err(PromptError::Eof) => {}
+            other => panic!("expected Eof, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn edit_line_returns_answer() {
+        let mut prompt = Prompt::with_io(Cursor::new("crayon\r\n"), Vec::new());
+        assert_eq!(prompt.edit("Name: ", "tutil").unwrap(), "crayon");
+
+        let (_, output) = prompt.into_inner();
+        assert_eq!(output, b"Name: ");
+    }
+
+    #[test]
+    fn edit_line_defaults_to_initial() {
+        let mut prompt = Prompt::with_io(Cursor::new("\n"), Vec::new());
+        assert_eq!(prompt.edit("Name: ", "tutil").unwrap(), "tutil");
+    }
+
+    #[test]
+    fn ask_returns_line() {
+        let mut prompt = Prompt::with_io(Cursor::new("Ferris\nunread\n"), Vec::new());
+        assert_eq!(prompt.ask("Name? ").unwrap(), "Ferris");
+
+        let (_, output) = prompt.into_inner();
+        assert_eq!(output, b"Name? ");
+    }
+
+    #[test]
+    fn ask_at_end_of_input() {
+        match Prompt::with_io(Cursor::new(""), Vec::new()).ask("Name? ") {
+            Err(PromptError::Eof) => {}
+            other => panic!("expected Eof, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn confirm_yes_and_no() {
+        for &(input, expected) in &[("y\n", true), ("YES\n", true), ("n\n", false), ("no\r\n", false)] {
+            let mut prompt = Prompt::with_io(Cursor::new(input), Vec::new());
+            assert_eq!(prompt.confirm("Continue? ").unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn confirm_pasted_from_windows() {
+        let mut prompt = Prompt::with_io(Cursor::new("\u{feff}y\r\n"), Vec::new());
+        assert!(prompt.confirm("Continue? ").unwrap());
+
+        let mut prompt = Prompt::with_io(Cursor::new("\u{feff}n\r\n"), Vec::new());
+        assert!(!prompt.confirm("Continue? ").unwrap());
+    }
+
+    #[test]
+    fn confirm_asks_again_until_answered() {
+        let mut prompt = Prompt::with_io(Cursor::new("maybe\n\ny\n"), Vec::new());
+        assert!(prompt.confirm("Continue? ").unwrap());
+
+        let (_, output) = prompt.into_inner();
+        assert_eq!(output, b"Continue? Continue? Continue? ");
+    }
+
+    #[test]
+    fn fuzzy_match_positions() {
+        let found = fuzzy_match("fb", "foo bar").unwrap();
+        assert_eq!(found.positions, [0, 4]);
+        assert_eq!(found.score, 2 * (MATCH_SCORE + WORD_START_BONUS));
+
+        assert_eq!(fuzzy_match("ab", "xaxab").unwrap().positions, [3, 4]);
+        assert_eq!(fuzzy_match("MIT", "mit").unwrap().positions, [0, 1, 2]);
+        assert_eq!(fuzzy_match("ba", "abc"), None);
+    }
+
+    #[test]
+    fn fuzzy_match_prefers_word_starts_and_runs() {
+        let score = |query, option| fuzzy_match(query, option).unwrap().score;
+
+        assert!(score("fb", "foo bar") > score("fb", "fabulous"));
+        assert!(score("gpl", "GPL-3.0") > score("gpl", "gimple"));
+        assert!(score("bar", "bar") > score("bar", "foobar"));
+    }
+
+    #[test]
+    fn rank_best_first() {
+        let options = ["fabulous", "foo bar", "baz", "xfb"];
+        let ranked: Vec<usize> = rank("fb", &options).iter().map(|&(i, _)| i).collect();
+        assert_eq!(ranked, [1, 0, 3]);
+    }
+
+    #[test]
+    fn rank_empty_query_lists_all_in_order() {
+        let options = ["c", "b", "a"];
+        let ranked: Vec<usize> = rank("", &options).iter().map(|&(i, _)| i).collect();
+        assert_eq!(ranked, [0, 1, 2]);
+        assert!(rank("z", &options).is_empty());
+    }
+
+    #[test]
+    fn highlight_matched_characters() {
+        crayon::set_enabled(true);
+        let found = fuzzy_match("fb", "foo bar").unwrap();
+        assert_eq!(highlight("foo bar", &found), "\x1b[1;4mf\x1b[0moo \x1b[1;4mb\x1b[0mar");
+    }
+
+    #[test]
+    fn fuzzy_select_raw_filters_and_navigates() {
+        let options = ["apple", "banana", "cherry", "grape"];
+        let choose = |keys: &str| {
+            fuzzy_select_raw("", &options, &mut Cursor::new(keys), false, &mut Vec::new()).unwrap()
+        };
+
+        assert_eq!(choose("\r"), 0);
+        assert_eq!(choose("\x1b[B\x1b[B\r"), 2);
+        assert_eq!(choose("ap\x1b[B\r"), 3);
+        assert_eq!(choose("\x1b[A\x1b[B\x1b[B\x1b[B\x1b[B\x1b[B\r"), 3);
+    }
+
+    #[test]
+    fn fuzzy_select_raw_ignores_enter_without_matches() {
+        let options = ["apple", "banana"];
+        let answer = fuzzy_select_raw("", &options, &mut Cursor::new("bz\r\x7f\r"), false,
+                                      &mut Vec::new());
+        assert_eq!(answer.unwrap(), 1);
+
+        match fuzzy_select_raw("", &options, &mut Cursor::new("z\r"), false, &mut Vec::new()) {
+            Err(PromptError::Eof) => {}
+            other => panic!("expected Eof, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn fuzzy_select_line_asks_until_matched() {
+        let options = ["apple", "banana", "cherry"];
+        let mut prompt = Prompt::with_io(Cursor::new("xyz\nchy\n"), Vec::new());
+        assert_eq!(prompt.fuzzy_select("Fruit: ", &options).unwrap(), 2);
+
+        let (_, output) = prompt.into_inner();
+        assert_eq!(output, b"Fruit: Fruit: ");
+    }
+
+    fn port(answer: &str) -> Result<(), String> {
+        answer.parse::<u16>().map(|_| ()).map_err(|_| format!("'{}' is not a port", answer))
+    }
+
+    #[test]
+    fn ask_validated_line_asks_until_valid() {
+        crayon::set_enabled(true);
+        let mut prompt = Prompt::with_io(Cursor::new("http\n8080\n"), Vec::new());
+        assert_eq!(prompt.ask_validated("Port: ", port).unwrap(), "8080");
+
+        let (_, output) = prompt.into_inner();
+        assert_eq!(String::from_utf8(output).unwrap(),
+                   "Port: \x1b[31m'http' is not a port\x1b[0m\nPort: ");
+    }
+
+    #[test]
+    fn ask_validated_line_at_end_of_input() {
+        match Prompt::with_io(Cursor::new("http\n"), Vec::new()).ask_validated("Port: ", port) {
+            Err(PromptError::Eof) => {}
+            other => panic!("expected Eof, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ask_validated_raw_ignores_enter_while_invalid() {
+        crayon::set_enabled(true);
+        let mut output = Vec::new();
+        let answer = ask_validated_raw("", port, &mut Cursor::new("8x\r\x7f0\r"), false,
+                                       &mut output);
+        assert_eq!(answer.unwrap(), "80");
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("\x1b[31m'8x' is not a port\x1b[0m"));
+        assert!(!output.contains("'' is not a port"));
+    }
+
+    #[test]
+    #[ignore = "requires typing into a terminal"]
+    fn interactive_edit() {
+        let answer = edit("Press Enter to accept: ", "tutil").unwrap();
+        assert_eq!(answer, "tutil");
+    }
+
+    #[test]
+    #[ignore = "requires typing into a terminal"]
+    fn interactive_fuzzy_select() {
+        let answer = fuzzy_select("Type 'mit' and press Enter: ", &["Apache-2.0", "MIT"]).unwrap();
+        assert_eq!(answer, 1);
+    }
+
+    #[test]
+    #[ignore = "requires typing into a terminal"]
+    fn interactive_ask_validated() {
+        let answer = ask_validated("Type 'x', then '80' and press Enter: ", port).unwrap();
+        assert_eq!(answer, "80");
+    }
+}
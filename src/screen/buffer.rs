@@ -0,0 +1,389 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A double-buffered grid of styled cells that redraws only what changed.
+
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Write};
+
+use unicode_width::UnicodeWidthChar;
+
+use crayon::{StyledString, Style, Transition};
+use super::{size_or_default, Height, Size, Width};
+
+/// A single character cell of a `Screen`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Cell {
+    /// The character in the cell, or `None` if the cell is covered by the
+    /// wide character in the cell before it.
+    c: Option<char>,
+    style: Style,
+}
+
+impl Cell {
+    /// Returns an empty cell, which is what the terminal shows after it is
+    /// cleared.
+    fn blank() -> Cell {
+        Cell { c: Some(' '), style: Style::default() }
+    }
+}
+
+/// A grid of styled cells the size of the terminal, which is drawn by writing
+/// only the cells that changed since the last time it was drawn.
+///
+/// Draw a frame by writing to the cells with methods such as `write_str()`,
+/// then call `flush()` to bring the terminal up to date. The cells keep their
+/// contents between frames, so either update just the parts that change or
+/// call `clear()` and draw everything again. Either way, the terminal only
+/// receives the difference from the previous frame, which avoids the flicker
+/// of clearing and redrawing the whole screen.
+///
+/// The first flush clears the terminal and draws every cell, as does the one
+/// after `resize()` or `invalidate()`.
+///
+/// ```no_run
+/// use tutil::crayon::Color::Green;
+/// use tutil::screen::{AlternateScreen, Screen};
+///
+/// let _alternate = AlternateScreen::new(std::io::stdout()).unwrap();
+/// let mut screen = Screen::new();
+///
+/// for i in 0..100 {
+///     screen.write_styled(0, 0, &Green.paint(format!("Step {}", i)));
+///     screen.flush().unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Screen<W: Write = io::Stdout> {
+    writer: W,
+    size: Size,
+    /// The frame being drawn.
+    cells: Vec<Cell>,
+    /// The frame that the terminal is showing.
+    rendered: Vec<Cell>,
+    /// Whether the terminal needs to be cleared and redrawn completely.
+    invalidated: bool,
+}
+
+impl Screen<io::Stdout> {
+    /// Creates a screen the size of the terminal that draws to standard
+    /// output, assuming 80 columns by 24 rows if the size is unknown.
+    pub fn new() -> Screen<io::Stdout> {
+        Screen::with_writer(io::stdout(), size_or_default())
+    }
+}
+
+impl Default for Screen<io::Stdout> {
+    fn default() -> Screen<io::Stdout> {
+        Screen::new()
+    }
+}
+
+impl<W: Write> Screen<W> {
+    /// Creates a screen of the given size that draws to `writer`.
+    pub fn with_writer(writer: W, size: Size) -> Screen<W> {
+        let blank = vec![Cell::blank(); size.area() as usize];
+        Screen { writer, size, cells: blank.clone(), rendered: blank, invalidated: true }
+    }
+
+    /// Returns the size of the screen.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Changes the size of the screen, such as after the terminal has been
+    /// resized. Every cell is cleared and the next flush redraws everything.
+    pub fn resize(&mut self, size: Size) {
+        let blank = vec![Cell::blank(); size.area() as usize];
+
+        self.size = size;
+        self.cells = blank.clone();
+        self.rendered = blank;
+        self.invalidated = true;
+    }
+
+    /// Makes the next flush clear the terminal and redraw every cell, such as
+    /// after something else has written to it.
+    pub fn invalidate(&mut self) {
+        self.invalidated = true;
+    }
+
+    /// Blanks every cell of the frame being drawn.
+    pub fn clear(&mut self) {
+        for cell in &mut self.cells {
+            *cell = Cell::blank();
+        }
+    }
+
+    /// Sets the cell at column `x` and row `y`, counted from zero, to `c` in
+    /// `style`, returning the number of columns that it takes up.
+    ///
+    /// A wide character also takes up the cell after it. Nothing is set if the
+    /// character does not fit on the screen or is a control character.
+    pub fn set(&mut self, x: u16, y: u16, c: char, style: Style) -> u16 {
+        let Size(Width(width), Height(height)) = self.size;
+        let char_width = match c.width() {
+            Some(char_width) if char_width > 0 => char_width as u16,
+            _ => return 0,
+        };
+
+        if y >= height || x as u32 + char_width as u32 > width as u32 {
+            return 0;
+        }
+
+        let index = self.index(x, y);
+
+        // Overwriting either half of a wide character leaves the other half
+        // blank rather than showing half of a character.
+        if self.cells[index].c.is_none() {
+            self.cells[index - 1] = Cell::blank();
+        }
+
+        let end = index + char_width as usize;
+        if end < self.cells.len() && self.cells[end].c.is_none() && (x + char_width) < width {
+            self.cells[end] = Cell::blank();
+        }
+
+        self.cells[index] = Cell { c: Some(c), style };
+        for covered in &mut self.cells[index + 1..end] {
+            *covered = Cell { c: None, style };
+        }
+
+        char_width
+    }
+
+    /// Writes `s` in `style` starting at column `x` and row `y`, returning the
+    /// number of columns written.
+    ///
+    /// The text does not wrap, so anything past the right edge of the screen
+    /// is cut off, including a wide character that only half fits. Control
+    /// characters, including newlines, are skipped.
+    pub fn write_str(&mut self, x: u16, y: u16, s: &str, style: Style) -> u16 {
+        let Size(Width(width), _) = self.size;
+        let mut column = x;
+
+        for c in s.chars() {
+            if column >= width {
+                break;
+            }
+
+            let written = self.set(column, y, c, style);
+
+            // A visible character that was not written is too wide for the
+            // rest of the row, so nothing after it should take its place.
+            if written == 0 && c.width().unwrap_or(0) > 0 {
+                break;
+            }
+
+            column += written;
+        }
+
+        column.saturating_sub(x)
+    }
+
+    /// Writes a [`StyledString`] starting at column `x` and row `y`, as with
+    /// `write_str()`.
+    ///
+    /// [`StyledString`]: ../crayon/struct.StyledString.html
+    pub fn write_styled(&mut self, x: u16, y: u16, s: &StyledString) -> u16 {
+        self.write_str(x, y, s, s.style())
+    }
+
+    /// Brings the terminal up to date with the frame that has been drawn,
+    /// writing only the cells that changed since the last flush.
+    pub fn flush(&mut self) -> io::Result<()> {
+        let mut output = String::new();
+
+        if self.invalidated {
+            output.push_str("\x1b[0m\x1b[H\x1b[2J");
+
+            for cell in &mut self.rendered {
+                *cell = Cell::blank();
+            }
+
+            self.invalidated = false;
+        }
+
+        let Size(Width(width), _) = self.size;
+        diff(&self.rendered, &self.cells, width as usize, &mut output);
+        self.rendered.clone_from(&self.cells);
+
+        if !output.is_empty() {
+            try!(self.writer.write_all(output.as_bytes()));
+        }
+
+        self.writer.flush()
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Consumes the `Screen`, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Returns the index in `cells` of the cell at column `x` and row `y`.
+    fn index(&self, x: u16, y: u16) -> usize {
+        let Size(Width(width), _) = self.size;
+        y as usize * width as usize + x as usize
+    }
+}
+
+/// Appends to `output` the cursor movements, escape codes and text that turn
+/// the frame `before` into the frame `after`, both of which are `width` cells
+/// wide, leaving the style reset at the end.
+///
+/// The terminal is assumed to be in the default style to begin with. Runs of
+/// changed cells on the same row are written after a single cursor movement.
+fn diff(before: &[Cell], after: &[Cell], width: usize, output: &mut String) {
+    let mut cursor = None;
+    let mut style = Style::default();
+
+    for (i, (old, new)) in before.iter().zip(after).enumerate() {
+        let c = match new.c {
+            Some(c) if old != new => c,
+            // The cell is either unchanged or the second half of a wide
+            // character, which was drawn along with the first half.
+            _ => continue,
+        };
+
+        let position = (i % width, i / width);
+
+        if cursor != Some(position) {
+            let _ = write!(output, "\x1b[{};{}H", position.1 + 1, position.0 + 1);
+        }
+
+        let _ = write!(output, "{}{}", Transition(style, new.style), c);
+        style = new.style;
+
+        // Once the last column is written, terminals differ in where they
+        // leave the cursor, so it is always moved explicitly afterwards.
+        let next = position.0 + c.width().unwrap_or(1);
+        cursor = if next < width { Some((next, position.1)) } else { None };
+    }
+
+    let _ = write!(output, "{}", Transition(style, Style::default()));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crayon;
+    use crayon::Color::{Blue, Red};
+
+    /// Returns the output of flushing `screen` and clears it for the next
+    /// flush.
+    fn flushed(screen: &mut Screen<Vec<u8>>) -> String {
+        screen.flush().unwrap();
+        String::from_utf8(screen.get_mut().split_off(0)).unwrap()
+    }
+
+    fn small_screen() -> Screen<Vec<u8>> {
+        Screen::with_writer(Vec::new(), Size(Width(4), Height(2)))
+    }
+
+    #[test]
+    fn first_flush_clears_and_draws() {
+        let mut screen = small_screen();
+        screen.write_str(0, 1, "ab", Style::default());
+
+        assert_eq!(flushed(&mut screen), "\x1b[0m\x1b[H\x1b[2J\x1b[2;1Hab");
+    }
+
+    #[test]
+    fn unchanged_frame_writes_nothing() {
+        let mut screen = small_screen();
+        screen.write_styled(0, 0, &Red.paint("abcd"));
+        flushed(&mut screen);
+
+        screen.write_styled(0, 0, &Red.paint("abcd"));
+        assert_eq!(flushed(&mut screen), "");
+    }
+
+    #[test]
+    fn single_changed_cell_is_one_move_and_write() {
+        let mut screen = small_screen();
+        screen.write_str(0, 0, "abcd", Style::default());
+        flushed(&mut screen);
+
+        screen.set(2, 1, 'x', Style::default());
+        assert_eq!(flushed(&mut screen), "\x1b[2;3Hx");
+    }
+
+    #[test]
+    fn run_of_changes_needs_one_move() {
+        crayon::set_enabled(true);
+        let mut screen = small_screen();
+        flushed(&mut screen);
+
+        screen.write_styled(1, 0, &Red.paint("ab"));
+        screen.set(3, 0, 'c', Blue.normal());
+        screen.set(1, 1, 'd', Blue.normal());
+
+        assert_eq!(flushed(&mut screen),
+                   "\x1b[1;2H\x1b[31mab\x1b[34mc\x1b[2;2Hd\x1b[0m");
+    }
+
+    #[test]
+    fn clear_writes_blanks_over_old_cells() {
+        let mut screen = small_screen();
+        screen.write_str(0, 0, "ab", Style::default());
+        flushed(&mut screen);
+
+        screen.clear();
+        assert_eq!(flushed(&mut screen), "\x1b[1;1H  ");
+    }
+
+    #[test]
+    fn wide_characters_cover_two_cells() {
+        let mut screen = small_screen();
+        flushed(&mut screen);
+
+        assert_eq!(screen.write_str(0, 0, "日本語", Style::default()), 4);
+        assert_eq!(flushed(&mut screen), "\x1b[1;1H日本");
+
+        screen.set(1, 0, 'x', Style::default());
+        assert_eq!(flushed(&mut screen), "\x1b[1;1H x");
+    }
+
+    #[test]
+    fn wide_character_at_right_edge_ends_the_text() {
+        let mut screen = small_screen();
+        flushed(&mut screen);
+
+        assert_eq!(screen.write_str(0, 0, "abc日d", Style::default()), 3);
+        assert_eq!(flushed(&mut screen), "\x1b[1;1Habc");
+    }
+
+    #[test]
+    fn writes_outside_screen_are_ignored() {
+        let mut screen = small_screen();
+        flushed(&mut screen);
+
+        assert_eq!(screen.set(4, 0, 'x', Style::default()), 0);
+        assert_eq!(screen.set(0, 2, 'x', Style::default()), 0);
+        assert_eq!(screen.set(0, 0, '\n', Style::default()), 0);
+        assert_eq!(flushed(&mut screen), "");
+    }
+
+    #[test]
+    fn resize_redraws_everything() {
+        let mut screen = small_screen();
+        screen.write_str(0, 0, "ab", Style::default());
+        flushed(&mut screen);
+
+        screen.resize(Size(Width(2), Height(1)));
+        screen.write_str(0, 0, "ab", Style::default());
+        assert_eq!(screen.size(), Size(Width(2), Height(1)));
+        assert_eq!(flushed(&mut screen), "\x1b[0m\x1b[H\x1b[2J\x1b[1;1Hab");
+    }
+}
@@ -0,0 +1,141 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A registry of the guards that currently have the terminal in an unusual
+//! state, so that the state can be restored if the process is interrupted.
+//!
+//! The registry is read from a signal handler, so it is made up of nothing but
+//! atomic counters and must never allocate or take a lock.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A kind of guard that changes the state of the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Guard {
+    /// Mouse events are being reported, by `MouseCapture`.
+    MouseCapture,
+
+    /// The alternate screen is being shown, by `AlternateScreen`.
+    AlternateScreen,
+
+    /// The cursor is hidden, by `HiddenCursor`.
+    HiddenCursor,
+
+    /// Standard input is in raw mode, by `RawMode`.
+    RawMode,
+}
+
+/// Every kind of guard, in the order that they should be undone.
+const GUARDS: [Guard; 4] = [Guard::MouseCapture, Guard::AlternateScreen, Guard::HiddenCursor,
+                           Guard::RawMode];
+
+impl Guard {
+    /// Returns the escape code that undoes this guard, or an empty slice if it
+    /// is undone some other way.
+    pub fn undo_code(self) -> &'static [u8] {
+        match self {
+            Guard::MouseCapture => b"\x1b[?1006l\x1b[?1000l",
+            Guard::AlternateScreen => b"\x1b[?1049l",
+            Guard::HiddenCursor => b"\x1b[?25h",
+            Guard::RawMode => b"",
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Guard::MouseCapture => 0,
+            Guard::AlternateScreen => 1,
+            Guard::HiddenCursor => 2,
+            Guard::RawMode => 3,
+        }
+    }
+}
+
+/// Counts of how many guards of each kind are currently alive.
+#[derive(Debug)]
+pub(crate) struct Registry {
+    active: [AtomicUsize; 4],
+}
+
+impl Registry {
+    pub const fn new() -> Registry {
+        Registry {
+            active: [AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0),
+                     AtomicUsize::new(0)],
+        }
+    }
+
+    /// Records that a guard of the given kind has been created.
+    pub fn push(&self, guard: Guard) {
+        self.active[guard.index()].fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Records that a guard of the given kind has been dropped.
+    pub fn pop(&self, guard: Guard) {
+        let count = &self.active[guard.index()];
+        let _ = count.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1));
+    }
+
+    /// Returns true if at least one guard of the given kind is alive.
+    pub fn is_active(&self, guard: Guard) -> bool {
+        self.active[guard.index()].load(Ordering::SeqCst) > 0
+    }
+
+    /// Returns the kinds of guard that are alive, in the order that they
+    /// should be undone.
+    pub fn active(&self) -> impl Iterator<Item = Guard> + '_ {
+        GUARDS.iter().cloned().filter(move |&guard| self.is_active(guard))
+    }
+}
+
+/// The registry of the guards that are alive in this process.
+pub(crate) static REGISTRY: Registry = Registry::new();
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn push_and_pop() {
+        let registry = Registry::new();
+        assert!(!registry.is_active(Guard::HiddenCursor));
+
+        registry.push(Guard::HiddenCursor);
+        registry.push(Guard::HiddenCursor);
+        registry.pop(Guard::HiddenCursor);
+        assert!(registry.is_active(Guard::HiddenCursor));
+
+        registry.pop(Guard::HiddenCursor);
+        assert!(!registry.is_active(Guard::HiddenCursor));
+    }
+
+    #[test]
+    fn pop_without_push() {
+        let registry = Registry::new();
+        registry.pop(Guard::RawMode);
+        registry.push(Guard::RawMode);
+
+        assert!(registry.is_active(Guard::RawMode));
+    }
+
+    #[test]
+    fn active_in_undo_order() {
+        let registry = Registry::new();
+        registry.push(Guard::RawMode);
+        registry.push(Guard::HiddenCursor);
+        registry.push(Guard::AlternateScreen);
+        registry.push(Guard::MouseCapture);
+
+        let active: Vec<Guard> = registry.active().collect();
+        assert_eq!(active, [Guard::MouseCapture, Guard::AlternateScreen, Guard::HiddenCursor,
+                            Guard::RawMode]);
+
+        registry.pop(Guard::MouseCapture);
+
+        registry.pop(Guard::HiddenCursor);
+
+        let active: Vec<Guard> = registry.active().collect();
+        assert_eq!(active, [Guard::AlternateScreen, Guard::RawMode]);
+    }
+}
@@ -23,11 +23,38 @@
 //! ```
 
 use std::fmt;
+use std::io::{self, Write};
+#[cfg(feature = "test-util")]
+use std::sync::Mutex;
+
+use system;
+
+use self::cleanup::{Guard, REGISTRY};
+
+mod buffer;
+mod cleanup;
+pub mod mouse;
+
+pub use self::buffer::Screen;
 
 /// Represents the width of a terminal.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Width(pub u16);
 
+impl Width {
+    /// Formats the width followed by its unit, such as `80 cols`.
+    ///
+    /// ```
+    /// use tutil::screen::Width;
+    ///
+    /// assert_eq!(Width(80).fmt_with_unit(), "80 cols");
+    /// ```
+    pub fn fmt_with_unit(&self) -> String {
+        let Width(width) = *self;
+        format!("{} {}", width, if width == 1 { "col" } else { "cols" })
+    }
+}
+
 impl fmt::Display for Width {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let Width(width) = *self;
@@ -38,9 +65,23 @@ impl fmt::Display for Width {
 }
 
 /// Represents the height of a terminal.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Height(pub u16);
 
+impl Height {
+    /// Formats the height followed by its unit, such as `24 rows`.
+    ///
+    /// ```
+    /// use tutil::screen::Height;
+    ///
+    /// assert_eq!(Height(24).fmt_with_unit(), "24 rows");
+    /// ```
+    pub fn fmt_with_unit(&self) -> String {
+        let Height(height) = *self;
+        format!("{} {}", height, if height == 1 { "row" } else { "rows" })
+    }
+}
+
 impl fmt::Display for Height {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let Height(height) = *self;
@@ -50,6 +91,355 @@ impl fmt::Display for Height {
     }
 }
 
+/// Represents the size of a terminal, as its width and height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Size(pub Width, pub Height);
+
+impl Size {
+    /// Returns the number of character cells in a screen of this size.
+    ///
+    /// The result is a `u32`, since the area of even a modest screen can be
+    /// too large for a `u16`.
+    ///
+    /// ```
+    /// use tutil::screen::{Size, Width, Height};
+    ///
+    /// assert_eq!(Size(Width(80), Height(24)).area(), 1920);
+    /// ```
+    pub fn area(&self) -> u32 {
+        let Size(Width(width), Height(height)) = *self;
+        width as u32 * height as u32
+    }
+
+    /// Returns true if either the width or the height is zero, so that
+    /// nothing can be displayed.
+    pub fn is_empty(&self) -> bool {
+        let Size(Width(width), Height(height)) = *self;
+        width == 0 || height == 0
+    }
+
+    /// Describes the size in words, such as `80 columns × 24 rows`, for
+    /// showing to the user.
+    ///
+    /// ```
+    /// use tutil::screen::{Size, Width, Height};
+    ///
+    /// assert_eq!(Size(Width(80), Height(24)).describe(), "80 columns × 24 rows");
+    /// ```
+    pub fn describe(&self) -> String {
+        let Size(Width(width), Height(height)) = *self;
+
+        format!("{} {} × {} {}",
+                width, if width == 1 { "column" } else { "columns" },
+                height, if height == 1 { "row" } else { "rows" })
+    }
+
+    /// Returns a size that is `frac_w` of the width and `frac_h` of the
+    /// height of this one, such as for a pane that takes up part of the
+    /// screen.
+    ///
+    /// Each dimension is rounded to the nearest cell and is always at least
+    /// one, so that the result can still display something. An infinite
+    /// fraction gives the largest possible dimension, or the smallest if it is
+    /// negative, and a NaN fraction gives the smallest.
+    ///
+    /// ```
+    /// use tutil::screen::{Size, Width, Height};
+    ///
+    /// let pane = Size(Width(80), Height(24)).scale(0.5, 1.0 / 3.0);
+    /// assert_eq!(pane, Size(Width(40), Height(8)));
+    /// ```
+    pub fn scale(&self, frac_w: f32, frac_h: f32) -> Size {
+        let Size(Width(width), Height(height)) = *self;
+        let scale = |n: u16, frac: f32| {
+            let scaled = (n as f32 * frac).round();
+
+            // NaN, which also comes from scaling zero by infinity, would
+            // otherwise be cast to zero.
+            if scaled.is_nan() {
+                1
+            } else {
+                scaled.clamp(1.0, u16::MAX as f32) as u16
+            }
+        };
+
+        Size(Width(scale(width, frac_w)), Height(scale(height, frac_h)))
+    }
+
+    /// Splits the size side by side into one that is `left_cols` wide and one
+    /// with the remaining columns, both of the full height.
+    ///
+    /// If `left_cols` is wider than this size, the left size takes the whole
+    /// width and the right one is zero columns wide.
+    ///
+    /// ```
+    /// use tutil::screen::{Size, Width, Height};
+    ///
+    /// let (sidebar, main) = Size(Width(80), Height(24)).split_horizontal(20);
+    /// assert_eq!(sidebar, Size(Width(20), Height(24)));
+    /// assert_eq!(main, Size(Width(60), Height(24)));
+    /// ```
+    pub fn split_horizontal(&self, left_cols: u16) -> (Size, Size) {
+        let Size(Width(width), height) = *self;
+        let left = left_cols.min(width);
+
+        (Size(Width(left), height), Size(Width(width - left), height))
+    }
+
+    /// Splits the size one above the other into one that is `top_rows` high
+    /// and one with the remaining rows, both of the full width.
+    ///
+    /// If `top_rows` is higher than this size, the top size takes the whole
+    /// height and the bottom one is zero rows high.
+    pub fn split_vertical(&self, top_rows: u16) -> (Size, Size) {
+        let Size(width, Height(height)) = *self;
+        let top = top_rows.min(height);
+
+        (Size(width, Height(top)), Size(width, Height(height - top)))
+    }
+
+    /// Returns the size as the `COLUMNS` and `LINES` environment variables,
+    /// for passing it on to a child process that has no terminal to ask.
+    ///
+    /// ```no_run
+    /// use std::process::Command;
+    /// use tutil::screen;
+    ///
+    /// Command::new("ls")
+    ///     .envs(screen::size_or_default().to_env_pairs().iter().cloned())
+    ///     .status()
+    ///     .unwrap();
+    /// ```
+    pub fn to_env_pairs(&self) -> [(String, String); 2] {
+        let Size(Width(width), Height(height)) = *self;
+
+        [("COLUMNS".to_string(), width.to_string()), ("LINES".to_string(), height.to_string())]
+    }
+
+    /// Reads a size from the `COLUMNS` and `LINES` environment variables, as
+    /// written by [`to_env_pairs()`](#method.to_env_pairs).
+    ///
+    /// `None` is returned unless both are set to a number from 1 to 65535.
+    pub fn from_env() -> Option<Size> {
+        Size::from_env_with(system::var)
+    }
+
+    /// The implementation of `from_env()`, reading environment variables
+    /// through `var`.
+    fn from_env_with<F>(var: F) -> Option<Size> where F: Fn(&str) -> Option<String> {
+        let parse = |name| {
+            var(name).and_then(|value| value.trim().parse().ok()).filter(|&n| n > 0)
+        };
+
+        match (parse("COLUMNS"), parse("LINES")) {
+            (Some(width), Some(height)) => Some(Size(Width(width), Height(height))),
+            _ => None,
+        }
+    }
+}
+
+/// The size to assume when the size of the terminal cannot be determined,
+/// which is the traditional 80 columns by 24 rows.
+pub const DEFAULT_SIZE: Size = Size(Width(80), Height(24));
+
+/// The size set by `set_test_size()`, if any.
+#[cfg(feature = "test-util")]
+static TEST_SIZE: Mutex<Option<Size>> = Mutex::new(None);
+
+/// Makes `size()`, `width()` and `height()` return the given size instead of
+/// querying the terminal, or go back to querying it if `size` is `None`.
+///
+/// This is for testing code that lays out its output according to the size
+/// of the terminal, so that the tests give the same results wherever they are
+/// run. It affects every thread, so tests that rely on different sizes should
+/// not run at the same time. Only available with the `test-util` feature.
+///
+/// ```
+/// use tutil::screen::{self, Size, Width, Height};
+///
+/// screen::set_test_size(Some(Size(Width(40), Height(10))));
+/// assert_eq!(screen::width(), Some(Width(40)));
+///
+/// screen::set_test_size(None);
+/// ```
+#[cfg(feature = "test-util")]
+pub fn set_test_size(size: Option<Size>) {
+    *TEST_SIZE.lock().unwrap_or_else(|e| e.into_inner()) = size;
+}
+
+/// Returns the size set by `set_test_size()`, which the platform
+/// implementations of `size()` return in place of the real size.
+#[cfg(feature = "test-util")]
+fn test_size() -> Option<Size> {
+    *TEST_SIZE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Without the `test-util` feature there is never a test size.
+#[cfg(not(feature = "test-util"))]
+fn test_size() -> Option<Size> {
+    None
+}
+
+/// Returns the terminal screen size, or `fallback` if it cannot be determined.
+///
+/// ```
+/// use tutil::screen::{self, Size, Width, Height};
+///
+/// let Size(Width(width), _) = screen::size_or(Size(Width(100), Height(40)));
+/// println!("Wrapping text at {} columns.", width);
+/// ```
+pub fn size_or(fallback: Size) -> Size {
+    size_or_with(size, fallback)
+}
+
+/// Returns the terminal screen size, or [`DEFAULT_SIZE`] if it cannot be
+/// determined.
+///
+/// [`DEFAULT_SIZE`]: constant.DEFAULT_SIZE.html
+pub fn size_or_default() -> Size {
+    size_or(DEFAULT_SIZE)
+}
+
+/// The implementation of `size_or()`, detecting the size with `detect` so that
+/// it can be tested regardless of whether there is a terminal.
+fn size_or_with<F>(detect: F, fallback: Size) -> Size where F: FnOnce() -> Option<Size> {
+    detect().unwrap_or(fallback)
+}
+
+/// A guard that hides the terminal cursor until it is dropped.
+///
+/// Hiding the cursor avoids flicker when output is redrawn frequently. The
+/// cursor is shown again when the guard is dropped, so it is restored even if
+/// the program panics. To also restore it if the program is interrupted, call
+/// [`install_cleanup()`](fn.install_cleanup.html).
+///
+/// ```no_run
+/// use std::io;
+/// use tutil::screen::HiddenCursor;
+///
+/// let _cursor = HiddenCursor::new(io::stdout()).unwrap();
+/// // Redraw some output...
+/// ```
+#[derive(Debug)]
+pub struct HiddenCursor<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> HiddenCursor<W> {
+    /// Hides the cursor by writing the appropriate escape code to `writer`.
+    pub fn new(mut writer: W) -> io::Result<HiddenCursor<W>> {
+        try!(writer.write_all(b"\x1b[?25l"));
+        try!(writer.flush());
+
+        REGISTRY.push(Guard::HiddenCursor);
+        Ok(HiddenCursor { writer })
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the underlying writer, so that output
+    /// can be written while the cursor is hidden.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+}
+
+impl<W: Write> Drop for HiddenCursor<W> {
+    fn drop(&mut self) {
+        // There is no way to report an error from `drop()`, and there is
+        // nothing more that can be done to restore the cursor anyway.
+        let _ = self.writer.write_all(Guard::HiddenCursor.undo_code());
+        let _ = self.writer.flush();
+        REGISTRY.pop(Guard::HiddenCursor);
+    }
+}
+
+/// A guard that switches the terminal to the alternate screen until it is
+/// dropped.
+///
+/// The alternate screen is a separate buffer without any scrollback, which is
+/// used by full-screen programs such as editors and pagers so that the
+/// original contents of the terminal reappear when they exit. Like
+/// [`HiddenCursor`], the original screen is restored when the guard is
+/// dropped, or on interruption if [`install_cleanup()`] has been called.
+///
+/// ```no_run
+/// use std::io;
+/// use tutil::screen::AlternateScreen;
+///
+/// let _screen = AlternateScreen::new(io::stdout()).unwrap();
+/// // Draw a full-screen interface...
+/// ```
+///
+/// [`HiddenCursor`]: struct.HiddenCursor.html
+/// [`install_cleanup()`]: fn.install_cleanup.html
+#[derive(Debug)]
+pub struct AlternateScreen<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> AlternateScreen<W> {
+    /// Switches to the alternate screen by writing the appropriate escape code
+    /// to `writer`.
+    pub fn new(mut writer: W) -> io::Result<AlternateScreen<W>> {
+        try!(writer.write_all(b"\x1b[?1049h"));
+        try!(writer.flush());
+
+        REGISTRY.push(Guard::AlternateScreen);
+        Ok(AlternateScreen { writer })
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the underlying writer, so that output
+    /// can be written to the alternate screen.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+}
+
+impl<W: Write> Drop for AlternateScreen<W> {
+    fn drop(&mut self) {
+        let _ = self.writer.write_all(Guard::AlternateScreen.undo_code());
+        let _ = self.writer.flush();
+        REGISTRY.pop(Guard::AlternateScreen);
+    }
+}
+
+/// Which of the terminal's input modes are turned on, as reported by
+/// `current_modes()`.
+///
+/// All three are on in a terminal's normal state and off in raw mode, so a
+/// program can tell whether something else has already changed the terminal,
+/// such as when it is run from inside another full-screen program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TermModes {
+    /// Whether typed characters are echoed back to the terminal.
+    pub echo: bool,
+
+    /// Whether input is made available a line at a time, after Enter is
+    /// pressed, with the terminal providing basic line editing.
+    pub canonical: bool,
+
+    /// Whether keys such as Ctrl-C generate signals rather than being read as
+    /// input.
+    pub signals: bool,
+}
+
+impl TermModes {
+    /// Returns true if echo, canonical mode and signal generation are all
+    /// off, as they are in raw mode.
+    pub fn is_raw(&self) -> bool {
+        !self.echo && !self.canonical && !self.signals
+    }
+}
+
 #[cfg(unix)]
 mod unix;
 
@@ -59,9 +449,19 @@ pub use self::unix::size;
 pub use self::unix::width;
 #[cfg(unix)]
 pub use self::unix::height;
+#[cfg(unix)]
+pub use self::unix::cell_pixel_size;
+#[cfg(unix)]
+pub use self::unix::install_cleanup;
+#[cfg(unix)]
+pub use self::unix::current_modes;
+#[cfg(unix)]
+pub(crate) use self::unix::RawMode;
+#[cfg(unix)]
+pub(crate) use self::unix::input_follows;
 
 #[cfg(windows)]
-mod windows;
+pub mod windows;
 
 #[cfg(windows)]
 pub use self::windows::size;
@@ -69,8 +469,152 @@ pub use self::windows::size;
 pub use self::windows::width;
 #[cfg(windows)]
 pub use self::windows::height;
+#[cfg(windows)]
+pub use self::windows::cell_pixel_size;
+#[cfg(windows)]
+pub use self::windows::install_cleanup;
+#[cfg(windows)]
+pub use self::windows::current_modes;
+#[cfg(windows)]
+pub(crate) use self::windows::RawMode;
+#[cfg(windows)]
+pub(crate) use self::windows::input_follows;
 
 #[cfg(test)]
 mod test {
-    // TODO: Test the `fmt::Display` implementations for `Width` and `Height`.
+    use super::*;
+    use system::test::fake_env;
+
+    #[test]
+    fn display_is_numeric() {
+        assert_eq!(Width(80).to_string(), "80");
+        assert_eq!(Height(24).to_string(), "24");
+    }
+
+    #[test]
+    fn format_with_units() {
+        assert_eq!(Width(80).fmt_with_unit(), "80 cols");
+        assert_eq!(Width(1).fmt_with_unit(), "1 col");
+        assert_eq!(Height(24).fmt_with_unit(), "24 rows");
+        assert_eq!(Height(1).fmt_with_unit(), "1 row");
+    }
+
+    #[test]
+    fn area_of_size() {
+        assert_eq!(DEFAULT_SIZE.area(), 1920);
+        assert_eq!(Size(Width(u16::MAX), Height(u16::MAX)).area(), 65535 * 65535);
+        assert!(!DEFAULT_SIZE.is_empty());
+    }
+
+    #[test]
+    fn zero_dimension_size() {
+        assert_eq!(Size(Width(0), Height(24)).area(), 0);
+        assert!(Size(Width(0), Height(24)).is_empty());
+        assert!(Size(Width(80), Height(0)).is_empty());
+    }
+
+    #[test]
+    fn scale_by_half() {
+        assert_eq!(DEFAULT_SIZE.scale(0.5, 0.5), Size(Width(40), Height(12)));
+        assert_eq!(Size(Width(81), Height(25)).scale(0.5, 0.5), Size(Width(41), Height(13)));
+    }
+
+    #[test]
+    fn scale_is_at_least_one_cell() {
+        assert_eq!(DEFAULT_SIZE.scale(0.0, -1.0), Size(Width(1), Height(1)));
+        assert_eq!(DEFAULT_SIZE.scale(1000.0, 1.0), Size(Width(u16::MAX), Height(24)));
+    }
+
+    #[test]
+    fn scale_by_non_finite_fractions() {
+        assert_eq!(DEFAULT_SIZE.scale(f32::NAN, f32::NAN), Size(Width(1), Height(1)));
+        assert_eq!(DEFAULT_SIZE.scale(f32::INFINITY, f32::NEG_INFINITY),
+                   Size(Width(u16::MAX), Height(1)));
+        assert_eq!(Size(Width(0), Height(24)).scale(f32::INFINITY, 0.5),
+                   Size(Width(1), Height(12)));
+    }
+
+    #[test]
+    fn split_horizontal_sums_to_width() {
+        let (left, right) = DEFAULT_SIZE.split_horizontal(30);
+        assert_eq!(left, Size(Width(30), Height(24)));
+        assert_eq!((left.0).0 + (right.0).0, 80);
+        assert_eq!(right.1, Height(24));
+
+        assert_eq!(DEFAULT_SIZE.split_horizontal(100).1, Size(Width(0), Height(24)));
+    }
+
+    #[test]
+    fn size_round_trips_through_env_pairs() {
+        let size = Size(Width(132), Height(43));
+        let pairs = size.to_env_pairs();
+        assert_eq!(pairs, [("COLUMNS".to_string(), "132".to_string()),
+                           ("LINES".to_string(), "43".to_string())]);
+
+        let vars: Vec<(&str, &str)> = pairs.iter().map(|(k, v)| (&k[..], &v[..])).collect();
+        assert_eq!(Size::from_env_with(fake_env(&vars)), Some(size));
+    }
+
+    #[test]
+    fn size_from_incomplete_env() {
+        assert_eq!(Size::from_env_with(fake_env(&[("COLUMNS", "80")])), None);
+        assert_eq!(Size::from_env_with(fake_env(&[("COLUMNS", "80"), ("LINES", "0")])), None);
+        assert_eq!(Size::from_env_with(fake_env(&[("COLUMNS", "wide"), ("LINES", "24")])), None);
+    }
+
+    #[test]
+    fn split_vertical_sums_to_height() {
+        let (top, bottom) = DEFAULT_SIZE.split_vertical(1);
+        assert_eq!(top, Size(Width(80), Height(1)));
+        assert_eq!(bottom, Size(Width(80), Height(23)));
+    }
+
+    #[test]
+    fn describe_size() {
+        assert_eq!(DEFAULT_SIZE.describe(), "80 columns × 24 rows");
+        assert_eq!(Size(Width(1), Height(1)).describe(), "1 column × 1 row");
+    }
+
+    #[test]
+    fn size_or_uses_fallback_when_undetected() {
+        let fallback = Size(Width(100), Height(40));
+        assert_eq!(size_or_with(|| None, fallback), fallback);
+        assert_eq!(size_or_with(|| None, DEFAULT_SIZE), Size(Width(80), Height(24)));
+    }
+
+    #[test]
+    fn size_or_prefers_detected_size() {
+        let detected = Size(Width(132), Height(43));
+        assert_eq!(size_or_with(|| Some(detected), DEFAULT_SIZE), detected);
+    }
+
+    #[test]
+    fn cursor_hidden_on_construction() {
+        let cursor = HiddenCursor::new(Vec::new()).unwrap();
+        assert_eq!(cursor.get_ref(), b"\x1b[?25l");
+    }
+
+    #[test]
+    fn cursor_shown_on_drop() {
+        let mut output = Vec::new();
+
+        {
+            let mut cursor = HiddenCursor::new(&mut output).unwrap();
+            cursor.get_mut().write_all(b"TEST").unwrap();
+        }
+
+        assert_eq!(output, b"\x1b[?25lTEST\x1b[?25h");
+    }
+
+    #[test]
+    fn alternate_screen_left_on_drop() {
+        let mut output = Vec::new();
+
+        {
+            let mut screen = AlternateScreen::new(&mut output).unwrap();
+            screen.get_mut().write_all(b"TEST").unwrap();
+        }
+
+        assert_eq!(output, b"\x1b[?1049hTEST\x1b[?1049l");
+    }
 }
@@ -0,0 +1,250 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Escape sequences for turning mouse reporting on and off.
+//!
+//! While mouse reporting is on, the terminal sends clicks and wheel movements
+//! to standard input as SGR-encoded escape sequences such as `\x1b[<0;12;5M`,
+//! instead of using them for selecting text. Use
+//! `tutil::system::supports_mouse()` to check whether the terminal is likely
+//! to understand these sequences before writing them.
+//!
+//! # Basic Usage
+//!
+//! ```no_run
+//! use std::io;
+//! use tutil::screen::mouse::MouseCapture;
+//!
+//! let _mouse = MouseCapture::new(io::stdout()).unwrap();
+//! // Read input and decode it with `mouse::parse_event()`...
+//! ```
+
+use std::io::{self, Write};
+use std::str;
+
+use super::cleanup::{Guard, REGISTRY};
+
+/// Returns the escape sequence that turns on reporting of mouse button
+/// presses and releases, using the SGR encoding.
+///
+/// The SGR encoding is used because, unlike the original encoding, it is not
+/// limited to the first 223 columns and rows.
+pub fn enable() -> &'static str {
+    "\x1b[?1000h\x1b[?1006h"
+}
+
+/// Returns the escape sequence that turns off mouse reporting, undoing
+/// `enable()`.
+pub fn disable() -> &'static str {
+    "\x1b[?1006l\x1b[?1000l"
+}
+
+/// A guard that turns on mouse reporting until it is dropped.
+///
+/// Like [`HiddenCursor`], mouse reporting is turned off again when the guard
+/// is dropped, or on interruption if [`install_cleanup()`] has been called,
+/// so that the terminal does not keep sending mouse events to the shell.
+///
+/// [`HiddenCursor`]: ../struct.HiddenCursor.html
+/// [`install_cleanup()`]: ../fn.install_cleanup.html
+#[derive(Debug)]
+pub struct MouseCapture<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> MouseCapture<W> {
+    /// Turns on mouse reporting by writing `enable()` to `writer`.
+    pub fn new(mut writer: W) -> io::Result<MouseCapture<W>> {
+        try!(writer.write_all(enable().as_bytes()));
+        try!(writer.flush());
+
+        REGISTRY.push(Guard::MouseCapture);
+        Ok(MouseCapture { writer })
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the underlying writer, so that output
+    /// can be written while mouse events are being reported.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+}
+
+impl<W: Write> Drop for MouseCapture<W> {
+    fn drop(&mut self) {
+        let _ = self.writer.write_all(Guard::MouseCapture.undo_code());
+        let _ = self.writer.flush();
+        REGISTRY.pop(Guard::MouseCapture);
+    }
+}
+
+/// The button involved in a mouse event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    /// The left, or primary, button.
+    Left,
+
+    /// The middle button, which is usually pressing the wheel.
+    Middle,
+
+    /// The right, or secondary, button.
+    Right,
+
+    /// The wheel being scrolled up, which is only ever pressed.
+    WheelUp,
+
+    /// The wheel being scrolled down, which is only ever pressed.
+    WheelDown,
+
+    /// The wheel being tilted left, which is only ever pressed.
+    WheelLeft,
+
+    /// The wheel being tilted right, which is only ever pressed.
+    WheelRight,
+
+    /// Any other button, identified by the number that the terminal reported
+    /// for it with the modifier keys removed.
+    Other(u16),
+}
+
+/// A mouse button being pressed or released at a position on the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MouseEvent {
+    /// The button that was pressed or released.
+    pub button: MouseButton,
+
+    /// The column of the event, where the leftmost column is 1.
+    pub x: u16,
+
+    /// The row of the event, where the top row is 1.
+    pub y: u16,
+
+    /// True if the button was pressed and false if it was released.
+    pub pressed: bool,
+}
+
+/// Decodes an SGR mouse sequence, such as `\x1b[<0;12;5M`, into a
+/// `MouseEvent`.
+///
+/// The sequence has the form `ESC [ < button ; x ; y` followed by `M` for a
+/// press or `m` for a release. Modifier keys held during the event are
+/// ignored. Returns `None` unless `bytes` is exactly one such sequence.
+///
+/// ```
+/// use tutil::screen::mouse::{self, MouseButton, MouseEvent};
+///
+/// let event = mouse::parse_event(b"\x1b[<0;12;5M").unwrap();
+/// assert_eq!(event, MouseEvent { button: MouseButton::Left, x: 12, y: 5, pressed: true });
+/// ```
+pub fn parse_event(bytes: &[u8]) -> Option<MouseEvent> {
+    if bytes.len() < 4 || !bytes.starts_with(b"\x1b[<") {
+        return None;
+    }
+
+    let (params, last) = bytes[3..].split_at(bytes.len() - 4);
+    let pressed = match last {
+        b"M" => true,
+        b"m" => false,
+        _ => return None,
+    };
+
+    let mut numbers = params.split(|&b| b == b';').map(parse_number);
+    let (code, x, y) = match (numbers.next(), numbers.next(), numbers.next(), numbers.next()) {
+        (Some(Some(code)), Some(Some(x)), Some(Some(y)), None) => (code, x, y),
+        _ => return None,
+    };
+
+    // Shift, Meta and Control are reported in bits 2 to 4, and motion while a
+    // button is held in bit 5.
+    let button = match code & !0b11_1100 {
+        0 => MouseButton::Left,
+        1 => MouseButton::Middle,
+        2 => MouseButton::Right,
+        64 => MouseButton::WheelUp,
+        65 => MouseButton::WheelDown,
+        66 => MouseButton::WheelLeft,
+        67 => MouseButton::WheelRight,
+        other => MouseButton::Other(other),
+    };
+
+    Some(MouseEvent { button, x, y, pressed })
+}
+
+/// Parses a parameter of a mouse sequence, which must be a non-empty run of
+/// decimal digits.
+fn parse_number(digits: &[u8]) -> Option<u16> {
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    str::from_utf8(digits).ok().and_then(|digits| digits.parse().ok())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn enable_and_disable_sequences() {
+        assert_eq!(enable(), "\x1b[?1000h\x1b[?1006h");
+        assert_eq!(disable(), "\x1b[?1006l\x1b[?1000l");
+    }
+
+    #[test]
+    fn capture_enabled_and_disabled() {
+        let mut output = Vec::new();
+
+        {
+            let mut capture = MouseCapture::new(&mut output).unwrap();
+            capture.get_mut().write_all(b"TEST").unwrap();
+        }
+
+        assert_eq!(output, format!("{}TEST{}", enable(), disable()).as_bytes());
+    }
+
+    #[test]
+    fn parse_press_and_release() {
+        assert_eq!(parse_event(b"\x1b[<0;1;1M"),
+                   Some(MouseEvent { button: MouseButton::Left, x: 1, y: 1, pressed: true }));
+        assert_eq!(parse_event(b"\x1b[<2;300;120m"),
+                   Some(MouseEvent { button: MouseButton::Right, x: 300, y: 120, pressed: false }));
+        assert_eq!(parse_event(b"\x1b[<1;5;6M").map(|event| event.button),
+                   Some(MouseButton::Middle));
+    }
+
+    #[test]
+    fn parse_wheel() {
+        let buttons: Vec<_> = [&b"\x1b[<64;10;3M"[..], b"\x1b[<65;10;3M", b"\x1b[<66;1;1M",
+                               b"\x1b[<67;1;1M"]
+            .iter()
+            .map(|bytes| parse_event(bytes).unwrap().button)
+            .collect();
+
+        assert_eq!(buttons, [MouseButton::WheelUp, MouseButton::WheelDown,
+                             MouseButton::WheelLeft, MouseButton::WheelRight]);
+    }
+
+    #[test]
+    fn parse_ignores_modifiers() {
+        // Control (16) with the left button, and Shift (4) with the wheel.
+        assert_eq!(parse_event(b"\x1b[<16;2;3M").unwrap().button, MouseButton::Left);
+        assert_eq!(parse_event(b"\x1b[<68;2;3M").unwrap().button, MouseButton::WheelUp);
+        // Dragging with the left button held (32).
+        assert_eq!(parse_event(b"\x1b[<32;2;3M").unwrap().button, MouseButton::Left);
+        assert_eq!(parse_event(b"\x1b[<128;2;3M").unwrap().button, MouseButton::Other(128));
+    }
+
+    #[test]
+    fn parse_rejects_malformed() {
+        for bytes in &[&b""[..], b"\x1b[<", b"\x1b[<M", b"\x1b[<0;1M", b"\x1b[<0;1;2;3M",
+                       b"\x1b[<0;1;2X", b"\x1b[<0;;2M", b"\x1b[<a;1;2M", b"\x1b[0;1;2M",
+                       b"\x1b[<0;1;99999M", b"\x1b[<0;1;2Mx"] {
+            assert_eq!(parse_event(bytes), None, "{:?}", bytes);
+        }
+    }
+}
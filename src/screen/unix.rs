@@ -4,10 +4,22 @@
 
 //! Unix implementation of `tutil::screen`, tested on Linux, FreeBSD and macOS.
 
-use super::{Width, Height};
+use super::{Width, Height, Size, TermModes};
+use super::cleanup::{Guard, REGISTRY};
 
-use std::os::raw::c_ushort;
-use libc::{ioctl, isatty, STDOUT_FILENO, TIOCGWINSZ};
+use std::io::{self, BufRead};
+use std::mem;
+use std::os::raw::{c_int, c_ushort, c_void};
+use std::sync::OnceLock;
+use libc::{ioctl, isatty, STDIN_FILENO, STDOUT_FILENO, TIOCGWINSZ};
+use libc::{cfmakeraw, tcgetattr, tcsetattr, termios, OPOST, TCSADRAIN};
+use libc::{ECHO, ICANON, ISIG};
+use libc::{fcntl, poll, pollfd, F_GETFL, F_SETFL, O_NONBLOCK, POLLIN};
+use libc::{raise, signal, sighandler_t, write, SIGINT, SIGTERM, SIG_DFL, SIG_ERR};
+
+/// The mode that standard input was in before raw mode was first enabled,
+/// for the signal handler to restore.
+static ORIGINAL_MODE: OnceLock<termios> = OnceLock::new();
 
 /// The struct required by the `TIOCGWINSZ` syscall; specified in the following
 /// [man page](http://www.delorie.com/djgpp/doc/libc/libc_495.html).
@@ -23,11 +35,36 @@ struct WinSize {
     ws_ypixel: c_ushort,
 }
 
+/// The largest number of columns or rows that a terminal is believed to have.
+/// Some pseudo-terminals report sizes such as 65535 columns, which are treated
+/// as a failure to detect the size rather than being passed on.
+const MAX_DIMENSION: u16 = 10000;
+
 /// Returns the terminal screen size (in columns and rows).
 ///
-/// Returns `None` if the screen size is `(0, 0)` or is not able to be
-/// determined.
-pub fn size() -> Option<(Width, Height)> {
+/// Returns `None` if the screen size is not able to be determined, or if
+/// either dimension is implausible: 0, or more than 10000 columns or rows.
+pub fn size() -> Option<Size> {
+    if let Some(size) = super::test_size() {
+        return Some(size);
+    }
+
+    winsize().and_then(|winsize| winsize_to_size(&winsize))
+}
+
+/// Returns the size of one character cell in pixels, as width and height,
+/// such as for scaling an image to cover an exact number of cells.
+///
+/// The size is the terminal's size in pixels divided by its size in cells,
+/// rounded to the nearest pixel. Returns `None` if either size cannot be
+/// determined; many terminals leave the size in pixels as 0.
+pub fn cell_pixel_size() -> Option<(u16, u16)> {
+    winsize().and_then(|winsize| winsize_to_cell_pixels(&winsize))
+}
+
+/// Returns the result of the `TIOCGWINSZ` syscall on standard output, or
+/// `None` if it is not a terminal.
+fn winsize() -> Option<WinSize> {
     let is_tty = unsafe { isatty(STDOUT_FILENO) == 1 };
 
     if !is_tty { return None; }
@@ -38,13 +75,38 @@ pub fn size() -> Option<(Width, Height)> {
         ioctl(STDOUT_FILENO, TIOCGWINSZ, &mut winsize) == 0
     };
 
-    if success {
-        Some((Width(winsize.ws_col), Height(winsize.ws_row)))
+    if success { Some(winsize) } else { None }
+}
+
+/// Converts the result of the `TIOCGWINSZ` syscall into a `Size`, returning
+/// `None` if it is implausible.
+fn winsize_to_size(winsize: &WinSize) -> Option<Size> {
+    let plausible = |n: u16| n > 0 && n <= MAX_DIMENSION;
+
+    if plausible(winsize.ws_col) && plausible(winsize.ws_row) {
+        Some(Size(Width(winsize.ws_col), Height(winsize.ws_row)))
     } else {
         None
     }
 }
 
+/// Divides the pixel size from the `TIOCGWINSZ` syscall by its size in cells,
+/// returning `None` if any of them are 0.
+fn winsize_to_cell_pixels(winsize: &WinSize) -> Option<(u16, u16)> {
+    let divide = |pixels: u16, cells: u16| {
+        if pixels == 0 || cells == 0 {
+            None
+        } else {
+            Some(((pixels as u32 + cells as u32 / 2) / cells as u32) as u16)
+        }
+    };
+
+    match (divide(winsize.ws_xpixel, winsize.ws_col), divide(winsize.ws_ypixel, winsize.ws_row)) {
+        (Some(width), Some(height)) => Some((width, height)),
+        _ => None,
+    }
+}
+
 /// Returns the terminal screen width (in columns).
 ///
 /// Returns `None` if the terminal width is detected as being <= 0 columns or is
@@ -52,7 +114,7 @@ pub fn size() -> Option<(Width, Height)> {
 pub fn width() -> Option<Width> {
     let size = size();
 
-    if let Some((Width(width), Height(_))) = size {
+    if let Some(Size(Width(width), Height(_))) = size {
         Some(Width(width))
     } else {
         None
@@ -66,17 +128,159 @@ pub fn width() -> Option<Width> {
 pub fn height() -> Option<Height> {
     let size = size();
 
-    if let Some((Width(_), Height(height))) = size {
+    if let Some(Size(Width(_), Height(height))) = size {
         Some(Height(height))
     } else {
         None
     }
 }
 
+/// A guard that puts the terminal attached to standard input into raw mode,
+/// restoring the original mode when dropped.
+///
+/// In raw mode input is made available a byte at a time, without echo or line
+/// editing, and Ctrl-C is read as a byte rather than raising `SIGINT`. Output
+/// processing is left enabled so that `\n` still starts a new line.
+pub struct RawMode {
+    original: termios,
+}
+
+impl RawMode {
+    /// Enables raw mode, failing if standard input is not a terminal.
+    pub fn enable() -> io::Result<RawMode> {
+        let mut termios: termios = unsafe { mem::zeroed() };
+
+        if unsafe { tcgetattr(STDIN_FILENO, &mut termios) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let original = termios;
+
+        unsafe { cfmakeraw(&mut termios) };
+        termios.c_oflag |= OPOST;
+
+        if unsafe { tcsetattr(STDIN_FILENO, TCSADRAIN, &termios) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let _ = ORIGINAL_MODE.set(original);
+        REGISTRY.push(Guard::RawMode);
+
+        Ok(RawMode { original })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe { tcsetattr(STDIN_FILENO, TCSADRAIN, &self.original) };
+        REGISTRY.pop(Guard::RawMode);
+    }
+}
+
+/// Returns true if more input follows within `timeout` milliseconds, which
+/// tells a lone Escape key press apart from the start of an escape sequence.
+///
+/// `input` must read from standard input. Bytes that it has already buffered
+/// count as following, so standard input is briefly made non-blocking while
+/// they are looked for, and the terminal is only waited on if there are none.
+/// The end of the input counts as nothing following.
+pub(crate) fn input_follows<R: BufRead>(input: &mut R, timeout: c_int) -> io::Result<bool> {
+    let flags = unsafe { fcntl(STDIN_FILENO, F_GETFL) };
+    let nonblocking = flags != -1 &&
+                      unsafe { fcntl(STDIN_FILENO, F_SETFL, flags | O_NONBLOCK) } != -1;
+
+    let buffered = input.fill_buf().map(|bytes| !bytes.is_empty());
+
+    if nonblocking {
+        unsafe { fcntl(STDIN_FILENO, F_SETFL, flags) };
+    }
+
+    match buffered {
+        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+            let mut fds = pollfd { fd: STDIN_FILENO, events: POLLIN, revents: 0 };
+            Ok(unsafe { poll(&mut fds, 1, timeout) } == 1)
+        }
+        buffered => buffered,
+    }
+}
+
+/// Returns the input modes of the terminal attached to standard input, or
+/// `None` if standard input is not a terminal.
+///
+/// ```no_run
+/// use tutil::screen;
+///
+/// if screen::current_modes().map_or(false, |modes| modes.is_raw()) {
+///     println!("Already in raw mode");
+/// }
+/// ```
+pub fn current_modes() -> Option<TermModes> {
+    let mut termios: termios = unsafe { mem::zeroed() };
+
+    if unsafe { tcgetattr(STDIN_FILENO, &mut termios) } != 0 {
+        return None;
+    }
+
+    Some(modes_of(&termios))
+}
+
+/// Extracts the modes reported by `current_modes()` from the local flags of
+/// `termios`.
+fn modes_of(termios: &termios) -> TermModes {
+    TermModes {
+        echo: termios.c_lflag & ECHO != 0,
+        canonical: termios.c_lflag & ICANON != 0,
+        signals: termios.c_lflag & ISIG != 0,
+    }
+}
+
+/// Installs handlers for `SIGINT` and `SIGTERM` that restore the terminal
+/// before the process is terminated.
+///
+/// The handlers undo the effects of any guards that are alive when the signal
+/// arrives, such as showing the cursor again and leaving raw mode, and then
+/// raise the signal again with its default action so that the process exits
+/// as it normally would.
+///
+/// Returns an error if the handlers could not be installed.
+pub fn install_cleanup() -> io::Result<()> {
+    let handler: extern "C" fn(c_int) = restore_and_raise;
+
+    for &signum in &[SIGINT, SIGTERM] {
+        if unsafe { signal(signum, handler as sighandler_t) } == SIG_ERR {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// The signal handler installed by `install_cleanup()`.
+///
+/// Only async-signal-safe functions may be called here, so the escape codes
+/// are written directly to the file descriptor rather than through `Stdout`.
+extern "C" fn restore_and_raise(signum: c_int) {
+    for guard in REGISTRY.active() {
+        let code = guard.undo_code();
+        unsafe { write(STDOUT_FILENO, code.as_ptr() as *const c_void, code.len()) };
+
+        if guard == Guard::RawMode {
+            if let Some(original) = ORIGINAL_MODE.get() {
+                unsafe { tcsetattr(STDIN_FILENO, TCSADRAIN, original) };
+            }
+        }
+    }
+
+    unsafe {
+        signal(signum, SIG_DFL);
+        raise(signum);
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use super::super::{Width, Height};
+    use super::super::{Width, Height, Size};
 
     use std::process::{Command, Stdio};
 
@@ -109,7 +313,7 @@ mod test {
         let cols = u16::from_str_radix(stdout.split_whitespace().last().unwrap(), 10).unwrap();
         let rows = u16::from_str_radix(stdout.split_whitespace().next().unwrap(), 10).unwrap();
 
-        if let Some((Width(width), Height(height))) = size() {
+        if let Some(Size(Width(width), Height(height))) = size() {
             assert_eq!(width, cols);
             assert_eq!(height, rows);
         } else {
@@ -150,4 +354,61 @@ mod test {
             assert_eq!(rows, 0);
         };
     }
+
+    fn winsize(cols: u16, rows: u16) -> WinSize {
+        WinSize { ws_row: rows, ws_col: cols, ws_xpixel: 0, ws_ypixel: 0 }
+    }
+
+    #[test]
+    fn plausible_winsize() {
+        assert_eq!(winsize_to_size(&winsize(80, 24)), Some(Size(Width(80), Height(24))));
+        assert_eq!(winsize_to_size(&winsize(1, 1)), Some(Size(Width(1), Height(1))));
+        assert_eq!(winsize_to_size(&winsize(10000, 10000)),
+                   Some(Size(Width(10000), Height(10000))));
+    }
+
+    #[test]
+    fn cell_pixels_from_winsize() {
+        let winsize = WinSize { ws_row: 24, ws_col: 80, ws_xpixel: 800, ws_ypixel: 408 };
+        assert_eq!(winsize_to_cell_pixels(&winsize), Some((10, 17)));
+
+        // 1000 / 120 is 8.33 and 500 / 40 is 12.5, which round to 8 and 13.
+        let winsize = WinSize { ws_row: 40, ws_col: 120, ws_xpixel: 1000, ws_ypixel: 500 };
+        assert_eq!(winsize_to_cell_pixels(&winsize), Some((8, 13)));
+    }
+
+    #[test]
+    fn cell_pixels_unavailable() {
+        assert_eq!(winsize_to_cell_pixels(&winsize(80, 24)), None);
+
+        let winsize = WinSize { ws_row: 0, ws_col: 80, ws_xpixel: 800, ws_ypixel: 408 };
+        assert_eq!(winsize_to_cell_pixels(&winsize), None);
+    }
+
+    #[test]
+    fn implausible_winsize() {
+        assert_eq!(winsize_to_size(&winsize(0, 24)), None);
+        assert_eq!(winsize_to_size(&winsize(80, 0)), None);
+        assert_eq!(winsize_to_size(&winsize(10001, 24)), None);
+        assert_eq!(winsize_to_size(&winsize(80, 65535)), None);
+    }
+
+    #[test]
+    fn modes_from_termios_flags() {
+        let mut termios: termios = unsafe { mem::zeroed() };
+        termios.c_lflag = ECHO | ICANON | ISIG;
+        assert_eq!(modes_of(&termios), TermModes { echo: true, canonical: true, signals: true });
+
+        termios.c_lflag = ISIG;
+        assert_eq!(modes_of(&termios), TermModes { echo: false, canonical: false, signals: true });
+
+        unsafe { cfmakeraw(&mut termios) };
+        assert!(modes_of(&termios).is_raw());
+    }
+
+    #[test]
+    fn current_modes_only_for_terminal() {
+        let is_terminal = unsafe { isatty(STDIN_FILENO) } == 1;
+        assert_eq!(current_modes().is_some(), is_terminal);
+    }
 }
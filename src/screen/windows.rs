@@ -2,20 +2,62 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-//! Windows implementation of `tutil::screen`.
-//!
-//! Currently there are no tests written for this implementation.
+//! Windows implementation of `tutil::screen`, along with queries that only
+//! make sense for the Windows console.
 
-use super::{Width, Height};
+use super::{Width, Height, Size, TermModes};
+use super::cleanup::{Guard, REGISTRY};
+
+use std::io::{self, BufRead};
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use winapi::{HANDLE, STD_OUTPUT_HANDLE, COORD, SMALL_RECT, CONSOLE_SCREEN_BUFFER_INFO};
-use kernel32::{GetStdHandle, GetConsoleScreenBufferInfo};
+use winapi::{DWORD, STD_INPUT_HANDLE, ENABLE_ECHO_INPUT, ENABLE_LINE_INPUT, ENABLE_PROCESSED_INPUT};
+use winapi::{BOOL, FALSE, TRUE, LPCVOID};
+use kernel32::{GetStdHandle, GetConsoleScreenBufferInfo, GetConsoleMode, SetConsoleMode};
+use kernel32::{SetConsoleCtrlHandler, WriteFile};
+
+/// Makes the console translate special keys, such as the arrow keys, into the
+/// same escape sequences that Unix terminals send. Not yet defined by `winapi`.
+const ENABLE_VIRTUAL_TERMINAL_INPUT: DWORD = 0x0200;
+
+/// The mode that the console input was in before raw mode was enabled, for
+/// the control handler to restore.
+static ORIGINAL_MODE: AtomicUsize = AtomicUsize::new(0);
 
 /// Returns the terminal screen size (in columns and rows).
 ///
 /// Returns `None` if the screen size is `(0, 0)` or is not able to be
 /// determined.
-pub fn size() -> Option<(Width, Height)> {
+pub fn size() -> Option<Size> {
+    if let Some(size) = super::test_size() {
+        return Some(size);
+    }
+
+    screen_buffer_info().map(|csbi| {
+        let width = Width((csbi.srWindow.Right - csbi.srWindow.Left + 1) as u16);
+        let height = Height((csbi.srWindow.Bottom - csbi.srWindow.Top + 1) as u16);
+        Size(width, height)
+    })
+}
+
+/// Returns the size of the console screen buffer (in columns and rows).
+///
+/// Unlike `size()`, which is the size of the visible window, this is the full
+/// extent of the buffer including the scrollback, so it is at least as large
+/// as `size()` in both dimensions.
+///
+/// Returns `None` if the buffer size is not able to be determined.
+pub fn buffer_size() -> Option<Size> {
+    screen_buffer_info().map(|csbi| {
+        Size(Width(csbi.dwSize.X as u16), Height(csbi.dwSize.Y as u16))
+    })
+}
+
+/// Returns information about the console screen buffer attached to standard
+/// output, or `None` if it is not a console.
+fn screen_buffer_info() -> Option<CONSOLE_SCREEN_BUFFER_INFO> {
     // Retrieve a handle to STDOUT.
     let handle: HANDLE = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) };
 
@@ -54,9 +96,7 @@ pub fn size() -> Option<(Width, Height)> {
     };
 
     if success {
-        let width = Width((csbi.srWindow.Right - csbi.srWindow.Left + 1) as u16);
-        let height = Height((csbi.srWindow.Bottom - csbi.srWindow.Top + 1) as u16);
-        Some((width, height))
+        Some(csbi)
     } else {
         None
     }
@@ -69,7 +109,7 @@ pub fn size() -> Option<(Width, Height)> {
 pub fn width() -> Option<Width> {
     let size = size();
 
-    if let Some((Width(width), Height(_))) = size {
+    if let Some(Size(Width(width), Height(_))) = size {
         Some(Width(width))
     } else {
         None
@@ -83,14 +123,160 @@ pub fn width() -> Option<Width> {
 pub fn height() -> Option<Height> {
     let size = size();
 
-    if let Some((Width(_), Height(height))) = size {
+    if let Some(Size(Width(_), Height(height))) = size {
         Some(Height(height))
     } else {
         None
     }
 }
 
+/// Returns the size of one character cell in pixels, as width and height.
+///
+/// The console does not report its size in pixels, so this always returns
+/// `None`.
+pub fn cell_pixel_size() -> Option<(u16, u16)> {
+    None
+}
+
+/// A guard that puts the console attached to standard input into raw mode,
+/// restoring the original mode when dropped.
+///
+/// In raw mode input is made available a key at a time, without echo or line
+/// editing, Ctrl-C is read as a byte rather than raising an interrupt and
+/// special keys are sent as escape sequences, as they are on Unix.
+pub(crate) struct RawMode {
+    handle: HANDLE,
+    original: DWORD,
+}
+
+impl RawMode {
+    /// Enables raw mode, failing if standard input is not a console.
+    pub fn enable() -> io::Result<RawMode> {
+        let handle: HANDLE = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+        let mut original: DWORD = 0;
+
+        if unsafe { GetConsoleMode(handle, &mut original) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mode = (original & !(ENABLE_ECHO_INPUT | ENABLE_LINE_INPUT | ENABLE_PROCESSED_INPUT))
+            | ENABLE_VIRTUAL_TERMINAL_INPUT;
+
+        if unsafe { SetConsoleMode(handle, mode) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        if !REGISTRY.is_active(Guard::RawMode) {
+            ORIGINAL_MODE.store(original as usize, Ordering::SeqCst);
+        }
+        REGISTRY.push(Guard::RawMode);
+
+        Ok(RawMode { handle, original })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe { SetConsoleMode(self.handle, self.original) };
+        REGISTRY.pop(Guard::RawMode);
+    }
+}
+
+/// Returns true if more input follows within `timeout` milliseconds, which
+/// tells a lone Escape key press apart from the start of an escape sequence.
+///
+/// The console cannot be waited on without losing track of what `input` has
+/// already buffered, so more input is always assumed to follow, and a lone
+/// Escape is only recognised once the next key is pressed.
+pub(crate) fn input_follows<R: BufRead>(_: &mut R, _: i32) -> io::Result<bool> {
+    Ok(true)
+}
+
+/// Returns the input modes of the console attached to standard input, or
+/// `None` if standard input is not a console.
+///
+/// Signal generation corresponds to processed input, which makes the console
+/// handle Ctrl-C itself.
+pub fn current_modes() -> Option<TermModes> {
+    let mut mode: DWORD = 0;
+
+    if unsafe { GetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), &mut mode) } == 0 {
+        return None;
+    }
+
+    Some(modes_of(mode))
+}
+
+/// Extracts the modes reported by `current_modes()` from a console mode.
+fn modes_of(mode: DWORD) -> TermModes {
+    TermModes {
+        echo: mode & ENABLE_ECHO_INPUT != 0,
+        canonical: mode & ENABLE_LINE_INPUT != 0,
+        signals: mode & ENABLE_PROCESSED_INPUT != 0,
+    }
+}
+
+/// Installs a console control handler that restores the console when Ctrl-C
+/// is pressed or the console is closed.
+///
+/// The handler undoes the effects of any guards that are alive at the time,
+/// such as showing the cursor again and leaving raw mode, and then lets the
+/// default handler terminate the process as it normally would.
+///
+/// Returns an error if the handler could not be installed.
+pub fn install_cleanup() -> io::Result<()> {
+    if unsafe { SetConsoleCtrlHandler(Some(restore_on_ctrl), TRUE) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// The control handler installed by `install_cleanup()`.
+///
+/// This runs on a separate thread, which may interrupt another thread that
+/// holds the lock on `Stdout`, so the escape codes are written directly to the
+/// console handle.
+unsafe extern "system" fn restore_on_ctrl(_: DWORD) -> BOOL {
+    let output: HANDLE = GetStdHandle(STD_OUTPUT_HANDLE);
+
+    for guard in REGISTRY.active() {
+        let code = guard.undo_code();
+        let mut written: DWORD = 0;
+        WriteFile(output, code.as_ptr() as LPCVOID, code.len() as DWORD, &mut written,
+                  ptr::null_mut());
+
+        if guard == Guard::RawMode {
+            let input: HANDLE = GetStdHandle(STD_INPUT_HANDLE);
+            SetConsoleMode(input, ORIGINAL_MODE.load(Ordering::SeqCst) as DWORD);
+        }
+    }
+
+    // Returning `FALSE` passes the event on to the next handler, which is the
+    // default one that terminates the process.
+    FALSE
+}
+
 #[cfg(test)]
 mod test {
-    // TODO: Write tests.
+    use super::*;
+
+    #[test]
+    fn buffer_at_least_as_large_as_window() {
+        // Both are `None` unless the tests are run in a console.
+        if let (Some(window), Some(buffer)) = (size(), buffer_size()) {
+            let (Size(Width(window_width), Height(window_height)),
+                 Size(Width(buffer_width), Height(buffer_height))) = (window, buffer);
+
+            assert!(buffer_width >= window_width);
+            assert!(buffer_height >= window_height);
+        }
+    }
+
+    #[test]
+    fn modes_from_console_mode() {
+        let all = ENABLE_ECHO_INPUT | ENABLE_LINE_INPUT | ENABLE_PROCESSED_INPUT;
+        assert_eq!(modes_of(all), TermModes { echo: true, canonical: true, signals: true });
+        assert!(modes_of(ENABLE_VIRTUAL_TERMINAL_INPUT).is_raw());
+    }
 }
@@ -0,0 +1,1080 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A module for querying the system and the environment that the program is
+//! running in.
+//!
+//! # Basic Usage
+//!
+//! ```
+//! use tutil::system;
+//!
+//! let bullet = if system::unicode_support() { "•" } else { "*" };
+//! println!("{} Item", bullet);
+//! ```
+
+use std::env;
+#[cfg(unix)]
+use std::io::{self, Write};
+
+#[cfg(unix)]
+use libc::{isatty, poll, pollfd, read, ttyname_r, POLLIN, STDERR_FILENO, STDIN_FILENO,
+           STDOUT_FILENO};
+#[cfg(unix)]
+use std::ffi::CStr;
+#[cfg(unix)]
+use std::os::raw::{c_char, c_int};
+
+#[cfg(windows)]
+use winapi::{DWORD, STD_ERROR_HANDLE, STD_INPUT_HANDLE, STD_OUTPUT_HANDLE};
+#[cfg(windows)]
+use kernel32::{GetConsoleMode, GetStdHandle};
+
+use crayon::{Color, ColorSupport};
+#[cfg(unix)]
+use screen::RawMode;
+use terminfo;
+
+/// Returns true if the current locale uses the UTF-8 encoding, meaning that
+/// characters outside of ASCII can be printed.
+///
+/// The locale is taken from the first non-empty variable out of `LC_ALL`,
+/// `LC_CTYPE` and `LANG`, following the POSIX order of precedence. On Windows,
+/// where these variables are rarely set, Unicode support is assumed when none
+/// of them are present.
+pub fn unicode_support() -> bool {
+    unicode_support_with(var)
+}
+
+/// The implementation of `unicode_support()`, reading environment variables
+/// through `var` so that it can be tested without touching the environment.
+pub(crate) fn unicode_support_with<F>(var: F) -> bool
+    where F: Fn(&str) -> Option<String>
+{
+    let locale = var("LC_ALL").or_else(|| var("LC_CTYPE")).or_else(|| var("LANG"));
+
+    match locale {
+        Some(locale) => {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        }
+        None => cfg!(windows),
+    }
+}
+
+/// The separators used when writing numbers in a particular locale, such as
+/// the `.` and `,` in `1,234.56`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// The character separating the integer and fractional parts.
+    pub decimal: char,
+
+    /// The character separating groups of thousands.
+    pub thousands: char,
+}
+
+impl Default for NumberFormat {
+    fn default() -> NumberFormat {
+        NumberFormat { decimal: '.', thousands: ',' }
+    }
+}
+
+/// Returns the separators used for numbers in the current locale.
+///
+/// The locale is taken from the first non-empty variable out of `LC_ALL`,
+/// `LC_NUMERIC` and `LANG`. If it is unset, unknown or the `C`/`POSIX` locale,
+/// the English separators `.` and `,` are returned.
+///
+/// # Limitations
+///
+/// For portability the separators come from a built-in table keyed on the
+/// locale name rather than from the C library's `localeconv()`, so locales
+/// that have been customised on the system are not taken into account and
+/// only the most common languages and territories are recognised. Locales
+/// that group with a space use a no-break space (`U+00A0`).
+pub fn locale_number_format() -> NumberFormat {
+    locale_number_format_with(var)
+}
+
+/// The implementation of `locale_number_format()`, reading environment
+/// variables through `var`.
+fn locale_number_format_with<F>(var: F) -> NumberFormat where F: Fn(&str) -> Option<String> {
+    let locale = match var("LC_ALL").or_else(|| var("LC_NUMERIC")).or_else(|| var("LANG")) {
+        Some(locale) => locale,
+        None => return NumberFormat::default(),
+    };
+
+    // Locale names take the form `language_TERRITORY.codeset@modifier`.
+    let name = locale.split(['.', '@']).next().unwrap_or("");
+    let mut parts = name.split('_');
+    let language = parts.next().unwrap_or("");
+    let territory = parts.next().unwrap_or("");
+
+    let (decimal, thousands) = match (language, territory) {
+        ("de", "CH") | ("it", "CH") | ("fr", "CH") => ('.', '\''),
+        ("de", _) | ("es", _) | ("it", _) | ("nl", _) | ("pt", _) | ("da", _) | ("id", _) |
+        ("tr", _) | ("el", _) | ("ro", _) | ("hr", _) | ("sl", _) | ("sr", _) => (',', '.'),
+        ("fr", _) | ("ru", _) | ("pl", _) | ("cs", _) | ("sk", _) | ("uk", _) | ("fi", _) |
+        ("sv", _) | ("nb", _) | ("nn", _) | ("no", _) | ("hu", _) | ("bg", _) | ("lt", _) |
+        ("lv", _) | ("et", _) => (',', '\u{a0}'),
+        _ => return NumberFormat::default(),
+    };
+
+    NumberFormat { decimal, thousands }
+}
+
+/// Returns true if the terminal is likely to display the italic property
+/// rather than ignoring it or showing it some other way, such as in reverse.
+///
+/// The terminal is identified by the `TERM` variable. If it is unset or does
+/// not support italic, `false` is returned.
+///
+/// The `sitm` capability is looked up in the terminal's terminfo entry. If no
+/// entry can be found, such as on Windows, a built-in table of terminal
+/// families is used instead, so the answer is a best guess.
+///
+/// # Limitations
+///
+/// `screen` is reported as not supporting italic, since its terminfo entry
+/// lacks the capability even though it is often run inside a terminal that
+/// has it.
+pub fn supports_italic() -> bool {
+    supports_italic_with(var)
+}
+
+/// The implementation of `supports_italic()`, reading environment variables
+/// through `var`.
+fn supports_italic_with<F>(var: F) -> bool where F: Fn(&str) -> Option<String> {
+    has_capability(var, terminfo::ENTER_ITALICS_MODE, ITALIC_TERMS)
+}
+
+/// Returns true if the terminal is likely to display the blink property.
+///
+/// The terminal is identified by the `TERM` variable. If it is unset or does
+/// not support blink, `false` is returned. As with `supports_italic()`, the
+/// `blink` capability is looked up in terminfo, falling back to a built-in
+/// table of terminal families.
+pub fn supports_blink() -> bool {
+    supports_blink_with(var)
+}
+
+/// The implementation of `supports_blink()`, reading environment variables
+/// through `var`.
+fn supports_blink_with<F>(var: F) -> bool where F: Fn(&str) -> Option<String> {
+    has_capability(var, terminfo::ENTER_BLINK_MODE, BLINK_TERMS)
+}
+
+/// Returns true if the terminal is likely to report mouse events once they
+/// are turned on with `tutil::screen::mouse::enable()`.
+///
+/// The terminal is identified by the `TERM` variable. If it is unset or does
+/// not support XTerm-style mouse reporting, `false` is returned. As with
+/// `supports_italic()`, the `kmous` capability is looked up in terminfo,
+/// falling back to a built-in table of terminal families.
+pub fn supports_mouse() -> bool {
+    supports_mouse_with(var)
+}
+
+/// The implementation of `supports_mouse()`, reading environment variables
+/// through `var`.
+fn supports_mouse_with<F>(var: F) -> bool where F: Fn(&str) -> Option<String> {
+    has_capability(var, terminfo::KEY_MOUSE, MOUSE_TERMS)
+}
+
+/// Returns whether the terminal named by `TERM` has the string capability at
+/// index `cap` in its terminfo entry, or if there is no readable entry,
+/// whether its family is in `table`.
+fn has_capability<F>(var: F, cap: usize, table: &[&str]) -> bool
+    where F: Fn(&str) -> Option<String>
+{
+    match terminfo::find(&var).and_then(|entry| terminfo::has_string(&entry, cap)) {
+        Some(has) => has,
+        None => match term_family(var) {
+            Some(family) => table.contains(&&*family),
+            None => false,
+        },
+    }
+}
+
+/// The families of terminal that support the italic property.
+const ITALIC_TERMS: &[&str] = &["alacritty", "foot", "gnome", "iterm2", "kitty", "konsole",
+                                "mintty", "rxvt", "st", "tmux", "vte", "wezterm", "xterm"];
+
+/// The families of terminal that support the blink property.
+const BLINK_TERMS: &[&str] = &["alacritty", "foot", "gnome", "kitty", "konsole", "linux",
+                               "putty", "rxvt", "screen", "st", "tmux", "vt100", "vt102",
+                               "vt220", "vte", "xterm"];
+
+/// The families of terminal that support XTerm-style mouse reporting.
+const MOUSE_TERMS: &[&str] = &["alacritty", "foot", "gnome", "iterm2", "kitty", "konsole",
+                               "mintty", "putty", "rxvt", "screen", "st", "tmux", "vte",
+                               "wezterm", "xterm"];
+
+/// Returns the family of the terminal named by `TERM`, which is the part of
+/// the name before any variant, such as `xterm` for `xterm-256color`.
+fn term_family<F>(var: F) -> Option<String> where F: Fn(&str) -> Option<String> {
+    var("TERM").map(|term| term.split('-').next().unwrap_or("").to_lowercase())
+}
+
+/// One of the three standard streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    /// Standard input.
+    Stdin,
+
+    /// Standard output.
+    Stdout,
+
+    /// Standard error.
+    Stderr,
+}
+
+impl Stream {
+    /// Returns the file descriptor of the stream.
+    #[cfg(unix)]
+    fn fd(self) -> c_int {
+        match self {
+            Stream::Stdin => STDIN_FILENO,
+            Stream::Stdout => STDOUT_FILENO,
+            Stream::Stderr => STDERR_FILENO,
+        }
+    }
+
+    /// Returns the identifier that `GetStdHandle()` takes for the stream.
+    #[cfg(windows)]
+    fn handle_id(self) -> DWORD {
+        match self {
+            Stream::Stdin => STD_INPUT_HANDLE,
+            Stream::Stdout => STD_OUTPUT_HANDLE,
+            Stream::Stderr => STD_ERROR_HANDLE,
+        }
+    }
+}
+
+/// Returns true if `stream` is not attached to a terminal, because it has
+/// been redirected to or from a file or pipe.
+///
+/// Each stream is checked separately, so a program can, for example, read
+/// piped input from standard input while still asking questions on standard
+/// error.
+///
+/// ```
+/// use tutil::system::{self, Stream};
+///
+/// if system::stream_is_redirected(Stream::Stdout) {
+///     // Write plain output for another program to read.
+/// }
+/// ```
+#[cfg(unix)]
+pub fn stream_is_redirected(stream: Stream) -> bool {
+    unsafe { isatty(stream.fd()) != 1 }
+}
+
+/// Returns true if `stream` is not attached to a terminal, because it has
+/// been redirected to or from a file or pipe.
+///
+/// Each stream is checked separately, so a program can, for example, read
+/// piped input from standard input while still asking questions on standard
+/// error.
+#[cfg(windows)]
+pub fn stream_is_redirected(stream: Stream) -> bool {
+    let mut mode: DWORD = 0;
+    unsafe { GetConsoleMode(GetStdHandle(stream.handle_id()), &mut mode) == 0 }
+}
+
+/// Returns true if standard input is a pseudo-terminal, such as the ones
+/// created by terminal emulators, `ssh`, `script` or `expect`, rather than a
+/// real terminal device such as the Linux console or a serial line.
+///
+/// The device is identified from its name, such as `/dev/pts/3`. `false` is
+/// returned if standard input is not a terminal, and always on Windows.
+pub fn is_pseudo_terminal() -> bool {
+    match tty_name() {
+        Some(name) => is_pseudo_terminal_path(&name),
+        None => false,
+    }
+}
+
+/// Returns the path of the terminal device attached to standard input.
+#[cfg(unix)]
+fn tty_name() -> Option<String> {
+    let mut buf: [c_char; 256] = [0; 256];
+
+    if unsafe { ttyname_r(STDIN_FILENO, buf.as_mut_ptr(), buf.len()) } != 0 {
+        return None;
+    }
+
+    let name = unsafe { CStr::from_ptr(buf.as_ptr()) };
+    name.to_str().ok().map(str::to_string)
+}
+
+/// Returns the path of the terminal device attached to standard input, which
+/// is never known on Windows.
+#[cfg(windows)]
+fn tty_name() -> Option<String> {
+    None
+}
+
+/// Returns true if `path` names a pseudo-terminal device.
+///
+/// These are `/dev/pts/N` on Linux and the BSDs, `/dev/ttysNNN` on macOS and
+/// the `/dev/ttyXY` devices of the older BSD scheme, where X is a letter from
+/// `p` to `z` and Y is a hexadecimal digit. Some systems continued that scheme
+/// from `a` to `e`, but those names are also used for serial ports, such as
+/// `/dev/ttyd0` on FreeBSD, so they are not counted.
+fn is_pseudo_terminal_path(path: &str) -> bool {
+    if path.starts_with("/dev/pts/") {
+        return true;
+    }
+
+    let name = match path.strip_prefix("/dev/tty") {
+        Some(name) => name.as_bytes(),
+        None => return false,
+    };
+
+    match name {
+        [b's', digits @ ..] if !digits.is_empty() => digits.iter().all(u8::is_ascii_digit),
+        &[series, index] => {
+            matches!(series, b'p'..=b'z') && index.is_ascii_hexdigit()
+        }
+        _ => false,
+    }
+}
+
+/// A terminal emulator that can be identified from the environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Emulator {
+    /// Alacritty.
+    Alacritty,
+
+    /// Terminal.app on macOS.
+    AppleTerminal,
+
+    /// foot.
+    Foot,
+
+    /// iTerm2.
+    ITerm2,
+
+    /// kitty.
+    Kitty,
+
+    /// Konsole.
+    Konsole,
+
+    /// The terminal built into Visual Studio Code.
+    VsCode,
+
+    /// A terminal built on the VTE library, such as GNOME Terminal.
+    Vte,
+
+    /// WezTerm.
+    WezTerm,
+
+    /// Windows Terminal.
+    WindowsTerminal,
+
+    /// A terminal that could not be identified.
+    Unknown,
+}
+
+/// Returns the terminal emulator named by the environment.
+///
+/// `TERM_PROGRAM` is checked first, since it is set by the terminals that use
+/// it even when `TERM` is overridden. After that come the variables that some
+/// emulators set for themselves, and finally `TERM`.
+fn emulator_with<F>(var: F) -> Emulator where F: Fn(&str) -> Option<String> {
+    match var("TERM_PROGRAM").as_deref() {
+        Some("Apple_Terminal") => return Emulator::AppleTerminal,
+        Some("iTerm.app") => return Emulator::ITerm2,
+        Some("vscode") => return Emulator::VsCode,
+        Some("WezTerm") => return Emulator::WezTerm,
+        _ => {}
+    }
+
+    if var("WT_SESSION").is_some() {
+        Emulator::WindowsTerminal
+    } else if var("KITTY_WINDOW_ID").is_some() {
+        Emulator::Kitty
+    } else if var("KONSOLE_VERSION").is_some() {
+        Emulator::Konsole
+    } else if var("VTE_VERSION").is_some() {
+        Emulator::Vte
+    } else {
+        match var("TERM").as_deref() {
+            Some("alacritty") => Emulator::Alacritty,
+            Some("foot") | Some("foot-extra") => Emulator::Foot,
+            Some("xterm-kitty") => Emulator::Kitty,
+            _ => Emulator::Unknown,
+        }
+    }
+}
+
+/// Returns how many colours the terminal named by the environment can show,
+/// without checking whether the output is actually a terminal.
+///
+/// `COLORTERM` set to `truecolor` or `24bit` is taken as support for any
+/// colour, as are the terminals known to support it. Otherwise a `TERM` ending
+/// in `256color` gives the 256-colour palette, `dumb` gives no colour at all
+/// and any other terminal is assumed to have the sixteen basic colours.
+pub(crate) fn color_support_with<F>(var: F) -> ColorSupport where F: Fn(&str) -> Option<String> {
+    let term = var("TERM");
+
+    let dumb = match term {
+        Some(ref term) => term == "dumb",
+        None => false,
+    };
+    if dumb {
+        return ColorSupport::None;
+    }
+
+    let truecolor = match var("COLORTERM") {
+        Some(value) => value == "truecolor" || value == "24bit",
+        None => false,
+    };
+    if truecolor {
+        return ColorSupport::TrueColor;
+    }
+
+    match emulator_with(&var) {
+        Emulator::ITerm2 | Emulator::VsCode | Emulator::WezTerm | Emulator::WindowsTerminal => {
+            return ColorSupport::TrueColor;
+        }
+        Emulator::AppleTerminal => return ColorSupport::Ansi256,
+        _ => {}
+    }
+
+    match term {
+        Some(ref term) if term.ends_with("-direct") => ColorSupport::TrueColor,
+        Some(ref term) if term.ends_with("256color") => ColorSupport::Ansi256,
+        Some(_) => ColorSupport::Ansi16,
+        None if cfg!(windows) => ColorSupport::Ansi16,
+        None => ColorSupport::None,
+    }
+}
+
+/// Returns true if the terminal named by the environment turns OSC 8 escape
+/// sequences into clickable links.
+fn hyperlinks_with<F>(var: F) -> bool where F: Fn(&str) -> Option<String> {
+    match emulator_with(&var) {
+        Emulator::AppleTerminal | Emulator::Unknown => false,
+        // Links arrived in VTE 0.50.
+        Emulator::Vte => match var("VTE_VERSION").and_then(|v| v.parse::<u32>().ok()) {
+            Some(version) => version >= 5000,
+            None => false,
+        },
+        _ => true,
+    }
+}
+
+/// Everything that is known about what the terminal can do, detected once so
+/// that it can be passed around and queried cheaply.
+///
+/// Each field is the answer that the corresponding function in this module
+/// would give, so the same caveats apply: most of them are best guesses based
+/// on the environment rather than on asking the terminal.
+///
+/// ```
+/// use tutil::system::Capabilities;
+///
+/// let caps = Capabilities::detect();
+/// let bullet = if caps.unicode { "•" } else { "*" };
+/// println!("{} Item", bullet);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// How many colours standard output can show. This is
+    /// `ColorSupport::None` when standard output is not a terminal.
+    pub color_support: ColorSupport,
+
+    /// Whether characters outside of ASCII can be printed, as given by
+    /// `unicode_support()`.
+    pub unicode: bool,
+
+    /// Whether OSC 8 hyperlinks are shown as clickable links.
+    pub hyperlinks: bool,
+
+    /// Whether mouse events can be reported, as given by `supports_mouse()`.
+    pub mouse: bool,
+
+    /// Whether italic text is displayed, as given by `supports_italic()`.
+    pub italic: bool,
+
+    /// Whether blinking text is displayed, as given by `supports_blink()`.
+    pub blink: bool,
+
+    /// The terminal emulator, if it could be identified.
+    pub emulator: Emulator,
+}
+
+impl Capabilities {
+    /// Detects the capabilities of the terminal from the environment and
+    /// from whether standard output is a terminal.
+    pub fn detect() -> Capabilities {
+        Capabilities::detect_with(var, !stream_is_redirected(Stream::Stdout))
+    }
+
+    /// The implementation of `detect()`, reading environment variables
+    /// through `var`.
+    fn detect_with<F>(var: F, is_terminal: bool) -> Capabilities
+        where F: Fn(&str) -> Option<String>
+    {
+        Capabilities {
+            color_support: if is_terminal { color_support_with(&var) } else { ColorSupport::None },
+            unicode: unicode_support_with(&var),
+            hyperlinks: hyperlinks_with(&var),
+            mouse: supports_mouse_with(&var),
+            italic: supports_italic_with(&var),
+            blink: supports_blink_with(&var),
+            emulator: emulator_with(&var),
+        }
+    }
+}
+
+/// Asks the terminal for its default foreground colour, which is the colour
+/// of unstyled text.
+///
+/// The query is the OSC 10 escape sequence, which most terminal emulators on
+/// Unix answer with the colour in the form `rgb:RRRR/GGGG/BBBB`. Standard
+/// input is briefly put into raw mode to read the answer. `None` is returned
+/// if standard input or output is not a terminal, or if the terminal does not
+/// answer within 100 milliseconds, which is always the case on Windows.
+///
+/// ```no_run
+/// use tutil::system;
+///
+/// if let Some(color) = system::foreground_color() {
+///     println!("Text is {:?}", color.to_rgb());
+/// }
+/// ```
+pub fn foreground_color() -> Option<Color> {
+    query_color(10)
+}
+
+/// Asks the terminal for its default background colour, using the OSC 11
+/// escape sequence.
+///
+/// See [`foreground_color()`](fn.foreground_color.html) for how the terminal
+/// is queried and when `None` is returned.
+pub fn background_color() -> Option<Color> {
+    query_color(11)
+}
+
+/// Asks the terminal for its Primary Device Attributes (DA1), returning the
+/// numbers that it reports, such as `[62, 4, 22]`.
+///
+/// The first number is the class of terminal that is emulated, such as 62 for
+/// a VT220, and the rest are features that it supports, such as 4 for sixel
+/// graphics or 22 for colour. The terminal is queried with `\x1b[c` as for
+/// [`foreground_color()`](fn.foreground_color.html), and `None` is returned
+/// in the same cases. Nearly every terminal emulator answers this query, so
+/// it is also a cheap way to tell whether the terminal answers at all.
+///
+/// ```no_run
+/// use tutil::system;
+///
+/// let sixel = system::query_device_attributes().map_or(false, |attributes| {
+///     attributes[1..].contains(&4)
+/// });
+/// ```
+pub fn query_device_attributes() -> Option<Vec<u16>> {
+    // The answer is `CSI ? ... c`, so any `c` typed before it is skipped.
+    let reply = query_terminal("\x1b[c", |reply| {
+        reply.ends_with(b"c") && reply.windows(3).any(|w| w == b"\x1b[?")
+    });
+
+    reply.and_then(|reply| parse_device_attributes(&String::from_utf8_lossy(&reply)))
+}
+
+/// Parses a terminal's answer to the DA1 query, such as `\x1b[?62;4;22c`,
+/// into the numbers that it contains.
+fn parse_device_attributes(reply: &str) -> Option<Vec<u16>> {
+    reply.find("\x1b[?")
+        .and_then(|start| reply[start + 3..].strip_suffix('c'))
+        .and_then(|params| params.split(';').map(|n| n.parse().ok()).collect())
+}
+
+/// Returns the default foreground and background colours described by the
+/// `COLORFGBG` variable, which rxvt, Konsole and some other terminals set to
+/// the palette indices of the colours, such as `15;0` for white on black.
+///
+/// Some terminals put another field between the two, as in `0;default;15`, so
+/// only the first and last fields are used. `None` is returned if the
+/// variable is unset or either field is not a palette index.
+///
+/// ```
+/// use tutil::system;
+///
+/// if let Some((foreground, background)) = system::colorfgbg() {
+///     println!("{:?} on {:?}", foreground, background);
+/// }
+/// ```
+pub fn colorfgbg() -> Option<(Color, Color)> {
+    colorfgbg_with(var)
+}
+
+/// The implementation of `colorfgbg()`, reading environment variables through
+/// `var`.
+fn colorfgbg_with<F>(var: F) -> Option<(Color, Color)> where F: Fn(&str) -> Option<String> {
+    var("COLORFGBG").and_then(|value| {
+        let mut fields = value.split(';');
+
+        let foreground = fields.next().and_then(|field| field.parse().ok());
+        let background = fields.next_back().and_then(|field| field.parse().ok());
+
+        match (foreground, background) {
+            (Some(foreground), Some(background)) => {
+                Some((Color::Fixed(foreground), Color::Fixed(background)))
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Returns true if the terminal's default background is dark, such as for
+/// choosing colours that will be readable on it.
+///
+/// `COLORFGBG` is checked first, as described in
+/// [`colorfgbg()`](fn.colorfgbg.html), since reading it is much cheaper than
+/// asking the terminal with [`background_color()`](fn.background_color.html).
+/// A colour is dark if its luminance is below half. `None` is returned if
+/// neither gives an answer.
+///
+/// ```no_run
+/// use tutil::crayon::Color::{Blue, Cyan};
+/// use tutil::system;
+///
+/// let accent = if system::background_is_dark() == Some(false) { Blue } else { Cyan };
+/// println!("{}", accent.paint("tutil"));
+/// ```
+pub fn background_is_dark() -> Option<bool> {
+    background_is_dark_with(var, background_color)
+}
+
+/// The implementation of `background_is_dark()`, reading environment
+/// variables through `var` and asking the terminal with `query`.
+fn background_is_dark_with<F, Q>(var: F, query: Q) -> Option<bool>
+    where F: Fn(&str) -> Option<String>,
+          Q: FnOnce() -> Option<Color>
+{
+    let background = colorfgbg_with(var).map(|(_, background)| background).or_else(query);
+
+    background.map(|background| {
+        let (r, g, b) = background.to_rgb();
+        let luminance = 0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64;
+
+        luminance < 127.5
+    })
+}
+
+/// How long to wait for the terminal to answer a query, in milliseconds.
+#[cfg(unix)]
+const QUERY_TIMEOUT: i32 = 100;
+
+/// Sends the OSC query for the colour with the given number, such as 10 for
+/// the foreground, and parses the answer.
+fn query_color(code: u8) -> Option<Color> {
+    // The answer ends with either `BEL` or the string terminator `ESC \`.
+    let reply = query_terminal(&format!("\x1b]{};?\x07", code), |reply| {
+        reply.ends_with(b"\x07") || reply.ends_with(b"\x1b\\")
+    });
+
+    reply.and_then(|reply| parse_color_reply(&String::from_utf8_lossy(&reply)))
+}
+
+/// Writes `query` to the terminal and reads its answer from standard input,
+/// which is briefly put into raw mode, until `complete` is true of the bytes
+/// read so far.
+///
+/// Returns `None` if standard input or output is not a terminal, or if the
+/// terminal stops answering for `QUERY_TIMEOUT` milliseconds.
+#[cfg(unix)]
+fn query_terminal<F>(query: &str, complete: F) -> Option<Vec<u8>> where F: Fn(&[u8]) -> bool {
+    if unsafe { isatty(STDIN_FILENO) != 1 || isatty(STDOUT_FILENO) != 1 } {
+        return None;
+    }
+
+    let _raw = match RawMode::enable() {
+        Ok(raw) => raw,
+        Err(_) => return None,
+    };
+
+    let mut stdout = io::stdout();
+    if stdout.write_all(query.as_bytes()).and_then(|_| stdout.flush()).is_err() {
+        return None;
+    }
+
+    let mut reply = Vec::new();
+    let mut fds = pollfd { fd: STDIN_FILENO, events: POLLIN, revents: 0 };
+
+    while !complete(&reply) {
+        if unsafe { poll(&mut fds, 1, QUERY_TIMEOUT) } != 1 {
+            return None;
+        }
+
+        let mut byte = [0u8];
+        if unsafe { read(STDIN_FILENO, byte.as_mut_ptr() as *mut _, 1) } != 1 {
+            return None;
+        }
+
+        reply.push(byte[0]);
+    }
+
+    Some(reply)
+}
+
+/// Querying the terminal is not supported on Windows.
+#[cfg(windows)]
+fn query_terminal<F>(_: &str, _: F) -> Option<Vec<u8>> where F: Fn(&[u8]) -> bool {
+    None
+}
+
+/// Parses a terminal's answer to an OSC colour query, such as
+/// `\x1b]10;rgb:ffff/ffff/ffff\x07`, into an `Rgb` colour.
+fn parse_color_reply(reply: &str) -> Option<Color> {
+    let start = match reply.find("rgb:") {
+        Some(start) => start + 4,
+        None => return None,
+    };
+    let spec = reply[start..].trim_end_matches(['\x07', '\x1b', '\\']);
+
+    let mut components = spec.split('/').map(parse_color_component);
+
+    match (components.next(), components.next(), components.next(), components.next()) {
+        (Some(Some(r)), Some(Some(g)), Some(Some(b)), None) => Some(Color::Rgb(r, g, b)),
+        _ => None,
+    }
+}
+
+/// Parses one to four hex digits as a colour component, scaling it down to
+/// eight bits, so that both `ff` and `ffff` are 255.
+fn parse_color_component(digits: &str) -> Option<u8> {
+    if digits.is_empty() || digits.len() > 4 {
+        return None;
+    }
+
+    let value = match u32::from_str_radix(digits, 16) {
+        Ok(value) => value,
+        Err(_) => return None,
+    };
+    let max = (1u32 << (4 * digits.len())) - 1;
+
+    Some(((value * 255 + max / 2) / max) as u8)
+}
+
+/// Looks up an environment variable, treating an empty or non-Unicode value
+/// the same as an unset one.
+pub(crate) fn var(name: &str) -> Option<String> {
+    env::var(name).ok().and_then(|value| if value.is_empty() { None } else { Some(value) })
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    /// Returns a function that looks up variables in the given list of pairs
+    /// instead of the environment.
+    ///
+    /// Unless the pairs say otherwise, `TERMINFO_DIRS` names a directory that
+    /// does not exist, so that the host's terminfo database is never read.
+    pub fn fake_env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let mut vars: HashMap<String, String> = vars.iter()
+            .map(|&(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        vars.entry("TERMINFO_DIRS".to_string()).or_insert_with(|| "/nonexistent".to_string());
+
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn unicode_under_utf8_locale() {
+        assert!(unicode_support_with(fake_env(&[("LANG", "en_NZ.UTF-8")])));
+        assert!(unicode_support_with(fake_env(&[("LC_CTYPE", "en_GB.utf8")])));
+    }
+
+    #[test]
+    fn no_unicode_under_c_locale() {
+        assert!(!unicode_support_with(fake_env(&[("LANG", "C")])));
+        assert!(!unicode_support_with(fake_env(&[("LC_ALL", "POSIX"), ("LANG", "en_US.UTF-8")])));
+    }
+
+    #[test]
+    fn number_format_for_locales() {
+        let german = locale_number_format_with(fake_env(&[("LANG", "de_DE.UTF-8")]));
+        assert_eq!(german, NumberFormat { decimal: ',', thousands: '.' });
+
+        let french = locale_number_format_with(fake_env(&[("LC_NUMERIC", "fr_FR.UTF-8@euro"),
+                                                          ("LANG", "en_NZ.UTF-8")]));
+        assert_eq!(french, NumberFormat { decimal: ',', thousands: '\u{a0}' });
+
+        let swiss = locale_number_format_with(fake_env(&[("LC_ALL", "de_CH")]));
+        assert_eq!(swiss, NumberFormat { decimal: '.', thousands: '\'' });
+    }
+
+    #[test]
+    fn default_number_format() {
+        let english = NumberFormat { decimal: '.', thousands: ',' };
+
+        assert_eq!(locale_number_format_with(fake_env(&[])), english);
+        assert_eq!(locale_number_format_with(fake_env(&[("LANG", "C")])), english);
+        assert_eq!(locale_number_format_with(fake_env(&[("LANG", "en_US.UTF-8")])), english);
+    }
+
+    #[test]
+    fn italic_for_known_terminals() {
+        assert!(supports_italic_with(fake_env(&[("TERM", "xterm-256color")])));
+        assert!(supports_italic_with(fake_env(&[("TERM", "tmux-256color")])));
+        assert!(!supports_italic_with(fake_env(&[("TERM", "screen-256color")])));
+        assert!(!supports_italic_with(fake_env(&[("TERM", "linux")])));
+    }
+
+    #[test]
+    fn blink_for_known_terminals() {
+        assert!(supports_blink_with(fake_env(&[("TERM", "xterm-256color")])));
+        assert!(supports_blink_with(fake_env(&[("TERM", "linux")])));
+        assert!(!supports_blink_with(fake_env(&[("TERM", "iterm2")])));
+    }
+
+    #[test]
+    fn mouse_for_known_terminals() {
+        assert!(supports_mouse_with(fake_env(&[("TERM", "xterm-256color")])));
+        assert!(supports_mouse_with(fake_env(&[("TERM", "screen")])));
+        assert!(!supports_mouse_with(fake_env(&[("TERM", "linux")])));
+    }
+
+    #[test]
+    fn capabilities_from_terminfo() {
+        use terminfo::test::{directory, entry, install, only};
+
+        // Both entries contradict the built-in table, which must not be used.
+        let dir = directory("system");
+        install(&dir, "s", "screen-256color",
+                &entry("screen-256color", &only(terminfo::ENTER_ITALICS_MODE, 400), 0o432));
+        install(&dir, "x", "xterm-256color", &entry("xterm-256color", &[], 0o432));
+        let dir = dir.display().to_string();
+
+        let screen = fake_env(&[("TERM", "screen-256color"), ("TERMINFO", &dir)]);
+        assert!(supports_italic_with(&screen));
+        assert!(!supports_blink_with(&screen));
+        assert!(!supports_mouse_with(&screen));
+
+        let xterm = fake_env(&[("TERM", "xterm-256color"), ("TERMINFO", &dir)]);
+        assert!(!supports_italic_with(&xterm));
+        assert!(!supports_blink_with(&xterm));
+        assert!(!supports_mouse_with(&xterm));
+
+        ::std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unknown_terminal_is_conservative() {
+        for env in &[fake_env(&[]), fake_env(&[("TERM", "dumb")])] {
+            assert!(!supports_italic_with(env));
+            assert!(!supports_blink_with(env));
+            assert!(!supports_mouse_with(env));
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn streams_map_to_fds() {
+        assert_eq!(Stream::Stdin.fd(), 0);
+        assert_eq!(Stream::Stdout.fd(), 1);
+        assert_eq!(Stream::Stderr.fd(), 2);
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn streams_map_to_handles() {
+        assert_eq!(Stream::Stdin.handle_id(), STD_INPUT_HANDLE);
+        assert_eq!(Stream::Stdout.handle_id(), STD_OUTPUT_HANDLE);
+        assert_eq!(Stream::Stderr.handle_id(), STD_ERROR_HANDLE);
+    }
+
+    #[test]
+    fn redirection_matches_terminal_detection() {
+        use std::io::{self, IsTerminal};
+
+        assert_eq!(stream_is_redirected(Stream::Stdin), !io::stdin().is_terminal());
+        assert_eq!(stream_is_redirected(Stream::Stdout), !io::stdout().is_terminal());
+        assert_eq!(stream_is_redirected(Stream::Stderr), !io::stderr().is_terminal());
+    }
+
+    #[test]
+    fn parse_16_bit_color_reply() {
+        assert_eq!(parse_color_reply("\x1b]10;rgb:ffff/8080/0000\x07"),
+                   Some(Color::Rgb(255, 128, 0)));
+        assert_eq!(parse_color_reply("\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\"),
+                   Some(Color::Rgb(30, 30, 46)));
+    }
+
+    #[test]
+    fn parse_color_reply_downscaling() {
+        assert_eq!(parse_color_component("ffff"), Some(255));
+        assert_eq!(parse_color_component("7fff"), Some(127));
+        assert_eq!(parse_color_component("0101"), Some(1));
+        assert_eq!(parse_color_component("ff"), Some(255));
+        assert_eq!(parse_color_component("f"), Some(255));
+        assert_eq!(parse_color_component("8"), Some(136));
+    }
+
+    #[test]
+    fn parse_malformed_color_reply() {
+        for reply in &["", "\x1b]10;rgb:ffff/ffff\x07", "\x1b]10;rgb:ffff/ffff/ffff/ffff\x07",
+                       "\x1b]10;rgb:fffff/0/0\x07", "\x1b]10;rgb:gg/00/00\x07",
+                       "\x1b]10;rgb://\x07", "\x1b]10;#ffffff\x07"] {
+            assert_eq!(parse_color_reply(reply), None, "{:?}", reply);
+        }
+    }
+
+    #[test]
+    #[ignore = "requires a terminal that answers colour queries"]
+    fn query_terminal_colors() {
+        assert!(foreground_color().is_some());
+        assert!(background_color().is_some());
+    }
+
+    #[test]
+    fn parse_device_attributes_replies() {
+        // xterm, VTE, the Linux console, Kitty and a VT100.
+        assert_eq!(parse_device_attributes("\x1b[?64;1;2;6;9;15;18;21;22c"),
+                   Some(vec![64, 1, 2, 6, 9, 15, 18, 21, 22]));
+        assert_eq!(parse_device_attributes("\x1b[?65;1;9c"), Some(vec![65, 1, 9]));
+        assert_eq!(parse_device_attributes("\x1b[?6c"), Some(vec![6]));
+        assert_eq!(parse_device_attributes("\x1b[?62;4;22c"), Some(vec![62, 4, 22]));
+        assert_eq!(parse_device_attributes("\x1b[?1;2c"), Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn parse_device_attributes_after_typed_input() {
+        assert_eq!(parse_device_attributes("abc\x1b[?62;4c"), Some(vec![62, 4]));
+    }
+
+    #[test]
+    fn parse_malformed_device_attributes() {
+        for reply in &["", "\x1b[?c", "\x1b[62;4c", "\x1b[?62;4", "\x1b[?62;x;4c",
+                       "\x1b[?62;c", "\x1b[?62;70000c"] {
+            assert_eq!(parse_device_attributes(reply), None, "{:?}", reply);
+        }
+    }
+
+    #[test]
+    #[ignore = "requires a terminal that answers device attribute queries"]
+    fn query_terminal_device_attributes() {
+        assert!(!query_device_attributes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_colorfgbg() {
+        let parse = |value| colorfgbg_with(fake_env(&[("COLORFGBG", value)]));
+
+        assert_eq!(parse("15;0"), Some((Color::Fixed(15), Color::Fixed(0))));
+        assert_eq!(parse("0;15"), Some((Color::Fixed(0), Color::Fixed(15))));
+        assert_eq!(parse("0;default;15"), Some((Color::Fixed(0), Color::Fixed(15))));
+    }
+
+    #[test]
+    fn parse_malformed_colorfgbg() {
+        let parse = |value| colorfgbg_with(fake_env(&[("COLORFGBG", value)]));
+
+        assert_eq!(parse("15"), None);
+        assert_eq!(parse("white;black"), None);
+        assert_eq!(parse("15;default"), None);
+        assert_eq!(parse("15;256"), None);
+        assert_eq!(colorfgbg_with(fake_env(&[])), None);
+    }
+
+    #[test]
+    fn background_is_dark_from_colorfgbg() {
+        let unasked = || panic!("the terminal was queried");
+
+        assert_eq!(background_is_dark_with(fake_env(&[("COLORFGBG", "15;0")]), unasked),
+                   Some(true));
+        assert_eq!(background_is_dark_with(fake_env(&[("COLORFGBG", "0;15")]), unasked),
+                   Some(false));
+    }
+
+    #[test]
+    fn background_is_dark_falls_back_to_query() {
+        let env = fake_env(&[("COLORFGBG", "oops")]);
+
+        assert_eq!(background_is_dark_with(&env, || Some(Color::Rgb(30, 30, 46))), Some(true));
+        assert_eq!(background_is_dark_with(&env, || Some(Color::Rgb(250, 250, 240))),
+                   Some(false));
+        assert_eq!(background_is_dark_with(&env, || None), None);
+    }
+
+    #[test]
+    fn classify_terminal_devices() {
+        for path in &["/dev/pts/0", "/dev/pts/17", "/dev/ttys003", "/dev/ttyp0", "/dev/ttyqf"] {
+            assert!(is_pseudo_terminal_path(path), "{} is a pseudo-terminal", path);
+        }
+
+        for path in &["/dev/tty1", "/dev/ttyS0", "/dev/console", "/dev/tty", "/dev/ttys",
+                      "/dev/ttyUSB0", "/dev/ttyp", "/dev/ttyd0", "/dev/ttya1", "pts/1"] {
+            assert!(!is_pseudo_terminal_path(path), "{} is not a pseudo-terminal", path);
+        }
+    }
+
+    #[test]
+    fn capabilities_of_kitty() {
+        let caps = Capabilities::detect_with(fake_env(&[("TERM", "xterm-kitty"),
+                                                        ("LANG", "en_NZ.UTF-8")]),
+                                             true);
+
+        assert_eq!(caps, Capabilities {
+            color_support: ColorSupport::Ansi16,
+            unicode: true,
+            hyperlinks: true,
+            mouse: true,
+            italic: true,
+            blink: true,
+            emulator: Emulator::Kitty,
+        });
+    }
+
+    #[test]
+    fn capabilities_of_iterm2() {
+        let caps = Capabilities::detect_with(fake_env(&[("TERM_PROGRAM", "iTerm.app"),
+                                                        ("TERM", "xterm-256color"),
+                                                        ("LANG", "C")]),
+                                             true);
+
+        assert_eq!(caps.emulator, Emulator::ITerm2);
+        assert_eq!(caps.color_support, ColorSupport::TrueColor);
+        assert!(caps.hyperlinks);
+        assert!(!caps.unicode);
+    }
+
+    #[test]
+    fn capabilities_of_old_vte() {
+        let old = Capabilities::detect_with(fake_env(&[("TERM", "xterm-256color"),
+                                                       ("VTE_VERSION", "4803")]),
+                                            true);
+        let new = Capabilities::detect_with(fake_env(&[("TERM", "xterm-256color"),
+                                                       ("VTE_VERSION", "6003"),
+                                                       ("COLORTERM", "truecolor")]),
+                                            true);
+
+        assert_eq!(old.emulator, Emulator::Vte);
+        assert_eq!(old.color_support, ColorSupport::Ansi256);
+        assert!(!old.hyperlinks);
+        assert_eq!(new.color_support, ColorSupport::TrueColor);
+        assert!(new.hyperlinks);
+    }
+
+    #[test]
+    fn capabilities_without_terminal() {
+        let dumb = Capabilities::detect_with(fake_env(&[("TERM", "dumb")]), true);
+        let piped = Capabilities::detect_with(fake_env(&[("TERM", "xterm-256color")]), false);
+
+        assert_eq!(dumb.color_support, ColorSupport::None);
+        assert_eq!(dumb.emulator, Emulator::Unknown);
+        assert!(!dumb.italic && !dumb.mouse && !dumb.hyperlinks);
+        assert_eq!(piped.color_support, ColorSupport::None);
+        assert!(piped.italic);
+    }
+}
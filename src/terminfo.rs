@@ -0,0 +1,282 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Internal helpers for reading compiled terminfo entries.
+//!
+//! Only as much of the format is understood as `tutil::system` needs to tell
+//! whether a terminal has a string capability, such as `sitm` for italic. The
+//! layout is described in `term(5)`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The index of the `blink` (enter blink mode) string capability.
+pub const ENTER_BLINK_MODE: usize = 26;
+
+/// The index of the `sitm` (enter italics mode) string capability.
+pub const ENTER_ITALICS_MODE: usize = 311;
+
+/// The index of the `kmous` (mouse event) string capability.
+pub const KEY_MOUSE: usize = 355;
+
+/// The magic number of an entry whose numbers are two bytes wide.
+const MAGIC: i16 = 0o432;
+
+/// The magic number of an entry whose numbers are four bytes wide, as written
+/// by ncurses 6.1 and later.
+const MAGIC_32: i16 = 0o1036;
+
+/// The directories searched when `TERMINFO_DIRS` does not say otherwise.
+const DEFAULT_DIRS: &[&str] = &["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo",
+                                "/usr/lib/terminfo"];
+
+/// Returns the compiled terminfo entry for the terminal named by `TERM`,
+/// reading environment variables through `var`.
+///
+/// The directories are searched in the same order as ncurses: `TERMINFO`,
+/// `~/.terminfo`, then each directory in `TERMINFO_DIRS`, where an empty
+/// element stands for the system directories. Within each directory both the
+/// first-letter layout (`x/xterm`) and the hexadecimal layout used on macOS
+/// (`78/xterm`) are tried. `None` is returned if `TERM` is unset or no entry
+/// can be read.
+pub fn find<F>(var: F) -> Option<Vec<u8>> where F: Fn(&str) -> Option<String> {
+    let term = match var("TERM") {
+        Some(ref term) if !term.is_empty() && !term.contains('/') => term.clone(),
+        _ => return None,
+    };
+    let subdirs: Vec<String> = term.chars()
+        .take(1)
+        .flat_map(|first| vec![first.to_string(), format!("{:x}", first as u32)])
+        .collect();
+
+    for dir in directories(&var) {
+        for subdir in &subdirs {
+            if let Ok(entry) = fs::read(dir.join(subdir).join(&term)) {
+                return Some(entry);
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the directories to search for terminfo entries, in order.
+fn directories<F>(var: &F) -> Vec<PathBuf> where F: Fn(&str) -> Option<String> {
+    let mut dirs = Vec::new();
+
+    if let Some(dir) = var("TERMINFO") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(home) = var("HOME") {
+        dirs.push(Path::new(&home).join(".terminfo"));
+    }
+
+    match var("TERMINFO_DIRS") {
+        Some(list) => {
+            for dir in list.split(':') {
+                if dir.is_empty() {
+                    dirs.extend(DEFAULT_DIRS.iter().map(PathBuf::from));
+                } else {
+                    dirs.push(PathBuf::from(dir));
+                }
+            }
+        }
+        None => dirs.extend(DEFAULT_DIRS.iter().map(PathBuf::from)),
+    }
+
+    dirs
+}
+
+/// Returns whether the compiled terminfo `entry` has the string capability at
+/// index `cap`, such as `ENTER_ITALICS_MODE`.
+///
+/// A capability that is absent or cancelled counts as missing. `None` is
+/// returned if `entry` is not a compiled terminfo entry or is truncated.
+pub fn has_string(entry: &[u8], cap: usize) -> Option<bool> {
+    let word = |index: usize| {
+        entry.get(index..index + 2).map(|b| (u16::from(b[0]) | u16::from(b[1]) << 8) as i16)
+    };
+
+    // A missing field reads as -1, which is rejected along with any other
+    // negative size below.
+    let mut header = [0; 6];
+    for (i, field) in header.iter_mut().enumerate() {
+        *field = word(i * 2).unwrap_or(-1);
+    }
+
+    let number_size = match header[0] {
+        MAGIC => 2,
+        MAGIC_32 => 4,
+        _ => return None,
+    };
+    if header[1..].iter().any(|&field| field < 0) {
+        return None;
+    }
+    let (names, bools, numbers, strings, table) =
+        (header[1] as usize, header[2] as usize, header[3] as usize, header[4] as usize,
+         header[5] as usize);
+
+    if cap >= strings {
+        return Some(false);
+    }
+
+    // The numbers section starts on an even byte.
+    let mut offset = 12 + names + bools;
+    offset += offset % 2;
+    offset += numbers * number_size;
+
+    let table_start = offset + strings * 2;
+    if entry.len() < table_start + table {
+        return None;
+    }
+
+    word(offset + cap * 2).map(|position| position >= 0 && (position as usize) < table)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    use std::env;
+
+    /// Returns a compiled terminfo entry named `name` whose string
+    /// capabilities are `strings`, with `None` for absent ones.
+    pub fn entry(name: &str, strings: &[Option<&str>], magic: i16) -> Vec<u8> {
+        let number_size = if magic == MAGIC_32 { 4 } else { 2 };
+        let names = name.len() + 1;
+        let bools = 1;
+        let numbers = 1;
+
+        let mut offsets = Vec::new();
+        let mut table = Vec::new();
+        for string in strings {
+            match *string {
+                Some(string) => {
+                    offsets.push(table.len() as i16);
+                    table.extend_from_slice(string.as_bytes());
+                    table.push(0);
+                }
+                None => offsets.push(-1),
+            }
+        }
+
+        let mut bytes = Vec::new();
+        for &field in &[magic, names as i16, bools as i16, numbers as i16,
+                        strings.len() as i16, table.len() as i16] {
+            bytes.extend_from_slice(&[field as u8, (field >> 8) as u8]);
+        }
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.push(0);
+        bytes.push(1);
+        if bytes.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        bytes.extend(vec![0xff; numbers * number_size]);
+        for offset in offsets {
+            bytes.extend_from_slice(&[offset as u8, (offset >> 8) as u8]);
+        }
+        bytes.extend(table);
+
+        bytes
+    }
+
+    /// Returns a list of `len` string capabilities with `cap` set and every
+    /// other one absent.
+    pub fn only(cap: usize, len: usize) -> Vec<Option<&'static str>> {
+        (0..len).map(|i| if i == cap { Some("\x1b[0m") } else { None }).collect()
+    }
+
+    /// Creates a fresh terminfo directory under the temporary directory,
+    /// unique to `name` and this process, and returns its path.
+    pub fn directory(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("tutil-terminfo-{}-{}", name,
+                                               ::std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes `entry` for the terminal `term` into `dir`, under `subdir`.
+    pub fn install(dir: &Path, subdir: &str, term: &str, entry: &[u8]) {
+        fs::create_dir_all(dir.join(subdir)).unwrap();
+        fs::write(dir.join(subdir).join(term), entry).unwrap();
+    }
+
+    #[test]
+    fn present_string_capability() {
+        let entry = entry("xterm", &only(ENTER_ITALICS_MODE, 400), MAGIC);
+
+        assert_eq!(has_string(&entry, ENTER_ITALICS_MODE), Some(true));
+        assert_eq!(has_string(&entry, ENTER_BLINK_MODE), Some(false));
+    }
+
+    #[test]
+    fn capability_beyond_the_entry_is_missing() {
+        let entry = entry("vt100", &only(ENTER_BLINK_MODE, 100), MAGIC);
+
+        assert_eq!(has_string(&entry, ENTER_BLINK_MODE), Some(true));
+        assert_eq!(has_string(&entry, KEY_MOUSE), Some(false));
+    }
+
+    #[test]
+    fn wide_number_entries() {
+        // An odd-length name also exercises the padding before the numbers.
+        let entry = entry("kitty", &only(KEY_MOUSE, 400), MAGIC_32);
+
+        assert_eq!(has_string(&entry, KEY_MOUSE), Some(true));
+        assert_eq!(has_string(&entry, ENTER_ITALICS_MODE), Some(false));
+    }
+
+    #[test]
+    fn malformed_entries() {
+        let entry = entry("xterm", &only(ENTER_ITALICS_MODE, 400), MAGIC);
+
+        assert_eq!(has_string(&entry[..entry.len() - 3], ENTER_ITALICS_MODE), None);
+        assert_eq!(has_string(b"#!/bin/sh\n", ENTER_ITALICS_MODE), None);
+        assert_eq!(has_string(&[], ENTER_ITALICS_MODE), None);
+    }
+
+    #[test]
+    fn find_in_each_layout() {
+        let dir = directory("layout");
+        install(&dir, "x", "xterm-test", b"letter");
+        install(&dir, "6b", "kitty-test", b"hex");
+        let dirs = format!("/nonexistent:{}", dir.display());
+
+        let find_term = |term: &str| find(|name: &str| match name {
+            "TERM" => Some(term.to_string()),
+            "TERMINFO_DIRS" => Some(dirs.clone()),
+            _ => None,
+        });
+
+        assert_eq!(find_term("xterm-test"), Some(b"letter".to_vec()));
+        assert_eq!(find_term("kitty-test"), Some(b"hex".to_vec()));
+        assert_eq!(find_term("screen-test"), None);
+        assert_eq!(find_term("../x/xterm-test"), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn terminfo_variable_searched_first() {
+        let first = directory("first");
+        let second = directory("second");
+        install(&first, "x", "xterm-test", b"first");
+        install(&second, "x", "xterm-test", b"second");
+        let (first_path, second_path) = (first.display().to_string(),
+                                         second.display().to_string());
+
+        let entry = find(|name: &str| match name {
+            "TERM" => Some("xterm-test".to_string()),
+            "TERMINFO" => Some(first_path.clone()),
+            "TERMINFO_DIRS" => Some(second_path.clone()),
+            _ => None,
+        });
+
+        assert_eq!(entry, Some(b"first".to_vec()));
+
+        fs::remove_dir_all(&first).unwrap();
+        fs::remove_dir_all(&second).unwrap();
+    }
+}
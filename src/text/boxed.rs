@@ -0,0 +1,179 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Drawing boxes around text.
+
+use crayon::visible_width;
+use screen::{self, Height};
+
+use super::{default_glyphs, pad, Align, GlyphSet};
+
+/// How tall a box drawn by [`boxed_with()`] should be.
+///
+/// [`boxed_with()`]: fn.boxed_with.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BoxHeight {
+    /// Just tall enough to fit the content.
+    #[default]
+    Fit,
+
+    /// As tall as the terminal, which is useful for simple full-screen
+    /// layouts. If the height of the terminal cannot be determined, this is
+    /// the same as `Fit`.
+    FillTerminal,
+
+    /// The given number of lines, including the top and bottom borders. The
+    /// box is never made shorter than its content.
+    Lines(usize),
+}
+
+/// Where content is placed within a box that is taller than the content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VerticalAlign {
+    /// The content is placed at the top, with blank lines below it.
+    #[default]
+    Top,
+
+    /// The content is placed in the middle, with any odd blank line placed
+    /// below it.
+    Center,
+}
+
+/// Options for drawing a box with [`boxed_with()`].
+///
+/// [`boxed_with()`]: fn.boxed_with.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoxOptions {
+    /// The characters used to draw the border, which default to
+    /// [`default_glyphs()`](fn.default_glyphs.html).
+    pub glyphs: GlyphSet,
+
+    /// How tall the box should be, which defaults to `BoxHeight::Fit`.
+    pub height: BoxHeight,
+
+    /// Where the content is placed if the box is taller than it, which
+    /// defaults to `VerticalAlign::Top`.
+    pub align: VerticalAlign,
+}
+
+impl Default for BoxOptions {
+    fn default() -> BoxOptions {
+        BoxOptions {
+            glyphs: default_glyphs(),
+            height: BoxHeight::default(),
+            align: VerticalAlign::default(),
+        }
+    }
+}
+
+/// Draws a box around `content`, just large enough to fit it.
+///
+/// ```
+/// use tutil::text;
+///
+/// println!("{}", text::boxed("Hello,\nworld!"));
+/// ```
+pub fn boxed(content: &str) -> String {
+    boxed_with(content, &BoxOptions::default())
+}
+
+/// Draws a box around `content` according to `options`.
+///
+/// ```
+/// use tutil::text::{self, BoxHeight, BoxOptions, GlyphSet, VerticalAlign};
+///
+/// let options = BoxOptions {
+///     glyphs: GlyphSet::Ascii,
+///     height: BoxHeight::Lines(5),
+///     align: VerticalAlign::Center,
+/// };
+///
+/// assert_eq!(text::boxed_with("Hi", &options), "+----+\n|    |\n| Hi |\n|    |\n+----+");
+/// ```
+pub fn boxed_with(content: &str, options: &BoxOptions) -> String {
+    let (horizontal, vertical, corners) = match options.glyphs {
+        GlyphSet::Unicode => ('─', '│', ['┌', '┐', '└', '┘']),
+        GlyphSet::Ascii => ('-', '|', ['+', '+', '+', '+']),
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    let width = lines.iter().map(|line| visible_width(line)).max().unwrap_or(0);
+
+    let height = match options.height {
+        BoxHeight::Fit => 0,
+        BoxHeight::FillTerminal => screen::height().map_or(0, |Height(height)| height as usize),
+        BoxHeight::Lines(height) => height,
+    };
+
+    let blank = height.saturating_sub(lines.len() + 2);
+    let (above, below) = match options.align {
+        VerticalAlign::Top => (0, blank),
+        VerticalAlign::Center => (blank / 2, blank - blank / 2),
+    };
+
+    let rule: String = (0..width + 2).map(|_| horizontal).collect();
+    let empty = String::new();
+
+    let mut output = Vec::with_capacity(lines.len() + blank + 2);
+    output.push(format!("{}{}{}", corners[0], rule, corners[1]));
+
+    let interior = (0..above).map(|_| &empty[..])
+        .chain(lines.iter().cloned())
+        .chain((0..below).map(|_| &empty[..]));
+
+    for line in interior {
+        output.push(format!("{} {} {}", vertical, pad(line, width, Align::Left), vertical));
+    }
+
+    output.push(format!("{}{}{}", corners[2], rule, corners[3]));
+    output.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn options(height: BoxHeight, align: VerticalAlign) -> BoxOptions {
+        BoxOptions { glyphs: GlyphSet::Ascii, height, align }
+    }
+
+    #[test]
+    fn fit_content() {
+        let boxed = boxed_with("a\nbcd", &options(BoxHeight::Fit, VerticalAlign::Top));
+        assert_eq!(boxed, "+-----+\n| a   |\n| bcd |\n+-----+");
+    }
+
+    #[test]
+    fn unicode_glyphs() {
+        let options = BoxOptions { glyphs: GlyphSet::Unicode, ..BoxOptions::default() };
+        assert_eq!(boxed_with("a", &options), "┌───┐\n│ a │\n└───┘");
+    }
+
+    #[test]
+    fn explicit_height_top_aligned() {
+        let boxed = boxed_with("a", &options(BoxHeight::Lines(6), VerticalAlign::Top));
+        let lines: Vec<&str> = boxed.lines().collect();
+
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[1], "| a |");
+        assert_eq!(lines[2..5], ["|   |"; 3]);
+    }
+
+    #[test]
+    fn explicit_height_center_aligned() {
+        let boxed = boxed_with("a", &options(BoxHeight::Lines(6), VerticalAlign::Center));
+        let lines: Vec<&str> = boxed.lines().collect();
+
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[1], "|   |");
+        assert_eq!(lines[2], "| a |");
+        assert_eq!(lines[3..5], ["|   |"; 2]);
+    }
+
+    #[test]
+    fn height_never_smaller_than_content() {
+        let boxed = boxed_with("a\nb\nc", &options(BoxHeight::Lines(2), VerticalAlign::Top));
+        assert_eq!(boxed.lines().count(), 5);
+    }
+}
@@ -0,0 +1,89 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Colouring the lines of a unified diff.
+
+use crayon::{Style, StyledString};
+use crayon::Color::{Cyan, Green, Red};
+
+/// Styles a single line of a unified diff: added lines, starting with `+`,
+/// are green, removed lines, starting with `-`, are red, hunk headers,
+/// starting with `@@`, are cyan and any other line is left plain.
+///
+/// ```
+/// use tutil::text;
+///
+/// println!("{}", text::diff_line("+fn main() {}"));
+/// ```
+pub fn diff_line(line: &str) -> StyledString<'_> {
+    let style = if line.starts_with("@@") {
+        Cyan.normal()
+    } else if line.starts_with('+') {
+        Green.normal()
+    } else if line.starts_with('-') {
+        Red.normal()
+    } else {
+        Style::default()
+    };
+
+    style.paint(line)
+}
+
+/// Styles every line of a unified diff with [`diff_line()`], keeping the line
+/// endings as they are.
+///
+/// The escape codes of each line end before its line ending, so the colour of
+/// one line never carries over to the next.
+///
+/// [`diff_line()`]: fn.diff_line.html
+pub fn colorize_diff(diff: &str) -> String {
+    let mut output = String::with_capacity(diff.len() * 2);
+
+    for line in diff.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+
+        output.push_str(&diff_line(content).to_string());
+        output.push_str(&line[content.len()..]);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crayon;
+
+    #[test]
+    fn added_line() {
+        crayon::set_enabled(true);
+        assert_eq!(diff_line("+let x = 1;").to_string(), "\x1b[32m+let x = 1;\x1b[0m");
+    }
+
+    #[test]
+    fn removed_line() {
+        crayon::set_enabled(true);
+        assert_eq!(diff_line("-let x = 0;").to_string(), "\x1b[31m-let x = 0;\x1b[0m");
+    }
+
+    #[test]
+    fn hunk_header() {
+        crayon::set_enabled(true);
+        assert_eq!(diff_line("@@ -1,3 +1,3 @@").to_string(), "\x1b[36m@@ -1,3 +1,3 @@\x1b[0m");
+    }
+
+    #[test]
+    fn context_line() {
+        assert_eq!(diff_line(" fn main() {").to_string(), " fn main() {");
+        assert_eq!(diff_line("").to_string(), "");
+    }
+
+    #[test]
+    fn colorize_whole_diff() {
+        crayon::set_enabled(true);
+        let diff = "@@ -1 +1 @@\n-old\r\n+new\n same";
+        assert_eq!(colorize_diff(diff),
+                   "\x1b[36m@@ -1 +1 @@\x1b[0m\n\x1b[31m-old\x1b[0m\r\n\x1b[32m+new\x1b[0m\n same");
+    }
+}
@@ -0,0 +1,94 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Laying out a flat list of items as a grid, in the style of `ls`.
+
+use crayon::visible_width;
+
+use super::COLUMN_GAP;
+
+/// Works out how to lay `items` out in a grid no wider than `width` columns,
+/// returning the number of columns and rows as `(columns, rows)`.
+///
+/// Items are assumed to fill the grid down each column and then across, as
+/// `ls` does, with each column as wide as its widest item and two spaces
+/// between columns. The layout with the most columns that fits is chosen.
+///
+/// If even a single column does not fit, because an item is wider than
+/// `width`, the result is one column with a row for each item. An empty list
+/// of items results in `(0, 0)`.
+///
+/// ```
+/// use tutil::text;
+///
+/// let items = ["one", "two", "three", "four", "five"];
+/// assert_eq!(text::grid_layout(&items, 80), (5, 1));
+/// assert_eq!(text::grid_layout(&items, 16), (3, 2));
+/// ```
+pub fn grid_layout(items: &[&str], width: usize) -> (usize, usize) {
+    if items.is_empty() {
+        return (0, 0);
+    }
+
+    let widths: Vec<usize> = items.iter().map(|item| visible_width(item)).collect();
+
+    for columns in (2..items.len() + 1).rev() {
+        // Filling down the columns first means that the number of rows
+        // decides the layout, and some column counts are not achievable. For
+        // example five items in four columns would need two rows, but two
+        // rows only ever need three columns.
+        let rows = items.len().div_ceil(columns);
+        let columns = items.len().div_ceil(rows);
+
+        let total: usize = widths.chunks(rows)
+            .map(|column| column.iter().cloned().max().unwrap_or(0))
+            .sum::<usize>() + COLUMN_GAP * (columns - 1);
+
+        if total <= width {
+            return (columns, rows);
+        }
+    }
+
+    (1, items.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crayon::Color::Red;
+
+    #[test]
+    fn fits_in_one_row() {
+        assert_eq!(grid_layout(&["a", "bb", "ccc"], 80), (3, 1));
+        assert_eq!(grid_layout(&["a", "bb", "ccc"], 10), (3, 1));
+    }
+
+    #[test]
+    fn wraps_onto_several_rows() {
+        let items = ["alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta"];
+
+        // Two rows would need "alpha", "gamma", "epsilon" and "eta" as the
+        // first row: 5 + 5 + 7 + 3 + 2 * 3 = 26 columns.
+        assert_eq!(grid_layout(&items, 26), (4, 2));
+        assert_eq!(grid_layout(&items, 25), (3, 3));
+        assert_eq!(grid_layout(&items, 7), (1, 7));
+    }
+
+    #[test]
+    fn single_item_too_wide() {
+        assert_eq!(grid_layout(&["abcdefghij"], 5), (1, 1));
+        assert_eq!(grid_layout(&["a", "abcdefghij", "b"], 5), (1, 3));
+    }
+
+    #[test]
+    fn styled_items_measured_by_display_width() {
+        let styled = Red.paint("abc").to_string();
+        assert_eq!(grid_layout(&[&styled, "def"], 8), (2, 1));
+    }
+
+    #[test]
+    fn no_items() {
+        assert_eq!(grid_layout(&[], 80), (0, 0));
+    }
+}
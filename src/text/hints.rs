@@ -0,0 +1,92 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Laying out a line of key binding hints, such as in the footer of a prompt.
+
+use crayon::{visible_width, Style};
+use screen::{self, Size, Width};
+
+/// The spaces between one hint and the next.
+const SEPARATOR: &str = "  ";
+
+/// Renders `hints` as a list of key bindings and what they do, such as
+/// `q quit  ↑/↓ move  ⏎ select`, with each key styled with `key_style` and
+/// each description with `desc_style`.
+///
+/// A key and its description are separated by a space and the hints by two.
+/// Hints that do not fit in the width of the terminal are moved onto further
+/// lines, but a hint is never split between lines. If the size of the
+/// terminal cannot be determined, a width of 80 columns is assumed. The last
+/// line does not end with a newline.
+///
+/// ```
+/// use tutil::crayon::Style;
+/// use tutil::text;
+///
+/// let hints = [("q", "quit"), ("↑/↓", "move"), ("⏎", "select")];
+/// println!("{}", text::key_hints(&hints, Style::new().bold(), Style::new().dimmed()));
+/// ```
+pub fn key_hints(hints: &[(&str, &str)], key_style: Style, desc_style: Style) -> String {
+    let Size(Width(width), _) = screen::size_or_default();
+    key_hints_in(hints, key_style, desc_style, width as usize)
+}
+
+/// The implementation of `key_hints()`, wrapping the hints to `width`
+/// columns.
+fn key_hints_in(hints: &[(&str, &str)], key_style: Style, desc_style: Style, width: usize)
+                -> String {
+    let mut output = String::new();
+    let mut line_width = 0;
+
+    for &(key, description) in hints {
+        let hint_width = visible_width(key) + 1 + visible_width(description);
+
+        if line_width > 0 {
+            if line_width + SEPARATOR.len() + hint_width > width {
+                output.push('\n');
+                line_width = 0;
+            } else {
+                output.push_str(SEPARATOR);
+                line_width += SEPARATOR.len();
+            }
+        }
+
+        output.push_str(&format!("{} {}", key_style.paint(key), desc_style.paint(description)));
+        line_width += hint_width;
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crayon;
+
+    #[test]
+    fn hints_styled_and_spaced() {
+        crayon::set_enabled(true);
+        let hints = [("q", "quit"), ("↑/↓", "move")];
+        let output = key_hints_in(&hints, Style::new().bold(), Style::new().dimmed(), 80);
+
+        assert_eq!(output,
+                   "\x1b[1mq\x1b[0m \x1b[2mquit\x1b[0m  \x1b[1m↑/↓\x1b[0m \x1b[2mmove\x1b[0m");
+    }
+
+    #[test]
+    fn hints_wrap_between_hints() {
+        let hints = [("q", "quit"), ("↑/↓", "move"), ("enter", "select")];
+        let plain = Style::new();
+
+        assert_eq!(key_hints_in(&hints, plain, plain, 16), "q quit  ↑/↓ move\nenter select");
+        assert_eq!(key_hints_in(&hints, plain, plain, 15), "q quit\n↑/↓ move\nenter select");
+    }
+
+    #[test]
+    fn hint_wider_than_line_kept_whole() {
+        let hints = [("ctrl-c", "cancel"), ("q", "quit")];
+        assert_eq!(key_hints_in(&hints, Style::new(), Style::new(), 5), "ctrl-c cancel\nq quit");
+        assert_eq!(key_hints_in(&[], Style::new(), Style::new(), 80), "");
+    }
+}
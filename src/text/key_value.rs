@@ -0,0 +1,92 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Laying out keys and values in two aligned columns.
+
+use crayon::{visible_width, Style};
+use screen::{self, Size, Width};
+
+use super::{pad, Align};
+use super::wrap::wrap_lines;
+
+/// Renders `pairs` as two columns, with each key styled with `key_style` and
+/// followed by `gap` spaces, and the values lined up after the widest key.
+///
+/// Values that do not fit in the width of the terminal are wrapped onto
+/// further lines, which are indented to line up with the start of the value.
+/// If the size of the terminal cannot be determined, a width of 80 columns is
+/// assumed. Every line, including the last, ends with a newline.
+///
+/// ```
+/// use tutil::crayon::Style;
+/// use tutil::text;
+///
+/// let pairs = [("name", "tutil"), ("version", "0.2.0")];
+/// print!("{}", text::key_value(&pairs, Style::new().bold(), 2));
+/// ```
+pub fn key_value(pairs: &[(&str, &str)], key_style: Style, gap: usize) -> String {
+    let Size(Width(width), _) = screen::size_or_default();
+    key_value_in(pairs, key_style, gap, width as usize)
+}
+
+/// The implementation of `key_value()`, laying the pairs out in `width`
+/// columns.
+fn key_value_in(pairs: &[(&str, &str)], key_style: Style, gap: usize, width: usize) -> String {
+    let key_width = pairs.iter().map(|&(key, _)| visible_width(key)).max().unwrap_or(0);
+    let indent = key_width + gap;
+
+    let mut output = String::new();
+
+    for &(key, value) in pairs {
+        output.push_str(&pad(&key_style.paint(key).to_string(), indent, Align::Left));
+
+        let lines = wrap_lines(value, width.saturating_sub(indent));
+
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                output.extend((0..indent).map(|_| ' '));
+            }
+
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crayon;
+
+    #[test]
+    fn keys_aligned() {
+        let pairs = [("a", "one"), ("longer", "two"), ("mid", "three")];
+        let output = key_value_in(&pairs, Style::new(), 2, 80);
+
+        assert_eq!(output, "a       one\nlonger  two\nmid     three\n");
+    }
+
+    #[test]
+    fn styled_keys_padded_outside_style() {
+        crayon::set_enabled(true);
+        let output = key_value_in(&[("a", "one"), ("bb", "two")], Style::new().underline(), 1, 80);
+        assert_eq!(output, "\x1b[4ma\x1b[0m  one\n\x1b[4mbb\x1b[0m two\n");
+    }
+
+    #[test]
+    fn long_value_wraps_with_hanging_indent() {
+        let pairs = [("key", "the quick brown fox jumps"), ("k", "short")];
+        let output = key_value_in(&pairs, Style::new(), 3, 16);
+
+        assert_eq!(output, "key   the quick\n      brown fox\n      jumps\nk     short\n");
+    }
+
+    #[test]
+    fn empty_value() {
+        assert_eq!(key_value_in(&[("key", "")], Style::new(), 2, 80), "key  \n");
+        assert_eq!(key_value_in(&[], Style::new(), 2, 80), "");
+    }
+}
@@ -0,0 +1,592 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A module for manipulating text that is destined for the terminal.
+//!
+//! Every function in this module is aware of ANSI escape sequences, such as
+//! those produced by `tutil::crayon`, and does not count them towards the
+//! width of the text.
+//!
+//! # Basic Usage
+//!
+//! ```
+//! use tutil::text;
+//!
+//! assert_eq!(text::expand_tabs("a\tb", 4), "a   b");
+//! ```
+
+use unicode_width::UnicodeWidthChar;
+
+use std::borrow::Cow;
+use std::path;
+
+use ansi::{self, Segment};
+use crayon::{visible_width, Style, StyledString};
+use screen::{self, Size, Width};
+use system;
+
+pub use self::boxed::{boxed, boxed_with, BoxHeight, BoxOptions, VerticalAlign};
+pub use self::diff::{colorize_diff, diff_line};
+pub use self::grid::grid_layout;
+pub use self::hints::key_hints;
+pub use self::key_value::key_value;
+pub use self::table::{Align, Table};
+pub use self::truncate::{truncate, TruncateAlign, TruncateOptions};
+pub use self::wrap::{balance_columns, fits_in, rows_for, wrap, wrap_hyphenated};
+
+mod boxed;
+mod diff;
+mod grid;
+mod hints;
+mod key_value;
+mod table;
+mod truncate;
+mod wrap;
+
+/// The number of spaces placed between adjacent columns of a table or grid.
+const COLUMN_GAP: usize = 2;
+
+/// The set of characters used when drawing boxes, rules, tables, progress bars
+/// and spinners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlyphSet {
+    /// Box-drawing and block characters from Unicode, such as `─` and `█`.
+    Unicode,
+
+    /// Plain ASCII characters, such as `-` and `#`, for terminals and locales
+    /// that cannot display Unicode.
+    Ascii,
+}
+
+/// Returns the [`GlyphSet`] that suits the current environment.
+///
+/// This is `GlyphSet::Ascii` if the locale does not support Unicode (see
+/// `tutil::system::unicode_support()`) or `TERM` is `dumb`, otherwise it is
+/// `GlyphSet::Unicode`.
+///
+/// [`GlyphSet`]: enum.GlyphSet.html
+pub fn default_glyphs() -> GlyphSet {
+    default_glyphs_with(system::var)
+}
+
+/// The implementation of `default_glyphs()`, reading environment variables
+/// through `var`.
+fn default_glyphs_with<F>(var: F) -> GlyphSet where F: Fn(&str) -> Option<String> {
+    if var("TERM").as_deref() == Some("dumb") || !system::unicode_support_with(&var) {
+        GlyphSet::Ascii
+    } else {
+        GlyphSet::Unicode
+    }
+}
+
+/// Pads `s` with spaces to `width` columns according to `align`.
+///
+/// Strings that are already at least `width` columns wide are returned
+/// unchanged.
+fn pad(s: &str, width: usize, align: Align) -> String {
+    let extra = width.saturating_sub(visible_width(s));
+
+    let (left, right) = match align {
+        Align::Left => (0, extra),
+        Align::Right => (extra, 0),
+        Align::Center => (extra / 2, extra - extra / 2),
+    };
+
+    let mut padded = String::with_capacity(s.len() + extra);
+    padded.extend((0..left).map(|_| ' '));
+    padded.push_str(s);
+    padded.extend((0..right).map(|_| ' '));
+    padded
+}
+
+/// Removes what commonly gets in the way of comparing input that was read from
+/// a file or pasted in: a leading byte order mark, and the line ending,
+/// whether it is `\n` or `\r\n`.
+///
+/// Every trailing `\r` and `\n` is removed, so a stray carriage return from a
+/// Windows paste does not stop `"y\r"` from matching `"y"`. The prompts in
+/// `tutil::prompt` apply this to every line they read.
+///
+/// ```
+/// use tutil::text;
+///
+/// assert_eq!(text::normalize_input("\u{feff}yes\r\n"), "yes");
+/// ```
+pub fn normalize_input(input: &str) -> &str {
+    input.strip_prefix('\u{feff}').unwrap_or(input).trim_end_matches(['\r', '\n'])
+}
+
+/// Replaces every tab in `input` with enough spaces to reach the next tab stop,
+/// where tab stops are placed every `tab_width` columns.
+///
+/// Only visible columns are counted, so escape sequences do not throw off the
+/// alignment of coloured text. The column is reset at the start of every line.
+/// A `tab_width` of 0 removes tabs entirely.
+pub fn expand_tabs(input: &str, tab_width: usize) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut column = 0;
+
+    for segment in ansi::segments(input) {
+        match segment {
+            Segment::Escape(escape) => output.push_str(escape),
+            Segment::Text(text) => {
+                for c in text.chars() {
+                    match c {
+                        '\t' if tab_width > 0 => {
+                            let spaces = tab_width - column % tab_width;
+                            output.extend((0..spaces).map(|_| ' '));
+                            column += spaces;
+                        }
+                        '\t' => {}
+                        '\n' | '\r' => {
+                            output.push(c);
+                            column = 0;
+                        }
+                        _ => {
+                            output.push(c);
+                            column += c.width().unwrap_or(0);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    output
+}
+
+/// Truncates every line of `art`, such as a banner loaded from a file, to the
+/// width of the terminal so that it does not wrap and garble the layout.
+///
+/// This is [`clamp_block()`] with the width of the terminal, or 80 columns if
+/// that cannot be determined.
+///
+/// [`clamp_block()`]: fn.clamp_block.html
+///
+/// ```
+/// use tutil::crayon::Color::Red;
+/// use tutil::text;
+///
+/// let banner = Red.bold().paint("=== Welcome to tutil ===").to_string();
+/// println!("{}", text::fit_to_width(&banner));
+/// ```
+pub fn fit_to_width(art: &str) -> String {
+    let Size(Width(width), _) = screen::size_or_default();
+    clamp_block(art, width as usize)
+}
+
+/// Truncates every line of `block` to at most `width` display columns, so that
+/// it can be printed without any line wrapping.
+///
+/// Escape sequences are kept, and a reset is appended to any styled line that
+/// is cut short so that its style does not leak into the following output.
+///
+/// ```
+/// use tutil::text;
+///
+/// assert_eq!(text::clamp_block("tutil\ncrayon", 4), "tuti\ncray");
+/// ```
+pub fn clamp_block(block: &str, width: usize) -> String {
+    block.split('\n').map(|line| truncate_line(line, width)).collect::<Vec<_>>().join("\n")
+}
+
+/// Truncates `line` to at most `width` display columns, keeping any escape
+/// sequences before the cut and appending a reset if the line was styled.
+fn truncate_line(line: &str, width: usize) -> Cow<'_, str> {
+    if visible_width(line) <= width {
+        return Cow::Borrowed(line);
+    }
+
+    let mut output = String::with_capacity(line.len());
+    let mut column = 0;
+    let mut styled = false;
+
+    'segments: for segment in ansi::segments(line) {
+        match segment {
+            Segment::Escape(escape) => {
+                output.push_str(escape);
+                styled = true;
+            }
+            Segment::Text(text) => {
+                for c in text.chars() {
+                    column += c.width().unwrap_or(0);
+
+                    if column > width {
+                        break 'segments;
+                    }
+
+                    output.push(c);
+                }
+            }
+        }
+    }
+
+    if styled {
+        output.push_str("\x1b[0m");
+    }
+
+    Cow::Owned(output)
+}
+
+/// Shortens `path` to at most `width` display columns by replacing the middle
+/// with `…`, so that the leading components and the file name stay visible.
+///
+/// As many leading components are kept as fit. If not even the first one
+/// fits, only the file name is kept, and if the file name does not fit either,
+/// its end is kept so that the extension can still be seen. Paths that already
+/// fit are returned unchanged.
+///
+/// ```
+/// use tutil::text;
+///
+/// let path = "/home/user/projects/tutil/src/text/mod.rs";
+/// assert_eq!(text::truncate_path(path, 24), "/home/user/…/mod.rs");
+/// assert_eq!(text::truncate_path(path, 8), "…/mod.rs");
+/// assert_eq!(text::truncate_path(path, 5), "…d.rs");
+/// ```
+pub fn truncate_path(path: &str, width: usize) -> String {
+    if visible_width(path) <= width {
+        return path.to_string();
+    }
+
+    if width == 0 {
+        return String::new();
+    }
+
+    // The file name keeps its separator, which ends up after the ellipsis.
+    let (dirs, file) = path.split_at(path.rfind(path::is_separator).unwrap_or(0));
+
+    let prefix = dirs.match_indices(path::is_separator)
+        .map(|(i, separator)| &dirs[..i + separator.len()])
+        .take_while(|prefix| visible_width(prefix) + 1 + visible_width(file) <= width)
+        .last();
+
+    if let Some(prefix) = prefix {
+        return format!("{}…{}", prefix, file);
+    }
+
+    if visible_width(file) < width {
+        return format!("…{}", file);
+    }
+
+    // Keep as much of the end of the file name as fits after the ellipsis.
+    let mut column = 1;
+    let start = file.char_indices()
+        .rev()
+        .take_while(|&(_, c)| {
+            column += c.width().unwrap_or(0);
+            column <= width
+        })
+        .last()
+        .map_or(file.len(), |(i, _)| i);
+
+    format!("…{}", &file[start..])
+}
+
+/// Divides `width` columns between fields in proportion to `weights`,
+/// returning the column that each field starts at.
+///
+/// The width of each field is the difference between its position and the
+/// next one, or `width` for the last field, so the widths always add up to
+/// exactly `width`. Positions are rounded down, which means that they never
+/// exceed `width`. Negative weights count as zero, and if no weight is above
+/// zero the columns are divided equally.
+///
+/// ```
+/// use tutil::text;
+///
+/// assert_eq!(text::column_positions(80, &[1.0, 1.0, 2.0]), [0, 20, 40]);
+/// ```
+pub fn column_positions(width: usize, weights: &[f32]) -> Vec<usize> {
+    let weights: Vec<f64> = weights.iter().map(|&weight| (weight as f64).max(0.0)).collect();
+    let total: f64 = weights.iter().sum();
+
+    let mut before = 0.0;
+    let mut positions = Vec::with_capacity(weights.len());
+
+    for (i, weight) in weights.iter().enumerate() {
+        let position = if total > 0.0 {
+            (width as f64 * before / total).floor() as usize
+        } else {
+            width * i / weights.len()
+        };
+
+        positions.push(position.min(width));
+        before += weight;
+    }
+
+    positions
+}
+
+/// Renders a two-state indicator, such as for a setting in a status display,
+/// using `style_on` or `style_off` depending on `on`.
+///
+/// The indicator is `●` when on and `○` when off, or `[x]` and `[ ]` when
+/// `default_glyphs()` is `GlyphSet::Ascii`.
+///
+/// ```
+/// use tutil::crayon::Color::{Green, White};
+/// use tutil::text;
+///
+/// println!("{} Notifications", text::toggle(true, Green.normal(), White.dimmed()));
+/// ```
+pub fn toggle(on: bool, style_on: Style, style_off: Style) -> StyledString<'static> {
+    toggle_with(on, style_on, style_off, default_glyphs())
+}
+
+/// The implementation of `toggle()`, drawing with the given glyphs.
+fn toggle_with(on: bool, style_on: Style, style_off: Style, glyphs: GlyphSet)
+               -> StyledString<'static> {
+    let glyph = match (glyphs, on) {
+        (GlyphSet::Unicode, true) => "●",
+        (GlyphSet::Unicode, false) => "○",
+        (GlyphSet::Ascii, true) => "[x]",
+        (GlyphSet::Ascii, false) => "[ ]",
+    };
+
+    if on { style_on.paint(glyph) } else { style_off.paint(glyph) }
+}
+
+/// The block characters that fill one to seven eighths of a cell from the
+/// left, used for the partly filled cell of `cell_progress()`.
+const EIGHTHS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Renders a progress bar that is exactly `width` columns wide, such as for a
+/// cell of a [`Table`], with `fraction` of it filled.
+///
+/// `fraction` is clamped to between 0.0 and 1.0. With Unicode glyphs the bar
+/// is drawn with block characters, using partial blocks so that it fills in
+/// eighths of a cell, and the rest of the bar is blank. With ASCII glyphs the
+/// bar is drawn with `#` and `-`. The whole bar is painted with `style`, so a
+/// background colour shows the unfilled part.
+///
+/// ```
+/// use tutil::crayon::Color::{Black, Green};
+/// use tutil::text::{self, Table};
+///
+/// let mut table = Table::new();
+/// table.add_row(vec!["build".into(),
+///                    text::cell_progress(0.4, 10, Green.on(Black)).to_string()]);
+/// ```
+///
+/// [`Table`]: struct.Table.html
+pub fn cell_progress(fraction: f64, width: usize, style: Style) -> StyledString<'static> {
+    cell_progress_with(fraction, width, style, default_glyphs())
+}
+
+/// The implementation of `cell_progress()`, drawing with the given glyphs.
+fn cell_progress_with(fraction: f64, width: usize, style: Style, glyphs: GlyphSet)
+                      -> StyledString<'static> {
+    let fraction = if fraction.is_nan() { 0.0 } else { fraction.clamp(0.0, 1.0) };
+    let mut bar = String::with_capacity(width * 3);
+
+    match glyphs {
+        GlyphSet::Unicode => {
+            let eighths = (fraction * width as f64 * 8.0).round() as usize;
+            bar.extend((0..eighths / 8).map(|_| '█'));
+
+            if let Some(&partial) = (eighths % 8).checked_sub(1).and_then(|i| EIGHTHS.get(i)) {
+                bar.push(partial);
+            }
+
+            let drawn = eighths.div_ceil(8);
+            bar.extend((drawn..width).map(|_| ' '));
+        }
+        GlyphSet::Ascii => {
+            let filled = (fraction * width as f64).round() as usize;
+            bar.extend((0..filled).map(|_| '#'));
+            bar.extend((filled..width).map(|_| '-'));
+        }
+    }
+
+    style.paint(bar)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crayon;
+    use crayon::Color::Red;
+    use system::test::fake_env;
+
+    #[test]
+    fn normalize_bom_and_line_endings() {
+        assert_eq!(normalize_input("\u{feff}y\r\n"), "y");
+        assert_eq!(normalize_input("y\r"), "y");
+        assert_eq!(normalize_input("y\n\n"), "y");
+        assert_eq!(normalize_input(" y \n"), " y ");
+        assert_eq!(normalize_input("a\u{feff}b"), "a\u{feff}b");
+        assert_eq!(normalize_input(""), "");
+    }
+
+    #[test]
+    fn expand_leading_tab() {
+        assert_eq!(expand_tabs("\tTEST", 8), "        TEST");
+    }
+
+    #[test]
+    fn expand_tab_after_colored_text() {
+        crayon::set_enabled(true);
+        let input = format!("{}\tTEST", Red.paint("ab"));
+        assert_eq!(expand_tabs(&input, 8), "\x1b[31mab\x1b[0m      TEST");
+    }
+
+    #[test]
+    fn expand_tabs_with_width_of_four() {
+        assert_eq!(expand_tabs("a\tbcd\tef\n\tg", 4), "a   bcd ef\n    g");
+    }
+
+    #[test]
+    fn expand_tabs_with_width_of_zero() {
+        assert_eq!(expand_tabs("a\tb", 0), "ab");
+    }
+
+    #[test]
+    fn unicode_glyphs_under_utf8_locale() {
+        let env = fake_env(&[("LANG", "en_NZ.UTF-8"), ("TERM", "xterm-256color")]);
+        assert_eq!(default_glyphs_with(env), GlyphSet::Unicode);
+    }
+
+    #[test]
+    fn ascii_glyphs_under_c_locale() {
+        let env = fake_env(&[("LANG", "C"), ("TERM", "xterm-256color")]);
+        assert_eq!(default_glyphs_with(env), GlyphSet::Ascii);
+    }
+
+    #[test]
+    fn ascii_glyphs_on_dumb_terminal() {
+        let env = fake_env(&[("LANG", "en_NZ.UTF-8"), ("TERM", "dumb")]);
+        assert_eq!(default_glyphs_with(env), GlyphSet::Ascii);
+    }
+
+    #[test]
+    fn truncate_colored_line() {
+        crayon::set_enabled(true);
+        let art = format!("{}\n{}", Red.paint("abcdefgh"), "abc");
+        let clamped = clamp_block(&art, 5);
+        let lines: Vec<&str> = clamped.lines().collect();
+
+        assert_eq!(lines, ["\x1b[31mabcde\x1b[0m", "abc"]);
+        assert!(lines.iter().all(|line| visible_width(line) <= 5));
+    }
+
+    #[test]
+    fn truncate_plain_line_without_reset() {
+        assert_eq!(clamp_block("abcdefgh\n", 3), "abc\n");
+    }
+
+    #[test]
+    fn truncate_before_wide_character() {
+        assert_eq!(clamp_block("ab日本", 3), "ab");
+    }
+
+    #[test]
+    fn clamp_block_with_one_long_line() {
+        crayon::set_enabled(true);
+        let block = format!("{}\nfits", Red.paint("too long"));
+        assert_eq!(clamp_block(&block, 4), "\x1b[31mtoo \x1b[0m\nfits");
+    }
+
+    #[test]
+    fn truncate_long_path() {
+        let path = "/home/u/projects/tutil/src/file.rs";
+        assert_eq!(truncate_path(path, 18), "/home/u/…/file.rs");
+        assert_eq!(truncate_path(path, 16), "/home/…/file.rs");
+        assert_eq!(truncate_path(path, 10), "/…/file.rs");
+        assert_eq!(truncate_path(path, 9), "…/file.rs");
+        assert_eq!(truncate_path(path, 4), "….rs");
+        assert_eq!(truncate_path(path, 1), "…");
+        assert_eq!(truncate_path(path, 0), "");
+    }
+
+    #[test]
+    fn truncate_relative_path() {
+        assert_eq!(truncate_path("src/text/table/mod.rs", 16), "src/…/mod.rs");
+        assert_eq!(truncate_path("a_very_long_file_name.rs", 10), "…e_name.rs");
+    }
+
+    #[test]
+    fn truncate_short_path() {
+        assert_eq!(truncate_path("src/lib.rs", 10), "src/lib.rs");
+        assert_eq!(truncate_path("/", 1), "/");
+    }
+
+    /// Returns the width of each field laid out by `column_positions()`.
+    fn field_widths(width: usize, positions: &[usize]) -> Vec<usize> {
+        positions.iter()
+            .zip(positions.iter().skip(1).chain(Some(&width)))
+            .map(|(start, end)| end - start)
+            .collect()
+    }
+
+    #[test]
+    fn column_positions_with_equal_weights() {
+        let positions = column_positions(80, &[1.0, 1.0, 1.0]);
+        assert_eq!(positions, [0, 26, 53]);
+        assert_eq!(field_widths(80, &positions).iter().sum::<usize>(), 80);
+    }
+
+    #[test]
+    fn column_positions_with_skewed_weights() {
+        let positions = column_positions(79, &[3.0, 1.0, 0.5, 0.0]);
+        assert_eq!(positions, [0, 52, 70, 79]);
+        assert_eq!(field_widths(79, &positions), [52, 18, 9, 0]);
+    }
+
+    #[test]
+    fn column_positions_without_positive_weights() {
+        assert_eq!(column_positions(10, &[0.0, -1.0]), [0, 5]);
+        assert_eq!(column_positions(10, &[]), []);
+        assert_eq!(column_positions(0, &[1.0, 2.0]), [0, 0]);
+    }
+
+    #[test]
+    fn unicode_toggle() {
+        crayon::set_enabled(true);
+        let on = toggle_with(true, Red.bold(), Style::default(), GlyphSet::Unicode);
+        let off = toggle_with(false, Red.bold(), Style::default(), GlyphSet::Unicode);
+
+        assert_eq!(on.to_string(), "\x1b[1;31m●\x1b[0m");
+        assert_eq!(off.to_string(), "○");
+    }
+
+    #[test]
+    fn ascii_toggle() {
+        crayon::set_enabled(true);
+        let on = toggle_with(true, Style::default(), Red.normal(), GlyphSet::Ascii);
+        let off = toggle_with(false, Style::default(), Red.normal(), GlyphSet::Ascii);
+
+        assert_eq!(on.to_string(), "[x]");
+        assert_eq!(off.to_string(), "\x1b[31m[ ]\x1b[0m");
+    }
+
+    #[test]
+    fn empty_cell_progress() {
+        let bar = cell_progress_with(0.0, 5, Style::default(), GlyphSet::Unicode);
+        assert_eq!(bar.to_string(), "     ");
+
+        let bar = cell_progress_with(-1.0, 5, Style::default(), GlyphSet::Ascii);
+        assert_eq!(bar.to_string(), "-----");
+    }
+
+    #[test]
+    fn partial_cell_progress() {
+        crayon::set_enabled(true);
+        let bar = cell_progress_with(0.45, 4, Red.normal(), GlyphSet::Unicode).to_string();
+        assert_eq!(bar, "\x1b[31m█▊  \x1b[0m");
+        assert_eq!(visible_width(&bar), 4);
+
+        let bar = cell_progress_with(0.45, 4, Style::default(), GlyphSet::Ascii);
+        assert_eq!(bar.to_string(), "##--");
+    }
+
+    #[test]
+    fn full_cell_progress() {
+        let bar = cell_progress_with(1.0, 3, Style::default(), GlyphSet::Unicode);
+        assert_eq!(bar.to_string(), "███");
+
+        let bar = cell_progress_with(2.5, 3, Style::default(), GlyphSet::Ascii);
+        assert_eq!(bar.to_string(), "###");
+    }
+}
@@ -0,0 +1,202 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A renderer for text laid out in aligned columns.
+
+use std::fmt;
+
+use crayon::visible_width;
+
+use super::{pad, COLUMN_GAP};
+
+/// The horizontal alignment of the content of a column.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    /// Content is placed against the left edge of the column.
+    #[default]
+    Left,
+
+    /// Content is placed against the right edge of the column, which is best
+    /// suited for numbers.
+    Right,
+
+    /// Content is placed in the middle of the column, with any odd column of
+    /// padding placed on the right.
+    Center,
+}
+
+/// Rows of text laid out in aligned columns.
+///
+/// Each column is as wide as its widest cell, measured in display columns so
+/// that styled and wide characters line up correctly.
+///
+/// ```
+/// use tutil::text::{Align, Table};
+///
+/// let mut table = Table::new();
+/// table.add_row(vec!["apples", "3"]);
+/// table.add_row(vec!["bananas", "12"]);
+/// table.set_alignment(1, Align::Right);
+///
+/// assert_eq!(table.to_string(), "apples    3\nbananas  12");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    rows: Vec<Vec<String>>,
+    alignments: Vec<Align>,
+    min_widths: Vec<usize>,
+}
+
+impl Table {
+    /// Creates a new, empty `Table`.
+    pub fn new() -> Table {
+        Table::default()
+    }
+
+    /// Appends a row of cells to the table.
+    ///
+    /// Rows do not all need to have the same number of cells; missing cells
+    /// are treated as empty.
+    pub fn add_row<I, S>(&mut self, row: I) -> &mut Table
+        where I: IntoIterator<Item = S>, S: Into<String>
+    {
+        self.rows.push(row.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets the alignment of the column at index `col`, which is
+    /// `Align::Left` by default.
+    pub fn set_alignment(&mut self, col: usize, align: Align) -> &mut Table {
+        if self.alignments.len() <= col {
+            self.alignments.resize(col + 1, Align::default());
+        }
+
+        self.alignments[col] = align;
+        self
+    }
+
+    /// Sets the minimum width of the column at index `col`, in display
+    /// columns.
+    ///
+    /// The column is padded to at least `width` even if all of its content is
+    /// narrower, but still grows to fit longer content. This keeps a table
+    /// that is redrawn with changing data from shifting about.
+    pub fn set_min_width(&mut self, col: usize, width: usize) -> &mut Table {
+        if self.min_widths.len() <= col {
+            self.min_widths.resize(col + 1, 0);
+        }
+
+        self.min_widths[col] = width;
+        self
+    }
+
+    /// Returns the width of each column, in display columns.
+    fn column_widths(&self) -> Vec<usize> {
+        let mut widths = self.min_widths.clone();
+
+        for row in &self.rows {
+            if widths.len() < row.len() {
+                widths.resize(row.len(), 0);
+            }
+
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(visible_width(cell));
+            }
+        }
+
+        widths
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let widths = self.column_widths();
+
+        for (i, row) in self.rows.iter().enumerate() {
+            if i > 0 {
+                try!(writeln!(f));
+            }
+
+            for (col, &width) in widths.iter().enumerate() {
+                if col > 0 {
+                    try!(write!(f, "{:1$}", "", COLUMN_GAP));
+                }
+
+                let cell = row.get(col).map_or("", |cell| &cell[..]);
+                let align = self.alignments.get(col).cloned().unwrap_or_default();
+
+                try!(write!(f, "{}", pad(cell, width, align)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crayon;
+    use crayon::Color::Red;
+
+    #[test]
+    fn left_aligned_by_default() {
+        let mut table = Table::new();
+        table.add_row(vec!["a", "bb"]);
+        table.add_row(vec!["ccc", "d"]);
+
+        assert_eq!(table.to_string(), "a    bb\nccc  d ");
+    }
+
+    #[test]
+    fn right_aligned_numbers() {
+        let mut table = Table::new();
+        table.add_row(vec!["Name", "Size"]);
+        table.add_row(vec!["tutil", "1024"]);
+        table.add_row(vec!["libc", "2"]);
+        table.set_alignment(1, Align::Right);
+
+        assert_eq!(table.to_string(), "Name   Size\ntutil  1024\nlibc      2");
+    }
+
+    #[test]
+    fn centered_column() {
+        let mut table = Table::new();
+        table.add_row(vec!["abcd"]);
+        table.add_row(vec!["a"]);
+        table.set_alignment(0, Align::Center);
+
+        assert_eq!(table.to_string(), "abcd\n a  ");
+    }
+
+    #[test]
+    fn styled_cells_measured_by_display_width() {
+        crayon::set_enabled(true);
+        let mut table = Table::new();
+        table.add_row(vec![Red.paint("ab").to_string(), "1".to_string()]);
+        table.add_row(vec!["abc".to_string(), "22".to_string()]);
+        table.set_alignment(1, Align::Right);
+
+        assert_eq!(table.to_string(), "\x1b[31mab\x1b[0m    1\nabc  22");
+    }
+
+    #[test]
+    fn min_width_pads_narrow_column() {
+        let mut table = Table::new();
+        table.add_row(vec!["a", "1"]);
+        table.add_row(vec!["b", "22"]);
+        table.set_min_width(1, 4).set_alignment(1, Align::Right);
+
+        assert_eq!(table.to_string(), "a     1\nb    22");
+    }
+
+    #[test]
+    fn min_width_does_not_truncate() {
+        let mut table = Table::new();
+        table.add_row(vec!["abcdef", "x"]);
+        table.set_min_width(0, 3);
+
+        assert_eq!(table.to_string(), "abcdef  x");
+    }
+}
@@ -0,0 +1,161 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Truncating text to a width, with an ellipsis marking what was removed.
+
+use unicode_width::UnicodeWidthChar;
+
+use ansi::{self, Segment};
+use crayon::visible_width;
+
+use super::truncate_line;
+
+/// Which part of the text [`truncate()`] removes.
+///
+/// [`truncate()`]: fn.truncate.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TruncateAlign {
+    /// The start is removed, so the ellipsis comes first.
+    Start,
+
+    /// The middle is removed, keeping the start and the end.
+    Middle,
+
+    /// The end is removed, so the ellipsis comes last.
+    #[default]
+    End,
+}
+
+/// Options for truncating text with [`truncate()`].
+///
+/// [`truncate()`]: fn.truncate.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruncateOptions {
+    /// The text put in place of what was removed, which defaults to `…`.
+    pub ellipsis: String,
+
+    /// Which part of the text is removed, which defaults to
+    /// `TruncateAlign::End`.
+    pub align: TruncateAlign,
+}
+
+impl Default for TruncateOptions {
+    fn default() -> TruncateOptions {
+        TruncateOptions { ellipsis: "…".to_string(), align: TruncateAlign::default() }
+    }
+}
+
+/// Truncates `s` to at most `width` display columns, replacing the removed
+/// part with an ellipsis.
+///
+/// Escape sequences are kept wherever they affect the text that remains, and
+/// a reset is written before the ellipsis if the text before it was styled,
+/// so the ellipsis itself is always plain. Text that already fits is returned
+/// unchanged. If even the ellipsis is wider than `width`, it is truncated
+/// instead.
+///
+/// ```
+/// use tutil::text::{self, TruncateAlign, TruncateOptions};
+///
+/// let options = TruncateOptions { align: TruncateAlign::Middle, ..Default::default() };
+/// assert_eq!(text::truncate("abcdefghij", 5, &options), "ab…ij");
+/// ```
+pub fn truncate(s: &str, width: usize, options: &TruncateOptions) -> String {
+    if visible_width(s) <= width {
+        return s.to_string();
+    }
+
+    let ellipsis = &options.ellipsis;
+    let available = match width.checked_sub(visible_width(ellipsis)) {
+        Some(available) => available,
+        None => return truncate_line(ellipsis, width).into_owned(),
+    };
+
+    match options.align {
+        TruncateAlign::Start => format!("{}{}", ellipsis, tail(s, available)),
+        TruncateAlign::Middle => {
+            let start = available - available / 2;
+            format!("{}{}{}", truncate_line(s, start), ellipsis, tail(s, available / 2))
+        }
+        TruncateAlign::End => format!("{}{}", truncate_line(s, available), ellipsis),
+    }
+}
+
+/// Returns the end of `s` that fits in `width` display columns, along with
+/// every escape sequence before it so that it keeps its style.
+fn tail(s: &str, width: usize) -> String {
+    let mut skip = visible_width(s).saturating_sub(width);
+    let mut output = String::with_capacity(s.len());
+
+    for segment in ansi::segments(s) {
+        match segment {
+            Segment::Escape(escape) => output.push_str(escape),
+            Segment::Text(text) => {
+                for c in text.chars() {
+                    if skip > 0 {
+                        skip = skip.saturating_sub(c.width().unwrap_or(0));
+                    } else {
+                        output.push(c);
+                    }
+                }
+            }
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crayon;
+    use crayon::Color::Red;
+
+    fn options(align: TruncateAlign) -> TruncateOptions {
+        TruncateOptions { align, ..Default::default() }
+    }
+
+    #[test]
+    fn truncate_each_alignment() {
+        let input = "the quick brown fox";
+
+        assert_eq!(truncate(input, 10, &options(TruncateAlign::End)), "the quick…");
+        assert_eq!(truncate(input, 10, &options(TruncateAlign::Start)), "…brown fox");
+        assert_eq!(truncate(input, 10, &options(TruncateAlign::Middle)), "the q… fox");
+    }
+
+    #[test]
+    fn truncate_with_custom_ellipsis() {
+        let options = TruncateOptions { ellipsis: "...".to_string(), align: TruncateAlign::End };
+
+        assert_eq!(truncate("the quick brown fox", 10, &options), "the qui...");
+        assert_eq!(truncate("the quick brown fox", 2, &options), "..");
+    }
+
+    #[test]
+    fn truncate_text_that_fits() {
+        for &align in &[TruncateAlign::Start, TruncateAlign::Middle, TruncateAlign::End] {
+            assert_eq!(truncate("short", 5, &options(align)), "short");
+        }
+    }
+
+    #[test]
+    fn truncate_styled_text() {
+        crayon::set_enabled(true);
+        let input = format!("{}{}", Red.paint("abcdef"), "ghij");
+
+        assert_eq!(truncate(&input, 5, &options(TruncateAlign::End)),
+                   "\x1b[31mabcd\x1b[0m…");
+        assert_eq!(truncate(&input, 5, &options(TruncateAlign::Start)),
+                   "…\x1b[31m\x1b[0mghij");
+        assert_eq!(truncate(&input, 5, &options(TruncateAlign::Middle)),
+                   "\x1b[31mab\x1b[0m…\x1b[31m\x1b[0mij");
+    }
+
+    #[test]
+    fn truncate_wide_characters() {
+        assert_eq!(truncate("日本語テキスト", 6, &options(TruncateAlign::Start)), "…スト");
+        assert_eq!(truncate("日本語テキスト", 6, &options(TruncateAlign::End)), "日本…");
+    }
+}
@@ -0,0 +1,330 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Wrapping text onto several lines at word boundaries.
+
+use unicode_width::UnicodeWidthChar;
+
+use ansi::{self, ESC};
+use crayon::visible_width;
+
+/// Wraps `input` onto lines of at most `width` display columns, breaking at
+/// spaces.
+///
+/// Existing line breaks are kept, and runs of whitespace between words are
+/// collapsed into a single space. Words that are wider than `width` on their
+/// own are broken wherever they reach it; use [`wrap_hyphenated()`] to mark
+/// those breaks with a hyphen. Escape sequences are kept with the text that
+/// follows them and take up no columns.
+///
+/// ```
+/// use tutil::text;
+///
+/// assert_eq!(text::wrap("the quick brown fox", 10), "the quick\nbrown fox");
+/// ```
+///
+/// [`wrap_hyphenated()`]: fn.wrap_hyphenated.html
+pub fn wrap(input: &str, width: usize) -> String {
+    wrap_lines(input, width).join("\n")
+}
+
+/// Wraps `input` in the same way as [`wrap()`], except that a `-` is added
+/// wherever a word that is too wide for a line has to be broken.
+///
+/// Words that fit on a line are never broken. A word that already contains
+/// hyphens is broken after one of them where possible, rather than gaining
+/// another.
+///
+/// ```
+/// use tutil::text;
+///
+/// assert_eq!(text::wrap_hyphenated("a supercalifragilistic word", 10),
+///            "a\nsupercali-\nfragilist-\nic word");
+/// ```
+///
+/// [`wrap()`]: fn.wrap.html
+pub fn wrap_hyphenated(input: &str, width: usize) -> String {
+    wrap_lines_with(input, width, true).join("\n")
+}
+
+/// Returns the number of lines that `input` takes up once it is wrapped to
+/// `width` columns with [`wrap()`], or 0 if it is empty.
+///
+/// ```
+/// use tutil::text;
+///
+/// assert_eq!(text::rows_for("the quick brown fox", 10), 2);
+/// ```
+///
+/// [`wrap()`]: fn.wrap.html
+pub fn rows_for(input: &str, width: usize) -> usize {
+    if input.is_empty() {
+        0
+    } else {
+        wrap_lines(input, width).len()
+    }
+}
+
+/// Returns true if `input` takes up no more than `max_lines` lines once it is
+/// wrapped to `width` columns with [`wrap()`], such as for deciding whether
+/// to show text in full or to summarise it.
+///
+/// ```
+/// use tutil::text;
+///
+/// let description = "A library for writing command line programs.";
+/// assert!(text::fits_in(description, 20, 3));
+/// assert!(!text::fits_in(description, 20, 2));
+/// ```
+///
+/// [`wrap()`]: fn.wrap.html
+pub fn fits_in(input: &str, width: usize, max_lines: usize) -> bool {
+    rows_for(input, width) <= max_lines
+}
+
+/// Wraps `text` to `width_per_col` columns with [`wrap()`] and shares the
+/// lines out between `columns` columns, as in a newspaper, returning the
+/// lines of each column joined with newlines.
+///
+/// Every column gets the same number of lines, except that the earlier
+/// columns get one more when the lines do not divide evenly. The lines are
+/// not padded, so pad them to `width_per_col` when placing the columns side
+/// by side. There is always one string for each column, even if it is empty.
+///
+/// ```
+/// use tutil::text;
+///
+/// let columns = text::balance_columns("the quick brown fox jumps over the lazy dog", 2, 9);
+/// assert_eq!(columns, ["the quick\nbrown fox\njumps", "over the\nlazy dog"]);
+/// ```
+///
+/// [`wrap()`]: fn.wrap.html
+pub fn balance_columns(text: &str, columns: usize, width_per_col: usize) -> Vec<String> {
+    let mut lines = wrap_lines(text, width_per_col).into_iter();
+    let total = if text.is_empty() { 0 } else { lines.len() };
+    let mut result = Vec::with_capacity(columns);
+
+    for i in 0..columns {
+        let length = total / columns + if i < total % columns { 1 } else { 0 };
+        result.push(lines.by_ref().take(length).collect::<Vec<_>>().join("\n"));
+    }
+
+    result
+}
+
+/// Wraps `input` into lines as described by `wrap()`.
+pub(crate) fn wrap_lines(input: &str, width: usize) -> Vec<String> {
+    wrap_lines_with(input, width, false)
+}
+
+/// The implementation of `wrap_lines()`, hyphenating broken words if
+/// `hyphenate` is true.
+fn wrap_lines_with(input: &str, width: usize, hyphenate: bool) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in input.split('\n') {
+        let mut line = String::new();
+        let mut line_width = 0;
+
+        for word in paragraph.split_whitespace() {
+            let word_width = visible_width(word);
+
+            if line_width > 0 && line_width + 1 + word_width > width {
+                lines.push(line);
+                line = String::new();
+                line_width = 0;
+            }
+
+            if line_width > 0 {
+                line.push(' ');
+                line_width += 1;
+            }
+
+            if word_width <= width {
+                line.push_str(word);
+                line_width += word_width;
+                continue;
+            }
+
+            let mut pieces = if hyphenate && width > 1 {
+                hyphenate_word(word, width)
+            } else {
+                split_word(word, width).into_iter().map(|piece| piece.to_string()).collect()
+            };
+
+            let last = pieces.pop().unwrap_or_default();
+            lines.extend(pieces);
+
+            line_width = visible_width(&last);
+            line = last;
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Breaks `word` into pieces of at most `width` display columns, ending every
+/// piece but the last with a hyphen.
+///
+/// A piece that can end just after a hyphen already in the word does so,
+/// instead of having one added. `width` must be at least 2.
+fn hyphenate_word(word: &str, width: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut rest = word;
+
+    while visible_width(rest) > width {
+        let longest = split_word(rest, width)[0];
+
+        let (end, hyphen) = match longest.rfind('-') {
+            Some(i) if i > 0 => (i + 1, ""),
+            _ => (split_word(rest, width - 1)[0].len(), "-"),
+        };
+
+        pieces.push(format!("{}{}", &rest[..end], hyphen));
+        rest = &rest[end..];
+    }
+
+    pieces.push(rest.to_string());
+    pieces
+}
+
+/// Breaks `word` into pieces of at most `width` display columns, except that
+/// a single character wider than `width` gets a piece of its own.
+fn split_word(word: &str, width: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut column = 0;
+    let mut i = 0;
+
+    while i < word.len() {
+        let rest = &word[i..];
+
+        if rest.starts_with(ESC) {
+            i += ansi::escape_len(rest);
+            continue;
+        }
+
+        let c = rest.chars().next().unwrap_or(' ');
+        let char_width = c.width().unwrap_or(0);
+
+        if column > 0 && column + char_width > width {
+            pieces.push(&word[start..i]);
+            start = i;
+            column = 0;
+        }
+
+        column += char_width;
+        i += c.len_utf8();
+    }
+
+    pieces.push(&word[start..]);
+    pieces
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crayon;
+    use crayon::Color::Red;
+    use std::slice;
+
+    #[test]
+    fn wrap_at_spaces() {
+        assert_eq!(wrap_lines("the quick brown fox", 10), ["the quick", "brown fox"]);
+        assert_eq!(wrap_lines("the quick  brown\tfox", 9), ["the quick", "brown fox"]);
+    }
+
+    #[test]
+    fn wrap_keeps_line_breaks() {
+        assert_eq!(wrap_lines("one\n\ntwo three", 5), ["one", "", "two", "three"]);
+    }
+
+    #[test]
+    fn wrap_breaks_long_words() {
+        assert_eq!(wrap_lines("a abcdefghij b", 4), ["a", "abcd", "efgh", "ij b"]);
+        assert_eq!(wrap_lines("日本語", 3), ["日", "本", "語"]);
+    }
+
+    #[test]
+    fn hyphenate_long_word() {
+        assert_eq!(wrap_hyphenated("a abcdefghij b", 5), "a\nabcd-\nefgh-\nij b");
+        assert_eq!(wrap_hyphenated("abcdefgh", 4), "abc-\ndef-\ngh");
+    }
+
+    #[test]
+    fn hyphenate_leaves_fitting_words() {
+        assert_eq!(wrap_hyphenated("hello world", 5), "hello\nworld");
+        assert_eq!(wrap_hyphenated("hello world", 5), wrap("hello world", 5));
+    }
+
+    #[test]
+    fn hyphenate_at_existing_hyphens() {
+        assert_eq!(wrap_hyphenated("well-known-fact", 8), "well-\nknown-\nfact");
+        assert_eq!(wrap_hyphenated("-abcdefgh", 5), "-abc-\ndefgh");
+    }
+
+    #[test]
+    fn hyphenate_at_width_of_one() {
+        assert_eq!(wrap_hyphenated("abc", 1), "a\nb\nc");
+    }
+
+    #[test]
+    fn wrap_ignores_escapes() {
+        crayon::set_enabled(true);
+        let styled = format!("{} world", Red.paint("hello"));
+        assert_eq!(wrap_lines(&styled, 11), slice::from_ref(&styled));
+        assert_eq!(wrap_lines(&styled, 10), [Red.paint("hello").to_string(), "world".to_string()]);
+    }
+
+    #[test]
+    fn rows_for_wrapped_text() {
+        assert_eq!(rows_for("one\n\ntwo three", 5), 4);
+        assert_eq!(rows_for("", 5), 0);
+    }
+
+    #[test]
+    fn text_that_fits() {
+        assert!(fits_in("the quick brown fox", 20, 1));
+        assert!(fits_in("", 20, 0));
+    }
+
+    #[test]
+    fn text_exactly_at_limit() {
+        assert!(fits_in("the quick brown fox", 10, 2));
+        assert!(fits_in("a abcdefghij b", 4, 4));
+    }
+
+    #[test]
+    fn text_that_overflows() {
+        assert!(!fits_in("the quick brown fox", 8, 3));
+        assert!(!fits_in("one\ntwo", 80, 1));
+    }
+
+    #[test]
+    fn balance_front_loads_remainder() {
+        let text = "one two three four five six seven";
+        let columns = balance_columns(text, 3, 5);
+
+        assert_eq!(columns.len(), 3);
+        assert_eq!(columns, ["one\ntwo\nthree", "four\nfive", "six\nseven"]);
+    }
+
+    #[test]
+    fn balance_evenly() {
+        let columns = balance_columns("a b c d e f", 3, 1);
+        let counts: Vec<_> = columns.iter().map(|column| column.lines().count()).collect();
+
+        assert_eq!(counts, [2, 2, 2]);
+    }
+
+    #[test]
+    fn balance_with_more_columns_than_lines() {
+        assert_eq!(balance_columns("one two", 4, 3), ["one", "two", "", ""]);
+        assert_eq!(balance_columns("", 2, 10), ["", ""]);
+        assert!(balance_columns("one", 0, 10).is_empty());
+    }
+}
@@ -0,0 +1,41 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Tests for `crayon::set_enabled()`, which turns colour on or off for the
+//! whole process and so is kept apart from the unit tests.
+
+extern crate tutil;
+
+use tutil::crayon::{self, Color};
+use tutil::crayon::Color::{Black, Red};
+use tutil::system::Stream;
+
+#[test]
+fn setting_color_for_the_process() {
+    let styled = Red.on(Black).bold().paint("hi");
+
+    // Start from colour turned on rather than whatever the environment says.
+    crayon::set_enabled(true);
+    assert!(styled.to_string().contains('\x1b'));
+
+    crayon::set_enabled(false);
+    assert!(!crayon::enabled());
+    assert!(!crayon::colors_enabled());
+    assert_eq!(styled.to_string(), "hi");
+    assert!(!Color::Rgb(255, 136, 0).paint("orange").to_string().contains('\x1b'));
+
+    // Unlike `without_color()`, the setting applies to every thread.
+    let output = std::thread::spawn(|| Red.paint("TEST").to_string()).join().unwrap();
+    assert_eq!(output, "TEST");
+
+    crayon::set_enabled(true);
+    assert!(crayon::enabled());
+    assert_eq!(styled.to_string(), "\x1b[1;31;40mhi\x1b[0m");
+
+    // Colour turned on explicitly wins over everything but `without_color()`.
+    assert_eq!(crayon::without_color(|| Red.paint("hi").to_string()), "hi");
+    assert!(!crayon::without_color(|| crayon::colors_enabled_for(Stream::Stdout)));
+    assert!(crayon::colors_enabled_for(Stream::Stdout));
+    assert!(crayon::colors_enabled_for(Stream::Stderr));
+}
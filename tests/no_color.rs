@@ -0,0 +1,40 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Tests for the `NO_COLOR` environment variable, which is only read once per
+//! process and so is kept apart from the unit tests.
+
+extern crate tutil;
+
+use std::env;
+
+use tutil::crayon;
+use tutil::crayon::Color::Red;
+use tutil::system::Stream;
+
+#[test]
+fn no_color_disables_styling() {
+    env::remove_var("FORCE_COLOR");
+    env::set_var("NO_COLOR", "1");
+
+    assert!(!crayon::enabled());
+    assert!(!crayon::colors_enabled_for(Stream::Stdout));
+    assert_eq!(Red.bold().paint("TEST").to_string(), "TEST");
+
+    // Streams read the environment each time, so `FORCE_COLOR` overrides
+    // `NO_COLOR` for them straight away.
+    env::set_var("FORCE_COLOR", "1");
+    assert!(crayon::colors_enabled_for(Stream::Stdout));
+
+    // The variables are only read once for painting.
+    env::remove_var("FORCE_COLOR");
+    env::remove_var("NO_COLOR");
+    assert_eq!(Red.paint("TEST").to_string(), "TEST");
+
+    // Colour turned on explicitly overrides `NO_COLOR` everywhere.
+    env::set_var("NO_COLOR", "1");
+    crayon::set_enabled(true);
+    assert!(crayon::colors_enabled_for(Stream::Stdout));
+    assert_eq!(Red.paint("TEST").to_string(), "\x1b[31mTEST\x1b[0m");
+}
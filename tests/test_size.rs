@@ -0,0 +1,27 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Tests for `screen::set_test_size()`, which changes the size for the whole
+//! process and so is kept apart from the unit tests.
+
+#![cfg(feature = "test-util")]
+
+extern crate tutil;
+
+use tutil::screen::{self, Height, Size, Width};
+
+#[test]
+fn fake_size_set_and_cleared() {
+    let real = screen::size();
+    let fake = Size(Width(123), Height(45));
+
+    screen::set_test_size(Some(fake));
+    assert_eq!(screen::size(), Some(fake));
+    assert_eq!(screen::width(), Some(Width(123)));
+    assert_eq!(screen::height(), Some(Height(45)));
+    assert_eq!(screen::size_or_default(), fake);
+
+    screen::set_test_size(None);
+    assert_eq!(screen::size(), real);
+}